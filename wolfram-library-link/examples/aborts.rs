@@ -1,13 +1,45 @@
-use wolfram_library_link as wll;
+use wolfram_library_link::{self as wll, KernelThread};
 
 /// This function will execute forever until a Wolfram Language abort occurs.
 #[wll::export]
-fn wait_for_abort() {
+fn wait_for_abort(kernel: &KernelThread) {
     loop {
-        if wll::aborted() {
+        if kernel.aborted() {
             return;
         }
 
         std::thread::yield_now();
     }
 }
+
+/// This function will execute forever until a Wolfram Language abort occurs, using
+/// [`check_abort()`][KernelThread::check_abort] to return early.
+#[wll::export(abortable)]
+fn check_for_abort(kernel: &KernelThread) -> Result<(), wll::AbortError> {
+    loop {
+        kernel.check_abort()?;
+
+        std::thread::yield_now();
+    }
+}
+
+/// This function will wait for a background thread that executes forever until a
+/// Wolfram Language abort occurs.
+#[wll::export(abortable)]
+fn wait_for_abort_in_background() -> Result<(), wll::AbortError> {
+    let watcher = wll::AbortWatcher::new();
+
+    let handle = std::thread::spawn({
+        let watcher = watcher.clone();
+
+        move || loop {
+            if watcher.is_aborted() {
+                return;
+            }
+
+            std::thread::yield_now();
+        }
+    });
+
+    watcher.join(handle)
+}
@@ -74,8 +74,10 @@ fn file_watch_thread_function(
         Some(since_epoch)
     };
 
+    let token = task.cancellation_token();
+
     loop {
-        if !task.is_alive() {
+        if token.is_cancelled() {
             break;
         }
 
@@ -88,7 +90,8 @@ fn file_watch_thread_function(
             task.raise_async_event("change", data);
         }
 
-        // Wait for a bit before polling again for any changes to the file.
-        std::thread::sleep(Duration::from_millis(pause_interval_ms));
+        // Wait for a bit before polling again for any changes to the file. This returns
+        // early if the task is removed in the meantime.
+        token.sleep_or_cancelled(Duration::from_millis(pause_interval_ms));
     }
 }
@@ -0,0 +1,39 @@
+use wolfram_library_link::{
+    self as wll,
+    expr::{Expr, Symbol},
+    AsyncTaskObject, DataStore, KernelThread, MainThreadExecutor,
+};
+
+// Export the function used by the Wolfram Language event handler to run the jobs
+// submitted to a `MainThreadExecutor`.
+wll::export_main_thread_executor!();
+
+/// Start an asynchronous task whose background thread evaluates `x + y` on the main
+/// Kernel thread, and raises the result as a `"result"` event.
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_adder(x: i64, y: i64) -> i64 {
+    let task = AsyncTaskObject::spawn_with_thread(move |task: AsyncTaskObject| {
+        let executor = MainThreadExecutor::new(&task);
+
+        // Plus[x, y]
+        let expr = Expr::normal(Symbol::new("System`Plus"), vec![
+            Expr::from(x),
+            Expr::from(y),
+        ]);
+
+        // Evaluate the expression on the main thread, and wait for the result.
+        let sum: Option<i64> = executor
+            .run(move |kernel: &KernelThread| kernel.evaluate_as(&expr).ok())
+            .flatten();
+
+        if let Some(sum) = sum {
+            let mut data = DataStore::new();
+            data.add_i64(sum);
+            task.raise_async_event("result", data);
+        }
+    });
+
+    task.id()
+}
@@ -1,20 +1,13 @@
-use wolfram_library_link::{
-    self as wll, export,
-    expr::{Expr, Symbol},
-};
+use wolfram_library_link::{self as wll, export, expr::Expr, KernelThread};
 
 #[export(wstp)]
 fn generate_message(_: Vec<Expr>) {
-    // Construct the expression `Message[MySymbol::msg, "..."]`.
-    let message = Expr::normal(Symbol::new("System`Message"), vec![
-        // MySymbol::msg is MessageName[MySymbol, "msg"]
-        Expr::normal(Symbol::new("System`MessageName"), vec![
-            Expr::from(Symbol::new("Global`MySymbol")),
-            Expr::string("msg"),
-        ]),
-        Expr::string("a Rust LibraryLink function"),
-    ]);
+    let kernel = KernelThread::current().expect("not called from the Kernel");
 
-    // Evaluate the message expression.
-    let _: Expr = wll::evaluate(&message);
+    // Issue the message `MySymbol::msg`, with a single string argument.
+    wll::message!(
+        &kernel,
+        "Global`MySymbol::msg",
+        "a Rust LibraryLink function"
+    );
 }
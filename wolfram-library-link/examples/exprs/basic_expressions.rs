@@ -1,6 +1,7 @@
 use wolfram_library_link::{
     self as wll,
     expr::{Expr, Symbol},
+    KernelThread,
 };
 
 /// This function is loaded by evaluating:
@@ -15,11 +16,13 @@ use wolfram_library_link::{
 /// ```
 #[wll::export(wstp)]
 pub fn echo_arguments(args: Vec<Expr>) -> Expr {
+    let kernel = KernelThread::current().expect("not called from the Kernel");
+
     let arg_count = args.len();
 
     for arg in args {
         // Echo[<arg>]
-        wll::evaluate(&Expr::normal(Symbol::new("System`Echo"), vec![arg]));
+        kernel.evaluate(&Expr::normal(Symbol::new("System`Echo"), vec![arg]));
     }
 
     Expr::string(format!("finished echoing {} argument(s)", arg_count))
@@ -1,19 +1,14 @@
-use std::{collections::HashMap, sync::Mutex};
-
-use once_cell::sync::Lazy;
-
 use wolfram_library_link::{
     self as wll,
     expr::{Expr, ExprKind, Symbol},
-    managed::{Id, ManagedExpressionEvent},
+    managed::ManagedStore,
 };
 
 wll::generate_loader![load_managed_exprs_functions];
 
 /// Storage for all instances of [`MyObject`] associated with managed expressions
 /// created using `CreateManagedLibraryExpression`.
-static INSTANCES: Lazy<Mutex<HashMap<Id, MyObject>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+static INSTANCES: ManagedStore<MyObject> = ManagedStore::new();
 
 #[derive(Clone)]
 struct MyObject {
@@ -22,28 +17,15 @@ struct MyObject {
 
 #[wll::init]
 fn init() {
-    // Register `manage_instance()` as the handler for managed expressions created using:
+    // Register `INSTANCES` as the handler for managed expressions created using:
     //
     //     CreateManagedLibraryExpression["my_object", _]
-    wll::managed::register_library_expression_manager("my_object", manage_instance);
-}
-
-fn manage_instance(action: ManagedExpressionEvent) {
-    let mut instances = INSTANCES.lock().unwrap();
-
-    match action {
-        ManagedExpressionEvent::Create(id) => {
-            // Insert a new MyObject instance with some default values.
-            instances.insert(id, MyObject {
-                value: String::from("default"),
-            });
-        },
-        ManagedExpressionEvent::Drop(id) => {
-            if let Some(obj) = instances.remove(&id) {
-                drop(obj);
-            }
-        },
-    }
+    //
+    // A new MyObject instance with some default values is inserted each time a managed
+    // expression is created, and removed when the managed expression is deallocated.
+    INSTANCES.register("my_object", |_id| MyObject {
+        value: String::from("default"),
+    });
 }
 
 /// Set the `MyObject.value` field for the specified instance ID.
@@ -57,7 +39,7 @@ fn set_instance_value(args: Vec<Expr>) {
         _ => panic!("expected 2nd argument to be a String, got: {}", args[1]),
     };
 
-    let mut instances = INSTANCES.lock().unwrap();
+    let mut instances = INSTANCES.lock();
 
     let instance: &mut MyObject =
         instances.get_mut(&id).expect("instance does not exist");
@@ -73,7 +55,7 @@ fn get_instance_data(args: Vec<Expr>) -> Expr {
     let id: u32 = unwrap_id_arg(&args[0]);
 
     let MyObject { value } = {
-        let instances = INSTANCES.lock().unwrap();
+        let instances = INSTANCES.lock();
 
         instances
             .get(&id)
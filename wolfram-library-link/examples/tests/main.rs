@@ -2,7 +2,12 @@ mod test_native_args;
 mod test_share_counts;
 mod test_threading;
 
+mod test_callbacks;
 mod test_data_store;
+mod test_evaluate;
 mod test_images;
+mod test_messages;
 mod test_numeric_array_conversions;
+mod test_sparse_arrays;
+mod test_tensors;
 mod test_wstp;
@@ -0,0 +1,52 @@
+use std::sync::{Mutex, Once};
+
+use wolfram_library_link::{
+    callback::{self, CallbackConnection, CallbackFunction},
+    export,
+};
+
+type Objective = CallbackFunction<fn(f64) -> f64>;
+
+static OBJECTIVE: Mutex<Option<Objective>> = Mutex::new(None);
+
+fn connect_objective(connection: CallbackConnection) -> bool {
+    match CallbackFunction::new(connection) {
+        Ok(function) => {
+            *OBJECTIVE.lock().unwrap() = Some(function);
+            true
+        },
+        Err(_) => false,
+    }
+}
+
+#[export]
+fn test_callback_register_manager() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| {
+        callback::register_library_callback_manager(
+            "test_callback_objective",
+            connect_objective,
+        )
+    });
+}
+
+/// Return the minimum value of the connected objective function at the integer points
+/// in `[start, end]`.
+#[export]
+fn test_callback_minimize(start: i64, end: i64) -> f64 {
+    let objective = OBJECTIVE.lock().unwrap();
+    let objective = objective
+        .as_ref()
+        .expect("no objective function is connected");
+
+    (start..=end)
+        .map(|x| objective.call(x as f64).expect("objective function failed"))
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[export]
+fn test_callback_release() {
+    // Dropping the CallbackFunction releases the connected function.
+    drop(OBJECTIVE.lock().unwrap().take());
+}
@@ -0,0 +1,125 @@
+use wolfram_library_link::{
+    self as wll,
+    expr::{Expr, Symbol},
+    KernelThread, Tensor, ToExpr,
+};
+
+/// Evaluate `Print["hello"]; 1/0`, and return an association describing the
+/// `EvaluationResult`.
+#[wll::export(wstp)]
+fn test_evaluate_detailed(_args: Vec<Expr>) -> Expr {
+    let kernel = KernelThread::current().unwrap();
+
+    // Print["hello"]; 1/0
+    let expr = Expr::normal(Symbol::new("System`CompoundExpression"), vec![
+        Expr::normal(Symbol::new("System`Print"), vec![Expr::string("hello")]),
+        Expr::normal(Symbol::new("System`Power"), vec![
+            Expr::from(0),
+            Expr::from(-1),
+        ]),
+    ]);
+
+    let result = kernel.evaluate_detailed(&expr).unwrap();
+
+    let messages = result
+        .messages
+        .iter()
+        .map(|message| {
+            Expr::string(format!(
+                "{}::{}",
+                message.symbol.symbol_name().as_str(),
+                message.tag
+            ))
+        })
+        .collect();
+
+    let printed = result.printed.iter().map(Expr::string).collect();
+
+    let rule = |key: &str, value: Expr| {
+        Expr::normal(Symbol::new("System`Rule"), vec![Expr::string(key), value])
+    };
+
+    Expr::normal(Symbol::new("System`Association"), vec![
+        rule("value", result.value),
+        rule(
+            "messages",
+            Expr::normal(Symbol::new("System`List"), messages),
+        ),
+        rule("printed", Expr::normal(Symbol::new("System`List"), printed)),
+        rule(
+            "aborted",
+            Expr::from(Symbol::new(if result.aborted {
+                "System`True"
+            } else {
+                "System`False"
+            })),
+        ),
+    ])
+}
+
+/// Evaluate `$Aborted`, which is not an aborted evaluation, and return the value and
+/// whether the evaluation was reported as aborted.
+#[wll::export(wstp)]
+fn test_evaluate_aborted_value(_args: Vec<Expr>) -> Expr {
+    let kernel = KernelThread::current().unwrap();
+
+    let result = kernel
+        .evaluate_detailed(&Expr::from(Symbol::new("System`$Aborted")))
+        .unwrap();
+
+    Expr::normal(Symbol::new("System`List"), vec![
+        result.value,
+        Expr::from(Symbol::new(if result.aborted {
+            "System`True"
+        } else {
+            "System`False"
+        })),
+    ])
+}
+
+/// Evaluate `$Version` and `1 + 2` as Rust types, and return the error message from
+/// evaluating an integer as a string.
+///
+/// Also evaluate `2^10` and `Range[3]^2` using the `evaluateExpression()` runtime
+/// function, and return whether evaluating a string as an integer failed.
+#[wll::export(wstp)]
+fn test_evaluate_as(_args: Vec<Expr>) -> Expr {
+    let kernel = KernelThread::current().unwrap();
+
+    let version: String = kernel.evaluate_str_as("$Version").unwrap();
+
+    // Plus[1, 2]
+    let sum: i64 = kernel
+        .evaluate_as(&Expr::normal(Symbol::new("System`Plus"), vec![
+            Expr::from(1),
+            Expr::from(2),
+        ]))
+        .unwrap();
+
+    let error = match kernel.evaluate_str_as::<String>("1 + 2") {
+        Err(error @ wll::EvalError::Conversion { .. }) => error.to_string(),
+        other => panic!("expected conversion error, got: {:?}", other),
+    };
+
+    let power: i64 = kernel.evaluate_str_as("2^10").unwrap();
+
+    let squares: Tensor<i64> = kernel.evaluate_str_as_tensor("Range[3]^2", 1).unwrap();
+
+    let runtime_error = matches!(
+        kernel.evaluate_str_as::<i64>("StringJoin[\"a\", \"b\"]"),
+        Err(wll::EvalError::Runtime(_))
+    );
+
+    Expr::normal(Symbol::new("System`List"), vec![
+        Expr::string(version),
+        Expr::from(sum),
+        Expr::string(error),
+        Expr::from(power),
+        squares.as_slice().to_expr(),
+        Expr::from(Symbol::new(if runtime_error {
+            "System`True"
+        } else {
+            "System`False"
+        })),
+    ])
+}
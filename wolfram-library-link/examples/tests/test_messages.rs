@@ -0,0 +1,37 @@
+use wolfram_library_link::{self as wll, expr::Expr, KernelThread, MessageName, ToExpr};
+
+static NOT_POSITIVE: MessageName = MessageName::new("RustLinkTests`Messages::notpos")
+    .with_template("Argument `1` is not a positive integer.");
+
+/// Issue `RustLinkTests`Messages::notpos` if `x` is not positive, using the template
+/// defined by `NOT_POSITIVE`.
+#[wll::export]
+fn test_message_name_issue(kernel: &KernelThread, x: i64) -> i64 {
+    if x <= 0 {
+        NOT_POSITIVE.issue(kernel, &[x.to_expr()]);
+        return 0;
+    }
+
+    x
+}
+
+/// Issue `RustLinkTests`Messages::args` with each of the arguments.
+#[wll::export(wstp)]
+fn test_message_macro(args: Vec<Expr>) {
+    let kernel = KernelThread::current().unwrap();
+
+    wll::message!(&kernel, "RustLinkTests`Messages::args", args.len(), args);
+}
+
+/// Try to issue a message from a thread other than the main Kernel thread, which is not
+/// possible, so no message is issued.
+#[wll::export]
+fn test_message_from_background_thread() -> bool {
+    std::thread::spawn(|| {
+        MessageName::new("RustLinkTests`Messages::args")
+            .try_issue(&[0.to_expr()])
+            .is_ok()
+    })
+    .join()
+    .unwrap()
+}
@@ -2,6 +2,7 @@ use std::ffi::CString;
 
 use wolfram_library_link::{
     self as wll,
+    expr::{Expr, Symbol},
     sys::{mint, mreal},
     NumericArray, UninitNumericArray,
 };
@@ -99,6 +100,26 @@ fn test_panic() {
     panic!("this function panicked");
 }
 
+#[wll::export(wstp)]
+fn test_last_panic(args: Vec<Expr>) -> Expr {
+    assert!(args.is_empty());
+
+    wll::last_panic().unwrap_or_else(|| Expr::symbol(Symbol::new("System`None")))
+}
+
+//-------
+// Errors
+//-------
+
+#[wll::export]
+fn test_result(x: i64) -> Result<i64, String> {
+    if x < 0 {
+        return Err(format!("expected non-negative integer, got {}", x));
+    }
+
+    Ok(x * 2)
+}
+
 //======================================
 // NumericArray's
 //======================================
@@ -1,4 +1,4 @@
-use wolfram_library_link::{self as wll, DataStore, NumericArray};
+use wolfram_library_link::{self as wll, DataStore, Manual, NumericArray, Shared};
 
 
 #[wll::export]
@@ -91,3 +91,38 @@ fn test_na_shared_clone(array: NumericArray<i64>) -> bool {
 
     true
 }
+
+//-----------------------------------
+// Test Shared<_> and Manual<_> args
+//-----------------------------------
+
+#[wll::export]
+fn test_na_shared_wrapper_count(array: Shared<NumericArray<i64>>) -> i64 {
+    array.share_count() as i64
+}
+
+#[wll::export]
+fn test_na_shared_wrapper_mutate(mut array: Shared<NumericArray<i64>>) {
+    let data = array
+        .as_slice_mut()
+        .expect("expected uniquely shared NumericArray");
+
+    for elem in data {
+        *elem *= 2;
+    }
+}
+
+#[wll::export]
+fn test_na_manual_wrapper_mutate(
+    mut array: Manual<NumericArray<i64>>,
+) -> Manual<NumericArray<i64>> {
+    let data = array
+        .as_slice_mut()
+        .expect("expected unshared NumericArray");
+
+    for elem in data {
+        *elem += 1;
+    }
+
+    array
+}
@@ -0,0 +1,37 @@
+use wolfram_library_link::{self as wll, SparseArray, Tensor};
+
+
+#[wll::export]
+fn test_sparse_explicit_values(array: &SparseArray<f64>) -> Tensor<f64> {
+    array.explicit_values().expect("expected explicit values").clone()
+}
+
+#[wll::export]
+fn test_sparse_row_pointers(array: &SparseArray<f64>) -> Tensor<i64> {
+    array.row_pointers().expect("expected row pointers").clone()
+}
+
+#[wll::export]
+fn test_sparse_column_indices(array: &SparseArray<f64>) -> Tensor<i64> {
+    array.column_indices().expect("expected column indices").clone()
+}
+
+#[wll::export]
+fn test_sparse_implicit_value(array: &SparseArray<f64>) -> f64 {
+    array.implicit_value().expect("expected implicit value")
+}
+
+#[wll::export]
+fn test_sparse_from_triplets() -> SparseArray<i64> {
+    SparseArray::from_triplets([3, 3], &[(0, 0, 1), (1, 2, 2), (2, 1, 3)]).unwrap()
+}
+
+#[wll::export]
+fn test_sparse_from_tensor(tensor: &Tensor<i64>) -> SparseArray<i64> {
+    SparseArray::from_tensor(tensor, None).unwrap()
+}
+
+#[wll::export]
+fn test_sparse_to_tensor(array: &SparseArray<i64>) -> Tensor<i64> {
+    array.to_tensor().unwrap()
+}
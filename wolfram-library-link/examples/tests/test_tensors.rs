@@ -0,0 +1,28 @@
+use wolfram_library_link::{self as wll, Tensor};
+
+
+#[wll::export]
+fn test_tensor_total_real(tensor: &Tensor<f64>) -> f64 {
+    tensor.as_slice().iter().sum()
+}
+
+#[wll::export]
+fn test_tensor_dimensions(tensor: &Tensor<i64>) -> Tensor<i64> {
+    let dims: Vec<i64> = tensor.dimensions().iter().map(|&dim| dim as i64).collect();
+
+    Tensor::from_slice(&dims)
+}
+
+#[wll::export]
+fn test_tensor_create_matrix() -> Tensor<f64> {
+    Tensor::from_array(&[2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+}
+
+#[wll::export]
+fn test_tensor_clone_is_unshared(tensor: Tensor<i64>) -> bool {
+    let clone = tensor.clone();
+
+    !clone.ptr_eq(&tensor)
+        && clone.share_count() == 0
+        && clone.as_slice() == tensor.as_slice()
+}
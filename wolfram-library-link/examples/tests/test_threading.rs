@@ -2,17 +2,56 @@ use std::panic;
 
 use wolfram_library_link::{
     self as wll,
-    expr::{Expr, Symbol},
+    expr::{Expr, ExprKind, Symbol},
+    KernelThread,
 };
 
 #[wll::export]
-fn test_runtime_function_from_main_thread() -> bool {
+fn test_runtime_function_from_main_thread(kernel: &KernelThread) -> bool {
     let expr = Expr::normal(Symbol::new("System`Plus"), vec![
         Expr::from(2),
         Expr::from(2),
     ]);
 
-    wll::evaluate(&expr) == Expr::from(4)
+    kernel.evaluate(&expr) == Expr::from(4)
+}
+
+/// Evaluate `` Global`nestedEvaluate[depth - 1] ``, which is expected to call back into
+/// this function, until `depth` is 0.
+///
+/// This tests Rust → WL → Rust → WL → ... chains of nested evaluations.
+#[wll::export]
+fn test_nested_evaluate(kernel: &KernelThread, depth: i64) -> i64 {
+    if depth <= 0 {
+        return 0;
+    }
+
+    let expr = Expr::normal(Symbol::new("Global`nestedEvaluate"), vec![Expr::from(
+        depth - 1,
+    )]);
+
+    match kernel.evaluate(&expr).kind() {
+        ExprKind::Integer(result) => result + 1,
+        _ => panic!("nested evaluation did not return an Integer"),
+    }
+}
+
+/// Evaluate `2 + 2` using a `KernelThread`, which can only be obtained on the main
+/// thread.
+#[wll::export]
+fn test_kernel_thread() -> bool {
+    let kernel = KernelThread::current().expect("expected to be on the main thread");
+
+    let on_spawned_thread = std::thread::spawn(|| KernelThread::current().is_some())
+        .join()
+        .unwrap();
+
+    let expr = Expr::normal(Symbol::new("System`Plus"), vec![
+        Expr::from(2),
+        Expr::from(2),
+    ]);
+
+    !on_spawned_thread && kernel.evaluate(&expr) == Expr::from(4)
 }
 
 #[wll::export]
@@ -22,6 +61,9 @@ fn test_runtime_function_from_non_main_thread() -> String {
             // Do nothing, just to avoid printing panic message to stderr.
         }));
 
+        // Test the runtime check made by the deprecated free function, which (unlike
+        // `KernelThread::evaluate()`) can be called from a spawned thread.
+        #[allow(deprecated)]
         let result = panic::catch_unwind(|| {
             wll::evaluate(&Expr::normal(Symbol::new("System`Plus"), vec![
                 Expr::from(2),
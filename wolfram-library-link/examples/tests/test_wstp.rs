@@ -2,6 +2,7 @@ use wolfram_library_link::{
     export,
     expr::Expr,
     wstp::{self, Link},
+    FromExpr, ToExpr,
 };
 
 #[export(wstp)]
@@ -91,3 +92,32 @@ fn test_wstp_fn_poison_link_and_panic(link: &mut Link) {
 fn test_wstp_expr_return_null(_args: Vec<Expr>) {
     // Do nothing.
 }
+
+//======================================
+// Typed parameters
+//======================================
+
+#[derive(FromExpr)]
+struct Settings {
+    scale: f64,
+    label: Option<String>,
+}
+
+#[derive(ToExpr)]
+struct Scaled {
+    values: Vec<f64>,
+    label: String,
+}
+
+#[export(wstp)]
+fn test_wstp_typed_scale(values: Vec<i64>, settings: Settings) -> Scaled {
+    let Settings { scale, label } = settings;
+
+    Scaled {
+        values: values
+            .into_iter()
+            .map(|value| value as f64 * scale)
+            .collect(),
+        label: label.unwrap_or_else(|| "unlabeled".to_owned()),
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::KernelThread;
+
+/// Error indicating that the user has requested that the current evaluation be aborted.
+///
+/// Use [`KernelThread::check_abort()`] to return this error from a function when an abort has been
+/// requested.
+///
+/// Functions exported using [`#[export(abortable)]`][crate::export#advanced] can return
+/// `Result<T, AbortError>`; when the error is returned because of an abort, no message
+/// is issued, and the evaluation that called the function returns
+/// [`$Aborted`][ref/$Aborted]<sub>WL</sub>.
+///
+/// [ref/$Aborted]: https://reference.wolfram.com/language/ref/$Aborted.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AbortError;
+
+impl fmt::Display for AbortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "evaluation was aborted")
+    }
+}
+
+impl std::error::Error for AbortError {}
+
+impl KernelThread {
+    /// Returns `Err(AbortError)` if the user has requested that the current evaluation
+    /// be aborted.
+    ///
+    /// This is equivalent to checking [`KernelThread::aborted()`], but can be used with
+    /// the `?` operator to return from long-running loops.
+    ///
+    /// Use an [`AbortWatcher`] to check for aborts from other threads.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use wolfram_library_link::{self as wll, AbortError, KernelThread};
+    ///
+    /// #[wll::export(abortable)]
+    /// fn count_up(kernel: &KernelThread, n: i64) -> Result<i64, AbortError> {
+    ///     let mut total = 0;
+    ///
+    ///     for i in 0..n {
+    ///         kernel.check_abort()?;
+    ///
+    ///         total += i;
+    ///     }
+    ///
+    ///     Ok(total)
+    /// }
+    /// # }
+    /// ```
+    pub fn check_abort(&self) -> Result<(), AbortError> {
+        if self.aborted() {
+            return Err(AbortError);
+        }
+
+        Ok(())
+    }
+}
+
+/// Shares the abort status of the current evaluation with background threads.
+///
+/// LibraryLink does not allow checking for aborts from threads other than the main
+/// Kernel thread. An `AbortWatcher` is created on the main thread and cloned into
+/// worker threads, which can call [`AbortWatcher::is_aborted()`] or
+/// [`AbortWatcher::check()`] from any thread. The main thread observes aborts and
+/// records them in the watcher, either by calling [`AbortWatcher::poll()`] or, more
+/// conveniently, by waiting for a worker thread using [`AbortWatcher::join()`].
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, AbortError, AbortWatcher};
+///
+/// #[wll::export(abortable)]
+/// fn sum_in_background(n: i64) -> Result<i64, AbortError> {
+///     let watcher = AbortWatcher::new();
+///
+///     let handle = std::thread::spawn({
+///         let watcher = watcher.clone();
+///
+///         move || -> Result<i64, AbortError> {
+///             let mut total = 0;
+///
+///             for i in 0..n {
+///                 watcher.check()?;
+///
+///                 total += i;
+///             }
+///
+///             Ok(total)
+///         }
+///     });
+///
+///     // Wait for the thread to finish, stopping it early if an abort occurs.
+///     watcher.join(handle)?
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AbortWatcher {
+    aborted: Arc<AtomicBool>,
+}
+
+impl AbortWatcher {
+    /// How often [`AbortWatcher::join()`] checks for an abort while waiting.
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Construct a new watcher, which has not observed an abort.
+    pub fn new() -> Self {
+        AbortWatcher::default()
+    }
+
+    /// Returns `true` if this watcher has observed an abort.
+    ///
+    /// This function can be called from any thread.
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Acquire)
+    }
+
+    /// Returns `Err(AbortError)` if this watcher has observed an abort.
+    ///
+    /// This function can be called from any thread.
+    pub fn check(&self) -> Result<(), AbortError> {
+        if self.is_aborted() {
+            return Err(AbortError);
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the user has requested that the current evaluation be aborted,
+    /// and if so, record the abort so that it is observed by every clone of this
+    /// watcher.
+    ///
+    /// Returns `true` if this watcher has observed an abort. When called from a thread
+    /// other than the main Kernel thread, this does not check for new aborts.
+    pub fn poll(&self) -> bool {
+        if let Some(kernel) = KernelThread::current() {
+            if kernel.aborted() {
+                self.aborted.store(true, Ordering::Release);
+            }
+        }
+
+        self.is_aborted()
+    }
+
+    /// Wait for the thread associated with `handle` to finish, while polling for
+    /// aborts.
+    ///
+    /// If an abort is observed, the thread is still waited for, but its result is
+    /// discarded and `Err(AbortError)` is returned. The thread should periodically call
+    /// [`AbortWatcher::check()`] so that it finishes promptly after an abort.
+    ///
+    /// If the thread panicked, the panic is resumed on the current thread.
+    ///
+    /// This function must be called from the main Kernel thread.
+    pub fn join<T>(&self, handle: JoinHandle<T>) -> Result<T, AbortError> {
+        crate::assert_main_thread();
+
+        while !handle.is_finished() {
+            self.poll();
+
+            thread::sleep(Self::POLL_INTERVAL);
+        }
+
+        let value = match handle.join() {
+            Ok(value) => value,
+            Err(payload) => std::panic::resume_unwind(payload),
+        };
+
+        if self.poll() {
+            return Err(AbortError);
+        }
+
+        Ok(value)
+    }
+}
@@ -2,8 +2,9 @@
 //! [`MArgument`]s.
 
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     ffi::{CStr, CString},
+    fmt,
     os::raw::c_char,
 };
 
@@ -14,7 +15,7 @@ use crate::{
     rtl,
     sys::{self, mint, mreal, MArgument},
     wstp::Link,
-    DataStore, Image, NumericArray,
+    DataStore, Image, KernelThread, MessageName, NumericArray, SparseArray, Tensor,
 };
 
 /// Trait implemented for types that can be passed via an [`MArgument`].
@@ -22,10 +23,45 @@ pub trait FromArg<'a> {
     #[allow(missing_docs)]
     unsafe fn from_arg(arg: &'a MArgument) -> Self;
 
+    /// Like [`FromArg::from_arg()`], but returns an error message instead of panicking
+    /// if `arg` does not refer to a valid value of this type.
+    ///
+    /// [`#[export]`][crate::export] functions use this method. If an argument fails to
+    /// convert, the function is not called, and the error is reported the same way as
+    /// an `Err(..)` returned from the function.
+    #[doc(hidden)]
+    unsafe fn try_from_arg(arg: &'a MArgument) -> Result<Self, String>
+    where
+        Self: Sized,
+    {
+        Ok(Self::from_arg(arg))
+    }
+
+    /// The number of [`MArgument`]s used by this parameter type.
+    ///
+    /// This is `0` for parameter types that are provided by the wrapper instead of the
+    /// Kernel, like [`&KernelThread`][KernelThread]. [`FromArg::from_arg()`] is not
+    /// called for such types, and they are not included in
+    /// [`NativeFunction::signature()`].
+    #[doc(hidden)]
+    const ARGUMENT_COUNT: usize = 1;
+
+    /// Construct a parameter that does not use an [`MArgument`]. Only called if
+    /// [`FromArg::ARGUMENT_COUNT`] is `0`.
+    #[doc(hidden)]
+    unsafe fn try_from_wrapper() -> Result<Self, String>
+    where
+        Self: Sized,
+    {
+        unreachable!(
+            "FromArg::try_from_wrapper() called for a type that uses an MArgument"
+        )
+    }
+
     /// Return the *LibraryLink* parameter type as a Wolfram Language expression.
     ///
     /// ```
-    /// use wolfram_library_link::{FromArg, NumericArray};
+    /// use wolfram_library_link::{FromArg, NumericArray, Tensor};
     ///
     /// assert_eq!(&bool::parameter_type().to_string(), "\"Boolean\"");
     /// assert_eq!(&i64::parameter_type().to_string(), "System`Integer");
@@ -33,6 +69,10 @@ pub trait FromArg<'a> {
     ///     &<&NumericArray<i8>>::parameter_type().to_string(),
     ///     r#"System`List[System`LibraryDataType[System`NumericArray, "Integer8"], "Constant"]"#
     /// );
+    /// assert_eq!(
+    ///     &<&Tensor<f64>>::parameter_type().to_string(),
+    ///     r#"System`List[System`Real, System`Blank[], "Constant"]"#
+    /// );
     /// ```
     ///
     /// See also [`IntoArg::return_type()`] and [`NativeFunction::signature()`].
@@ -353,6 +393,90 @@ impl<'a> FromArg<'a> for NumericArray<()> {
     }
 }
 
+//--------------------------------------
+// Tensor
+//--------------------------------------
+
+/// Construct the `{<T>, _}` packed array type specification used by `Tensor<T>`.
+///
+/// The rank of a [`Tensor`] is not encoded in its Rust type, so `_` is used to accept
+/// packed arrays of any rank.
+pub(crate) fn tensor_type<T: crate::TensorType>() -> Vec<Expr> {
+    vec![
+        Expr::from(Symbol::new(&format!("System`{}", T::TYPE.name()))),
+        Expr::normal(Symbol::new("System`Blank"), vec![]),
+    ]
+}
+
+impl<'a, T: crate::TensorType> FromArg<'a> for &'a Tensor<T> {
+    unsafe fn from_arg(arg: &'a MArgument) -> &'a Tensor<T> {
+        Tensor::ref_cast(&*arg.tensor)
+    }
+
+    fn parameter_type() -> Expr {
+        // {<T>, _, "Constant"}
+        let mut spec = tensor_type::<T>();
+        spec.push(Expr::string("Constant"));
+
+        Expr::normal(Symbol::new("System`List"), spec)
+    }
+}
+
+impl<'a, T: crate::TensorType> FromArg<'a> for Tensor<T> {
+    unsafe fn from_arg(arg: &'a MArgument) -> Tensor<T> {
+        Tensor::from_raw(*arg.tensor)
+    }
+
+    fn parameter_type() -> Expr {
+        // {<T>, _, "Shared"}
+        let mut spec = tensor_type::<T>();
+        spec.push(Expr::string("Shared"));
+
+        Expr::normal(Symbol::new("System`List"), spec)
+    }
+}
+
+//--------------------------------------
+// SparseArray
+//--------------------------------------
+
+/// Construct the `LibraryDataType[SparseArray, <T>]` type specification used by
+/// `SparseArray<T>`.
+pub(crate) fn sparse_array_type<T: crate::TensorType>() -> Expr {
+    Expr::normal(Symbol::new("System`LibraryDataType"), vec![
+        Expr::from(Symbol::new("System`SparseArray")),
+        Expr::from(Symbol::new(&format!("System`{}", T::TYPE.name()))),
+    ])
+}
+
+impl<'a, T: crate::TensorType> FromArg<'a> for &'a SparseArray<T> {
+    unsafe fn from_arg(arg: &'a MArgument) -> &'a SparseArray<T> {
+        SparseArray::ref_cast(&*arg.sparse)
+    }
+
+    fn parameter_type() -> Expr {
+        // {LibraryDataType[SparseArray, <T>], "Constant"}
+        Expr::normal(Symbol::new("System`List"), vec![
+            sparse_array_type::<T>(),
+            Expr::string("Constant"),
+        ])
+    }
+}
+
+impl<'a, T: crate::TensorType> FromArg<'a> for SparseArray<T> {
+    unsafe fn from_arg(arg: &'a MArgument) -> SparseArray<T> {
+        SparseArray::from_raw(*arg.sparse)
+    }
+
+    fn parameter_type() -> Expr {
+        // {LibraryDataType[SparseArray, <T>], "Shared"}
+        Expr::normal(Symbol::new("System`List"), vec![
+            sparse_array_type::<T>(),
+            Expr::string("Shared"),
+        ])
+    }
+}
+
 //--------------------------------------
 // Image
 //--------------------------------------
@@ -456,6 +580,59 @@ impl<'a> FromArg<'a> for &'a DataStore {
     }
 }
 
+//--------------------------------------
+// KernelThread
+//--------------------------------------
+
+/// A `&KernelThread` parameter of a function exported using
+/// [`#[export]`][crate::export] is provided by the generated wrapper function, and is
+/// not passed by the Kernel.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{export, KernelThread};
+///
+/// #[export]
+/// fn version_plus(kernel: &KernelThread, x: f64) -> f64 {
+///     let version: f64 = kernel.evaluate_str_as("$VersionNumber").unwrap();
+///
+///     version + x
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad["...", "version_plus", {Real}, Real]
+/// ```
+impl<'a> FromArg<'a> for &'a KernelThread {
+    unsafe fn from_arg(_: &'a MArgument) -> &'a KernelThread {
+        kernel_thread_ref()
+    }
+
+    const ARGUMENT_COUNT: usize = 0;
+
+    unsafe fn try_from_wrapper() -> Result<&'a KernelThread, String> {
+        Ok(kernel_thread_ref())
+    }
+
+    fn parameter_type() -> Expr {
+        panic!("&KernelThread is not passed as a LibraryLink function argument")
+    }
+}
+
+/// # Safety
+///
+/// This function must only be called from the wrapper of a native LibraryLink function.
+unsafe fn kernel_thread_ref<'a>() -> &'a KernelThread {
+    // Safety: The Kernel calls LibraryLink functions from the main thread.
+    let kernel = KernelThread::new_unchecked();
+
+    // `KernelThread` is zero-sized, so leaking a box of it does not allocate.
+    Box::leak(Box::new(kernel))
+}
+
 //======================================
 // impl IntoArg
 //======================================
@@ -470,6 +647,106 @@ impl IntoArg for () {
     }
 }
 
+//--------------------
+// Result
+//--------------------
+
+thread_local! {
+    /// Set by [`<Result<T, E> as IntoArg>::into_arg()`] when the returned value is an
+    /// `Err`. See [`take_returned_error()`].
+    static RETURNED_ERROR: Cell<bool> = const { Cell::new(false) };
+
+    /// Set while a function exported using `#[export(abortable)]` is being called on
+    /// this thread. See [`set_abortable()`].
+    static ABORTABLE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Set whether the function currently being called on this thread was exported using
+/// `#[export(abortable)]`, returning the previous value.
+///
+/// If set, an `Err(..)` returned while an abort is in progress is not issued as a
+/// message.
+pub(crate) fn set_abortable(abortable: bool) -> bool {
+    ABORTABLE.with(|cell| cell.replace(abortable))
+}
+
+/// Returns `true` if the last value returned via [`IntoArg`] on this thread was an
+/// `Err(..)`, and resets that state.
+pub(crate) fn take_returned_error() -> bool {
+    RETURNED_ERROR.with(|returned| returned.replace(false))
+}
+
+/// Issue `text` as a `LibraryFunction::rusterr` message, and make the LibraryLink
+/// function currently being called return `LIBRARY_FUNCTION_ERROR`.
+///
+/// # Safety
+///
+/// This function must only be called from the wrapper of a native LibraryLink function.
+unsafe fn return_error(text: String) {
+    // The Kernel discards the result of an aborted evaluation, so don't issue the error
+    // as a message if it was caused by the abort.
+    // Safety: The Kernel calls LibraryLink functions from the main thread.
+    let kernel = KernelThread::new_unchecked();
+
+    if !(ABORTABLE.with(Cell::get) && kernel.aborted()) {
+        // If the message can't be issued, the Kernel still issues
+        // LibraryFunction::rterr.
+        let _: bool = RUST_ERROR.issue(&kernel, &[Expr::string(text)]);
+    }
+
+    RETURNED_ERROR.with(|returned| returned.set(true));
+}
+
+/// Message issued with the text of an `Err(..)` returned from a native function.
+static RUST_ERROR: MessageName =
+    MessageName::new("LibraryFunction::rusterr").with_template("`1`");
+
+#[cfg(feature = "automate-function-loading-boilerplate")]
+crate::register_messages!(RUST_ERROR);
+
+/// Return a value, or fail with an error message.
+///
+/// If the result is `Err(..)`, the error text is issued as a `LibraryFunction::rusterr`
+/// message, and the LibraryLink function returns `LIBRARY_FUNCTION_ERROR`, which
+/// causes the Kernel to issue a [`LibraryFunction::rterr`][LibraryFunction] message and
+/// return `LibraryFunctionError[..]`.
+///
+/// In functions exported using [`#[export(abortable)]`][crate::export#advanced], an
+/// error returned while an abort is in progress is not issued as a message. This
+/// includes an [`AbortError`][crate::AbortError] returned by
+/// [`KernelThread::check_abort()`].
+///
+/// # Example
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::export;
+///
+/// #[export]
+/// fn checked_sqrt(x: f64) -> Result<f64, String> {
+///     if x < 0.0 {
+///         return Err(format!("cannot take square root of negative number: {}", x));
+///     }
+///
+///     Ok(x.sqrt())
+/// }
+/// # }
+/// ```
+///
+/// [LibraryFunction]: https://reference.wolfram.com/language/ref/LibraryFunction.html
+impl<T: IntoArg, E: fmt::Display> IntoArg for Result<T, E> {
+    unsafe fn into_arg(self, arg: MArgument) {
+        match self {
+            Ok(value) => value.into_arg(arg),
+            Err(err) => return_error(err.to_string()),
+        }
+    }
+
+    fn return_type() -> Expr {
+        T::return_type()
+    }
+}
+
 //---------------------
 // Primitive data types
 //---------------------
@@ -645,7 +922,7 @@ impl IntoArg for String {
 }
 
 //---------------------------------------
-// NumericArray, Image, DataStore
+// NumericArray, Tensor, SparseArray, Image, DataStore
 //---------------------------------------
 
 impl<T: crate::NumericArrayType> IntoArg for NumericArray<T> {
@@ -673,6 +950,28 @@ impl IntoArg for NumericArray<()> {
     }
 }
 
+impl<T: crate::TensorType> IntoArg for Tensor<T> {
+    unsafe fn into_arg(self, arg: MArgument) {
+        *arg.tensor = self.into_raw();
+    }
+
+    fn return_type() -> Expr {
+        // {<T>, _}
+        Expr::normal(Symbol::new("System`List"), tensor_type::<T>())
+    }
+}
+
+impl<T: crate::TensorType> IntoArg for SparseArray<T> {
+    unsafe fn into_arg(self, arg: MArgument) {
+        *arg.sparse = self.into_raw();
+    }
+
+    fn return_type() -> Expr {
+        // LibraryDataType[SparseArray, <T>]
+        sparse_array_type::<T>()
+    }
+}
+
 impl<T: crate::ImageData> IntoArg for Image<T> {
     unsafe fn into_arg(self, arg: MArgument) {
         *arg.image = self.into_raw();
@@ -757,22 +1056,35 @@ macro_rules! impl_NativeFunction {
             $($type: FromArg<'a>),*
         {
             unsafe fn call(&self, args: &'a [MArgument], ret: MArgument) {
-                // Re-use the $type name as the local variable names. E.g.
-                //     let A1 = A1::from_arg(..);
-                // This works because types and variable names are different namespaces.
-                #[allow(non_snake_case)]
-                let [$($type,)*] = match args {
-                    [$($type,)*] => [$($type,)*],
-                    _ => panic!(
+                let argument_count = 0 $(+ $type::ARGUMENT_COUNT)*;
+
+                if args.len() != argument_count {
+                    panic!(
                         "LibraryLink function number of arguments ({}) does not match \
                         number of parameters",
                         args.len()
-                    ),
-                };
+                    );
+                }
+
+                let mut args = args.iter();
 
+                // Re-use the $type name as the local variable names. E.g.
+                //     let A1 = A1::try_from_arg(..);
+                // This works because types and variable names are different namespaces.
                 $(
                     #[allow(non_snake_case)]
-                    let $type: $type = $type::from_arg($type);
+                    let $type: Result<$type, String> = if $type::ARGUMENT_COUNT == 0 {
+                        $type::try_from_wrapper()
+                    } else {
+                        // The number of arguments was checked above.
+                        $type::try_from_arg(args.next().unwrap())
+                    };
+
+                    #[allow(non_snake_case)]
+                    let $type: $type = match $type {
+                        Ok(value) => value,
+                        Err(err) => return return_error(err),
+                    };
                 )*
 
                 let result: R = self($($type,)*);
@@ -784,7 +1096,9 @@ macro_rules! impl_NativeFunction {
                 let mut param_tys = Vec::new();
 
                 $(
-                    param_tys.push($type::parameter_type());
+                    if $type::ARGUMENT_COUNT != 0 {
+                        param_tys.push($type::parameter_type());
+                    }
                 )*
 
                 Ok((param_tys, R::return_type()))
@@ -942,7 +1256,7 @@ impl WstpFunction for fn(Vec<Expr>) {
 // Utilities
 //----------------------------
 
-fn get_args_list(link: &mut Link) -> Result<Vec<Expr>, String> {
+pub(crate) fn get_args_list(link: &mut Link) -> Result<Vec<Expr>, String> {
     get_args_list_impl(link).map_err(|err: wstp::Error| {
         format!("WSTP error reading argument List expression: {}", err)
     })
@@ -6,28 +6,69 @@
 //! laid out by [this StackOverflow answer](https://mathematica.stackexchange.com/a/138433).
 
 use std::{
+    collections::HashMap,
     ffi::{c_void, CString},
-    panic,
+    mem, panic,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use static_assertions::assert_not_impl_any;
+use once_cell::sync::Lazy;
 
-use crate::{rtl, sys, DataStore};
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+use crate::{
+    catch_panic::call_and_catch_panic, expr::Expr, rtl, sys, DataStore, KernelThread,
+    NumericArray, NumericArrayType,
+};
 
 
 /// Handle to a Wolfram Language [`AsynchronousTaskObject`][ref/AsynchronousTaskObject]<sub>WL</sub>
 /// instance.
 ///
 /// Use [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread] to spawn a new
-/// asynchronous task.
+/// asynchronous task, or [`new_without_thread()`][AsyncTaskObject::new_without_thread]
+/// to create a task whose events are raised from a thread that is managed by the
+/// library.
 ///
 /// [ref/AsynchronousTaskObject]: https://reference.wolfram.com/language/ref/AsynchronousTaskObject.html
 #[derive(Debug)]
-pub struct AsyncTaskObject(sys::mint);
+pub struct AsyncTaskObject {
+    id: sys::mint,
+    /// Whether the task should be removed when this handle is dropped. This is only
+    /// true for tasks created using `new_without_thread()`.
+    remove_on_drop: bool,
+}
 
 // TODO: Determine if it would be safe for this type to implement Copy/Clone.
 assert_not_impl_any!(AsyncTaskObject: Copy, Clone);
 
+// Events can be raised from any thread.
+assert_impl_all!(AsyncTaskObject: Send);
+
+/// Token used by a background thread to check whether its asynchronous task has been
+/// stopped, e.g. by a call to [`TaskRemove`][ref/TaskRemove]<sub>WL</sub>.
+///
+/// Use [`AsyncTaskObject::cancellation_token()`] to get the token for a task.
+///
+/// LibraryLink does not notify a library when a task is removed, so a task is
+/// detected as cancelled by polling
+/// [`asynchronousTaskAliveQ`][sys::st_WolframIOLibrary_Functions::asynchronousTaskAliveQ].
+/// The blocking helper methods on this type poll at most every
+/// [`CancellationToken::POLL_INTERVAL`].
+///
+/// [ref/TaskRemove]: https://reference.wolfram.com/language/ref/TaskRemove.html
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    id: sys::mint,
+}
+
+assert_impl_all!(CancellationToken: Send, Sync);
+
 
 //======================================
 // Impls
@@ -56,7 +97,23 @@ impl AsyncTaskObject {
     /// will result in an asynchronous call to the Wolfram Language `handler` function
     /// specified in the call to `` Internal`CreateAsynchronousEvent ``.
     ///
+    /// If the background thread panics, a `"RustPanic"` event is raised before the
+    /// task ends. The event data has the named string elements `"message"` and
+    /// `"location"`, and, if the `LIBRARY_LINK_RUST_BACKTRACE` environment variable
+    /// is set, `"backtrace"`.
+    ///
+    /// # Cancellation
+    ///
+    /// The background thread is not stopped when the task is removed, e.g. by
+    /// [`TaskRemove`][ref/TaskRemove]<sub>WL</sub>. A long-running background thread
+    /// should use the [`CancellationToken`] returned by
+    /// [`task.cancellation_token()`][AsyncTaskObject::cancellation_token] to stop when
+    /// that happens, or be spawned using
+    /// [`spawn_cancellable()`][AsyncTaskObject::spawn_cancellable], which passes the
+    /// token to the closure.
+    ///
     /// [ref/AsynchronousTaskObject]: https://reference.wolfram.com/language/ref/AsynchronousTaskObject.html
+    /// [ref/TaskRemove]: https://reference.wolfram.com/language/ref/TaskRemove.html
     pub fn spawn_with_thread<F>(f: F) -> Self
     where
         F: FnMut(AsyncTaskObject) + Send + panic::UnwindSafe + 'static,
@@ -64,10 +121,118 @@ impl AsyncTaskObject {
         spawn_async_task_with_thread(f)
     }
 
+    /// Spawn a new Wolfram Language asynchronous task, passing the
+    /// [`CancellationToken`] of the task to the background thread.
+    ///
+    /// This is equivalent to calling
+    /// [`task.cancellation_token()`][AsyncTaskObject::cancellation_token] at the start
+    /// of a closure passed to [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread].
+    ///
+    /// # Example
+    ///
+    /// Raise an event every second until the task is removed:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use wolfram_library_link::{AsyncTaskObject, CancellationToken, DataStore};
+    ///
+    /// AsyncTaskObject::spawn_cancellable(
+    ///     |task: AsyncTaskObject, token: CancellationToken| {
+    ///         while !token.sleep_or_cancelled(Duration::from_secs(1)) {
+    ///             task.raise_async_event("tick", DataStore::new());
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    pub fn spawn_cancellable<F>(f: F) -> Self
+    where
+        F: FnMut(AsyncTaskObject, CancellationToken) + Send + panic::UnwindSafe + 'static,
+    {
+        let mut f = f;
+
+        spawn_async_task_with_thread(move |task: AsyncTaskObject| {
+            let token = task.cancellation_token();
+
+            f(task, token)
+        })
+    }
+
+    /// Create a new Wolfram Language asynchronous task that does not have an
+    /// associated background thread.
+    ///
+    /// Events for the task can be raised using
+    /// [`raise_async_event()`][AsyncTaskObject::raise_async_event] from any thread,
+    /// including threads owned by another runtime (e.g. a thread pool, or a thread
+    /// created by a C library). The returned handle can be moved to that thread.
+    ///
+    /// Like [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], this method
+    /// should be used within a LibraryLink function that was called via
+    /// `` Internal`CreateAsynchronousTask ``, and that function should return the
+    /// [`id()`][AsyncTaskObject::id] of the new task.
+    ///
+    /// The task is removed when the returned handle is dropped, or when
+    /// [`remove()`][AsyncTaskObject::remove] is called.
+    ///
+    /// *LibraryLink C Function:* [`createAsynchronousTaskWithoutThread`][sys::st_WolframIOLibrary_Functions::createAsynchronousTaskWithoutThread].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+    ///
+    /// #[wll::export]
+    /// fn start_ticker() -> mint {
+    ///     let task = AsyncTaskObject::new_without_thread();
+    ///     let id = task.id();
+    ///
+    ///     // This thread could also be owned by e.g. a thread pool.
+    ///     std::thread::spawn(move || {
+    ///         for i in 0..10 {
+    ///             let mut data = DataStore::new();
+    ///             data.add_i64(i);
+    ///             task.raise_async_event("tick", data);
+    ///         }
+    ///
+    ///         // `task` is dropped here, which removes the task.
+    ///     });
+    ///
+    ///     id
+    /// }
+    /// # }
+    /// ```
+    pub fn new_without_thread() -> Self {
+        let id: sys::mint = unsafe { rtl::createAsynchronousTaskWithoutThread() };
+
+        AsyncTaskObject {
+            id,
+            remove_on_drop: true,
+        }
+    }
+
     /// Returns the numeric ID which identifies this async object.
     pub fn id(&self) -> sys::mint {
-        let AsyncTaskObject(id) = *self;
-        id
+        self.id
+    }
+
+    /// Remove this asynchronous task, signaling to the Wolfram Language that the task
+    /// has finished.
+    ///
+    /// Returns `false` if the task had already been removed or was no longer alive.
+    ///
+    /// Handles to tasks created using
+    /// [`new_without_thread()`][AsyncTaskObject::new_without_thread] call this method
+    /// automatically when dropped. Handles to tasks created using
+    /// [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread] do not, because
+    /// the task's background thread is managed by the Wolfram runtime.
+    ///
+    /// *LibraryLink C Function:* [`removeAsynchronousTask`][sys::st_WolframIOLibrary_Functions::removeAsynchronousTask].
+    pub fn remove(mut self) -> bool {
+        self.remove_on_drop = false;
+
+        let removed: sys::mint = unsafe { rtl::removeAsynchronousTask(self.id) };
+
+        removed != 0
     }
 
     /// Returns whether this async task is still alive.
@@ -79,6 +244,29 @@ impl AsyncTaskObject {
         crate::bool_from_mbool(is_alive)
     }
 
+    /// Returns a [`CancellationToken`] that can be used to check whether this task has
+    /// been stopped.
+    ///
+    /// # Example
+    ///
+    /// Raise an event every second until the task is removed:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use wolfram_library_link::{AsyncTaskObject, DataStore};
+    ///
+    /// AsyncTaskObject::spawn_with_thread(|task: AsyncTaskObject| {
+    ///     let token = task.cancellation_token();
+    ///
+    ///     while !token.sleep_or_cancelled(Duration::from_secs(1)) {
+    ///         task.raise_async_event("tick", DataStore::new());
+    ///     }
+    /// });
+    /// ```
+    pub fn cancellation_token(&self) -> CancellationToken {
+        CancellationToken { id: self.id }
+    }
+
     /// Returns whether this async task has been started.
     ///
     /// *LibraryLink C Function:* [`asynchronousTaskStartedQ`][sys::st_WolframIOLibrary_Functions::asynchronousTaskStartedQ].
@@ -107,7 +295,7 @@ impl AsyncTaskObject {
     /// task_object.raise_async_event("change", DataStore::new());
     /// ```
     pub fn raise_async_event(&self, name: &str, data: DataStore) {
-        let AsyncTaskObject(id) = *self;
+        let AsyncTaskObject { id, .. } = *self;
 
         let name = CString::new(name)
             .expect("unable to convert raised async event name to CString");
@@ -117,6 +305,821 @@ impl AsyncTaskObject {
             rtl::raiseAsyncEvent(id, name.into_raw(), data.into_raw());
         }
     }
+
+    /// Construct a handle to a task that is owned by something else, and so should
+    /// not be removed when this handle is dropped.
+    fn from_unowned_id(id: sys::mint) -> Self {
+        AsyncTaskObject {
+            id,
+            remove_on_drop: false,
+        }
+    }
+}
+
+impl Drop for AsyncTaskObject {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            unsafe {
+                rtl::removeAsynchronousTask(self.id);
+            }
+        }
+    }
+}
+
+impl CancellationToken {
+    /// The maximum amount of time that [`sleep_or_cancelled()`][Self::sleep_or_cancelled]
+    /// and [`recv_or_cancelled()`][Self::recv_or_cancelled] block before checking
+    /// whether the task has been cancelled.
+    pub const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Returns whether the task has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        let is_alive: sys::mbool = unsafe { rtl::asynchronousTaskAliveQ(self.id) };
+
+        !crate::bool_from_mbool(is_alive)
+    }
+
+    /// Sleep for `duration`, returning early if the task is cancelled.
+    ///
+    /// Returns `true` if the task was cancelled.
+    pub fn sleep_or_cancelled(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+
+        loop {
+            if self.is_cancelled() {
+                return true;
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return false;
+            }
+
+            std::thread::sleep(Self::POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Wait for a value from `receiver`, returning early if the task is cancelled.
+    ///
+    /// Returns `None` if the task was cancelled, or if every sender for `receiver` was
+    /// dropped.
+    pub fn recv_or_cancelled<T>(&self, receiver: &mpsc::Receiver<T>) -> Option<T> {
+        loop {
+            if self.is_cancelled() {
+                return None;
+            }
+
+            match receiver.recv_timeout(Self::POLL_INTERVAL) {
+                Ok(value) => return Some(value),
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+//======================================
+// Periodic tasks
+//======================================
+
+/// Pause flags of the tasks spawned by [`AsyncTaskObject::spawn_periodic()`] that are
+/// still running, keyed by task ID.
+static PERIODIC_TASKS: Lazy<Mutex<HashMap<sys::mint, Arc<AtomicBool>>>> =
+    Lazy::new(Default::default);
+
+impl AsyncTaskObject {
+    /// Spawn a new Wolfram Language asynchronous task that calls `f` every `interval`.
+    ///
+    /// If `f` returns `Some((name, data))`, an event named `name` is raised. The first
+    /// call to `f` occurs one `interval` after the task is spawned.
+    ///
+    /// Calls are scheduled at fixed multiples of `interval` from the start of the task,
+    /// so the time taken by `f` does not cause the schedule to drift. If a call to `f`
+    /// takes longer than `interval`, any missed calls are skipped.
+    ///
+    /// The task stops when it is removed, e.g. by
+    /// [`TaskRemove`][ref/TaskRemove]<sub>WL</sub>. Use
+    /// [`pause_periodic()`][AsyncTaskObject::pause_periodic] and
+    /// [`resume_periodic()`][AsyncTaskObject::resume_periodic] to temporarily stop
+    /// calling `f`.
+    ///
+    /// Like [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], this method
+    /// should be used within a LibraryLink function that was called via
+    /// `` Internal`CreateAsynchronousTask ``, and that function should return the
+    /// [`id()`][AsyncTaskObject::id] of the new task.
+    ///
+    /// [ref/TaskRemove]: https://reference.wolfram.com/language/ref/TaskRemove.html
+    ///
+    /// # Example
+    ///
+    /// Raise a `"tick"` event every 100 milliseconds, and export functions that can be
+    /// used to pause and resume the task:
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use std::time::Duration;
+    /// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+    ///
+    /// #[wll::export]
+    /// fn start_ticker() -> mint {
+    ///     let mut count = 0;
+    ///
+    ///     let task = AsyncTaskObject::spawn_periodic(Duration::from_millis(100), move || {
+    ///         count += 1;
+    ///
+    ///         let mut data = DataStore::new();
+    ///         data.add_i64(count);
+    ///
+    ///         Some(("tick".to_owned(), data))
+    ///     });
+    ///
+    ///     task.id()
+    /// }
+    ///
+    /// #[wll::export]
+    /// fn pause_ticker(id: mint) -> bool {
+    ///     AsyncTaskObject::pause_periodic(id)
+    /// }
+    ///
+    /// #[wll::export]
+    /// fn resume_ticker(id: mint) -> bool {
+    ///     AsyncTaskObject::resume_periodic(id)
+    /// }
+    /// # }
+    /// ```
+    pub fn spawn_periodic<F>(interval: Duration, f: F) -> Self
+    where
+        F: FnMut() -> Option<(String, DataStore)> + Send + panic::UnwindSafe + 'static,
+    {
+        assert!(
+            !interval.is_zero(),
+            "AsyncTaskObject::spawn_periodic: interval must be greater than zero"
+        );
+
+        let paused = Arc::new(AtomicBool::new(false));
+
+        // Hold the lock until the task has been registered, so that the task can't
+        // finish and unregister itself first.
+        let mut tasks = PERIODIC_TASKS.lock().unwrap();
+
+        let task = AsyncTaskObject::spawn_with_thread({
+            let paused = Arc::clone(&paused);
+            let mut f = f;
+
+            move |task: AsyncTaskObject| {
+                // Unregister the task when it ends, even if `f` panics.
+                struct Unregister(sys::mint);
+
+                impl Drop for Unregister {
+                    fn drop(&mut self) {
+                        if let Ok(mut tasks) = PERIODIC_TASKS.lock() {
+                            tasks.remove(&self.0);
+                        }
+                    }
+                }
+
+                let _unregister = Unregister(task.id());
+
+                run_periodic(&task, interval, &paused, &mut f)
+            }
+        });
+
+        tasks.insert(task.id(), paused);
+
+        task
+    }
+
+    /// Pause the periodic task with the specified ID.
+    ///
+    /// Returns `false` if `id` is not the ID of a running task spawned by
+    /// [`spawn_periodic()`][AsyncTaskObject::spawn_periodic].
+    pub fn pause_periodic(id: sys::mint) -> bool {
+        set_periodic_paused(id, true)
+    }
+
+    /// Resume the paused periodic task with the specified ID.
+    ///
+    /// Returns `false` if `id` is not the ID of a running task spawned by
+    /// [`spawn_periodic()`][AsyncTaskObject::spawn_periodic].
+    pub fn resume_periodic(id: sys::mint) -> bool {
+        set_periodic_paused(id, false)
+    }
+}
+
+fn set_periodic_paused(id: sys::mint, paused: bool) -> bool {
+    match PERIODIC_TASKS.lock().unwrap().get(&id) {
+        Some(flag) => {
+            flag.store(paused, Ordering::SeqCst);
+            true
+        },
+        None => false,
+    }
+}
+
+fn run_periodic<F>(
+    task: &AsyncTaskObject,
+    interval: Duration,
+    paused: &AtomicBool,
+    f: &mut F,
+) where
+    F: FnMut() -> Option<(String, DataStore)>,
+{
+    let token = task.cancellation_token();
+
+    let mut next = Instant::now() + interval;
+
+    loop {
+        if token.sleep_or_cancelled(next.saturating_duration_since(Instant::now())) {
+            return;
+        }
+
+        if !paused.load(Ordering::SeqCst) {
+            if let Some((name, data)) = f() {
+                task.raise_async_event(&name, data);
+            }
+        }
+
+        next += interval;
+
+        // Skip any calls that were missed because `f` took longer than `interval`.
+        let now = Instant::now();
+
+        if next <= now {
+            let missed = (now - next).as_nanos() / interval.as_nanos() + 1;
+
+            next += interval * u32::try_from(missed).unwrap_or(u32::MAX);
+        }
+    }
+}
+
+//======================================
+// Batched events
+//======================================
+
+/// Sender that batches items into asynchronous events.
+///
+/// Raising a separate event for every item produced by a high-frequency source can
+/// flood the Wolfram Language event handler. An `EventSender` instead buffers items,
+/// and raises a single event containing every buffered item once either
+/// [`max_items`][BatchOptions::max_items] items are buffered, or
+/// [`max_delay`][BatchOptions::max_delay] has elapsed since the first buffered item
+/// was sent. The data of each event is built using [`BatchItem::into_data_store()`].
+///
+/// Items are buffered by a background thread. At most
+/// [`capacity`][BatchOptions::capacity] items can be waiting to be buffered, after
+/// which [`send()`][EventSender::send] blocks until there is room, providing
+/// backpressure to the producer.
+///
+/// Dropping the `EventSender` raises an event for any remaining buffered items, and
+/// waits for the background thread to finish.
+///
+/// # Example
+///
+/// Send readings in batches of up to 100 items, raising at least one event every 50ms
+/// while readings are being produced:
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use wolfram_library_link::{AsyncTaskObject, BatchOptions, EventSender};
+///
+/// # fn read_sensor() -> Option<f64> { None }
+/// AsyncTaskObject::spawn_with_thread(|task: AsyncTaskObject| {
+///     let sender = EventSender::<f64>::new(&task, "readings", BatchOptions {
+///         max_items: 100,
+///         max_delay: Duration::from_millis(50),
+///         ..BatchOptions::default()
+///     });
+///
+///     while let Some(reading) = read_sensor() {
+///         if sender.send(reading).is_err() {
+///             // The task was removed.
+///             break;
+///         }
+///     }
+/// });
+/// ```
+pub struct EventSender<T: BatchItem> {
+    sender: Option<mpsc::SyncSender<T>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// Size and time thresholds used by [`EventSender`].
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Maximum number of items included in a single event.
+    pub max_items: usize,
+    /// Maximum time an item is buffered before an event containing it is raised.
+    pub max_delay: Duration,
+    /// Maximum number of sent items waiting to be buffered before
+    /// [`EventSender::send()`] blocks.
+    pub capacity: usize,
+}
+
+/// Item type that can be sent using an [`EventSender`].
+///
+/// This trait is implemented for every [`NumericArrayType`], and for [`String`].
+pub trait BatchItem: Send + 'static {
+    /// Convert a batch of items into the data of a single asynchronous event.
+    fn into_data_store(batch: Vec<Self>) -> DataStore
+    where
+        Self: Sized;
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            max_items: 1024,
+            max_delay: Duration::from_millis(100),
+            capacity: 4096,
+        }
+    }
+}
+
+impl<T: BatchItem> EventSender<T> {
+    /// Create a new sender that raises events named `name` for `task`.
+    pub fn new(task: &AsyncTaskObject, name: &str, options: BatchOptions) -> Self {
+        let BatchOptions {
+            max_items,
+            max_delay,
+            capacity,
+        } = options;
+
+        assert!(
+            max_items > 0,
+            "EventSender: max_items must be greater than 0"
+        );
+
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+
+        let task = AsyncTaskObject::from_unowned_id(task.id());
+        let name = name.to_owned();
+
+        let thread = thread::spawn(move || {
+            batch_events::<T>(task, &name, receiver, max_items, max_delay)
+        });
+
+        EventSender {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Send an item, blocking if [`capacity`][BatchOptions::capacity] items are
+    /// already waiting to be buffered.
+    ///
+    /// Returns an error if the task has been removed.
+    pub fn send(&self, item: T) -> Result<(), mpsc::SendError<T>> {
+        self.sender().send(item)
+    }
+
+    /// Send an item, without blocking.
+    ///
+    /// Returns [`TrySendError::Full`][mpsc::TrySendError::Full] if
+    /// [`capacity`][BatchOptions::capacity] items are already waiting to be buffered,
+    /// and [`TrySendError::Disconnected`][mpsc::TrySendError::Disconnected] if the
+    /// task has been removed.
+    pub fn try_send(&self, item: T) -> Result<(), mpsc::TrySendError<T>> {
+        self.sender().try_send(item)
+    }
+
+    fn sender(&self) -> &mpsc::SyncSender<T> {
+        self.sender
+            .as_ref()
+            .expect("EventSender: sender was already dropped")
+    }
+}
+
+impl<T: BatchItem> Drop for EventSender<T> {
+    fn drop(&mut self) {
+        // Disconnect the channel, so that the background thread raises an event for
+        // any remaining items and then exits.
+        drop(self.sender.take());
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn batch_events<T: BatchItem>(
+    task: AsyncTaskObject,
+    name: &str,
+    receiver: mpsc::Receiver<T>,
+    max_items: usize,
+    max_delay: Duration,
+) {
+    let mut batch: Vec<T> = Vec::new();
+    // Time at which the current batch must be raised, if it is not empty.
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let received = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            },
+            None => receiver
+                .recv()
+                .map_err(|mpsc::RecvError| mpsc::RecvTimeoutError::Disconnected),
+        };
+
+        let disconnected = match received {
+            Ok(item) => {
+                if batch.is_empty() {
+                    deadline = Some(Instant::now() + max_delay);
+                }
+
+                batch.push(item);
+
+                if batch.len() < max_items {
+                    continue;
+                }
+
+                false
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => true,
+        };
+
+        if !batch.is_empty() {
+            // Stop if the task was removed. Dropping `receiver` causes any further
+            // sends to fail.
+            if !task.is_alive() {
+                return;
+            }
+
+            task.raise_async_event(name, T::into_data_store(mem::take(&mut batch)));
+            deadline = None;
+        }
+
+        if disconnected {
+            return;
+        }
+    }
+}
+
+/// Items are stored as a single rank 1 [`NumericArray`].
+impl<T: NumericArrayType + Send + 'static> BatchItem for T {
+    fn into_data_store(batch: Vec<Self>) -> DataStore {
+        let mut data = DataStore::new();
+        data.add_numeric_array(NumericArray::from_slice(&batch).into_generic());
+        data
+    }
+}
+
+/// Items are stored as separate string elements.
+impl BatchItem for String {
+    fn into_data_store(batch: Vec<Self>) -> DataStore {
+        let mut data = DataStore::new();
+
+        for item in &batch {
+            data.add_str(item);
+        }
+
+        data
+    }
+}
+
+//======================================
+// Main thread executor
+//======================================
+
+/// Work submitted to a [`MainThreadExecutor`], waiting to be run on the main Kernel
+/// thread.
+type MainThreadJob = Box<dyn FnOnce(&KernelThread) + Send>;
+
+/// Jobs submitted using [`MainThreadExecutor::submit()`] that have not been run yet,
+/// keyed by job ID.
+static MAIN_THREAD_JOBS: Lazy<Mutex<HashMap<sys::mint, MainThreadJob>>> =
+    Lazy::new(Default::default);
+
+static NEXT_MAIN_THREAD_JOB_ID: AtomicI64 = AtomicI64::new(1);
+
+/// Runs closures submitted by background threads on the main Kernel thread.
+///
+/// Callbacks into the Kernel, like [`KernelThread::evaluate()`], can only be made
+/// from the main Kernel thread. A `MainThreadExecutor` lets the background thread of an
+/// asynchronous task submit a closure, which is given a [`KernelThread`] when it is
+/// run on the main thread, and receive its result.
+///
+/// Submitting a closure raises a [`"RunOnMainThread"`][MainThreadExecutor::EVENT_NAME]
+/// event for the task, whose data is the integer ID of the submitted job. The Wolfram
+/// Language handler of the task must run the job by passing the ID to the
+/// [`wll_run_main_thread_job`][MainThreadExecutor::RUN_FUNCTION_NAME] function, which
+/// is exported from the library by
+/// [`export_main_thread_executor!`][crate::export_main_thread_executor]:
+///
+/// ```wolfram
+/// runMainThreadJob = LibraryFunctionLoad[
+///     "...", "wll_run_main_thread_job", {Integer}, "Void"
+/// ];
+///
+/// handler[task_, "RunOnMainThread", {id_}] := runMainThreadJob[id];
+///
+/// task = Internal`CreateAsynchronousTask[startWorker, {}, handler];
+/// ```
+///
+/// # Example
+///
+/// Ask the Kernel for the value of `$Version` from a background thread:
+///
+/// ```no_run
+/// use wolfram_library_link::{AsyncTaskObject, DataStore, KernelThread, MainThreadExecutor};
+///
+/// AsyncTaskObject::spawn_with_thread(|task: AsyncTaskObject| {
+///     let executor = MainThreadExecutor::new(&task);
+///
+///     let version: Option<String> = executor.run(|kernel: &KernelThread| {
+///         kernel.evaluate_str_as("$Version").unwrap()
+///     });
+///
+///     if let Some(version) = version {
+///         let mut data = DataStore::new();
+///         data.add_str(&version);
+///         task.raise_async_event("version", data);
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct MainThreadExecutor {
+    task: AsyncTaskObject,
+}
+
+assert_impl_all!(MainThreadExecutor: Send);
+
+impl MainThreadExecutor {
+    /// Name of the asynchronous event raised when a job is submitted.
+    pub const EVENT_NAME: &'static str = "RunOnMainThread";
+
+    /// Name of the exported LibraryLink function that runs a submitted job.
+    pub const RUN_FUNCTION_NAME: &'static str = "wll_run_main_thread_job";
+
+    /// Create an executor that raises events for `task`.
+    pub fn new(task: &AsyncTaskObject) -> Self {
+        MainThreadExecutor {
+            task: AsyncTaskObject::from_unowned_id(task.id()),
+        }
+    }
+
+    /// Submit `f` to be run on the main Kernel thread, returning a receiver for its
+    /// result.
+    ///
+    /// The sender of the returned channel is dropped without sending a value if `f`
+    /// panics. If the task is removed before the job is run, no value is ever sent; use
+    /// [`CancellationToken::recv_or_cancelled()`] to stop waiting when that happens.
+    pub fn submit<F, R>(&self, f: F) -> mpsc::Receiver<R>
+    where
+        F: FnOnce(&KernelThread) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (_, receiver) = self.submit_job(f);
+
+        receiver
+    }
+
+    /// Run `f` on the main Kernel thread, blocking until it has finished.
+    ///
+    /// If this is called from the main Kernel thread, `f` is run immediately, instead of
+    /// waiting for a job that could only be run by the blocked thread.
+    ///
+    /// Returns `None` if `f` panicked, or if the task was removed before `f` was run.
+    pub fn run<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&KernelThread) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if let Some(kernel) = KernelThread::current() {
+            return call_and_catch_panic(panic::AssertUnwindSafe(|| f(&kernel))).ok();
+        }
+
+        let (id, receiver) = self.submit_job(f);
+
+        let result = self.task.cancellation_token().recv_or_cancelled(&receiver);
+
+        if result.is_none() {
+            // The task was removed, so the job will never be run.
+            MAIN_THREAD_JOBS.lock().unwrap().remove(&id);
+        }
+
+        result
+    }
+
+    /// Evaluate `expr` on the main Kernel thread, blocking until the evaluation has
+    /// finished. See [`MainThreadExecutor::run()`] and [`KernelThread::try_evaluate()`].
+    pub fn evaluate(&self, expr: Expr) -> Result<Expr, String> {
+        self.run(move |kernel: &KernelThread| kernel.try_evaluate(&expr))
+            .unwrap_or_else(|| {
+                Err("MainThreadExecutor: expression was not evaluated".to_owned())
+            })
+    }
+
+    fn submit_job<F, R>(&self, f: F) -> (sys::mint, mpsc::Receiver<R>)
+    where
+        F: FnOnce(&KernelThread) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        let job: MainThreadJob = Box::new(move |kernel: &KernelThread| {
+            // Ignore the error if the receiver was dropped.
+            let _ = sender.send(f(kernel));
+        });
+
+        let id = NEXT_MAIN_THREAD_JOB_ID.fetch_add(1, Ordering::SeqCst);
+
+        MAIN_THREAD_JOBS.lock().unwrap().insert(id, job);
+
+        let mut data = DataStore::new();
+        data.add_i64(id);
+
+        self.task.raise_async_event(Self::EVENT_NAME, data);
+
+        (id, receiver)
+    }
+}
+
+impl Clone for MainThreadExecutor {
+    fn clone(&self) -> Self {
+        MainThreadExecutor::new(&self.task)
+    }
+}
+
+/// Run the job submitted by [`MainThreadExecutor`] with the specified ID.
+///
+/// This is exported as [`MainThreadExecutor::RUN_FUNCTION_NAME`] by
+/// [`export_main_thread_executor!`][crate::export_main_thread_executor], and is called
+/// by the Wolfram Language handler of [`MainThreadExecutor::EVENT_NAME`] events.
+pub(crate) fn run_main_thread_job(kernel: &KernelThread, id: sys::mint) {
+    // Don't hold the lock while the job is running, so that it can submit more jobs.
+    let job = MAIN_THREAD_JOBS.lock().unwrap().remove(&id);
+
+    if let Some(job) = job {
+        job(kernel);
+    }
+}
+
+/// Export the [`wll_run_main_thread_job`][MainThreadExecutor::RUN_FUNCTION_NAME]
+/// function, which runs jobs submitted to a [`MainThreadExecutor`].
+///
+/// A library that uses [`MainThreadExecutor`] must invoke this macro once.
+///
+/// # Example
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link as wll;
+///
+/// wll::export_main_thread_executor!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! export_main_thread_executor {
+    () => {
+        const _: () = {
+            #[no_mangle]
+            pub unsafe extern "C" fn wll_run_main_thread_job(
+                lib: $crate::sys::WolframLibraryData,
+                argc: $crate::sys::mint,
+                args: *mut $crate::sys::MArgument,
+                res: $crate::sys::MArgument,
+            ) -> std::os::raw::c_int {
+                $crate::macro_utils::run_main_thread_job(lib, args, argc, res)
+            }
+        };
+    };
+}
+
+//======================================
+// Futures
+//======================================
+
+/// Background executor shared by all tasks spawned using
+/// [`AsyncTaskObject::spawn_future()`] and [`AsyncTaskObject::spawn_stream()`].
+#[cfg(feature = "futures")]
+static EXECUTOR: once_cell::sync::Lazy<futures::executor::ThreadPool> =
+    once_cell::sync::Lazy::new(|| {
+        futures::executor::ThreadPool::builder()
+            .name_prefix("wolfram-library-link-async-")
+            .create()
+            .expect("unable to create async task thread pool")
+    });
+
+#[cfg(feature = "futures")]
+impl AsyncTaskObject {
+    /// Spawn a new Wolfram Language asynchronous task that runs `future` on a shared
+    /// background executor.
+    ///
+    /// When `future` completes, its output is raised as a `"data"` event, followed
+    /// by a `"done"` event with no data. If `future` panics, an `"error"` event is
+    /// raised instead, with the same data as the `"RustPanic"` event described in
+    /// [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread]. The task is
+    /// removed after the final event.
+    ///
+    /// Unlike [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], tasks
+    /// spawned using this method share a small pool of background threads, so many
+    /// concurrent tasks can be spawned cheaply.
+    ///
+    /// Like `spawn_with_thread()`, this method should be used within a LibraryLink
+    /// function that was called via `` Internal`CreateAsynchronousTask ``, and that
+    /// function should return the [`id()`][AsyncTaskObject::id] of the new task.
+    ///
+    /// *Requires the `"futures"` feature.*
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+    ///
+    /// #[wll::export]
+    /// fn start_download() -> mint {
+    ///     let task = AsyncTaskObject::spawn_future(async {
+    ///         let mut data = DataStore::new();
+    ///         data.add_str("downloaded");
+    ///         data
+    ///     });
+    ///
+    ///     task.id()
+    /// }
+    /// # }
+    /// ```
+    pub fn spawn_future<F>(future: F) -> Self
+    where
+        F: std::future::Future<Output = DataStore> + Send + 'static,
+    {
+        Self::spawn_stream(futures::stream::once(future))
+    }
+
+    /// Spawn a new Wolfram Language asynchronous task that runs `stream` on a shared
+    /// background executor.
+    ///
+    /// Each item yielded by `stream` is raised as a `"data"` event. When `stream`
+    /// finishes, a `"done"` event with no data is raised. If `stream` panics, an
+    /// `"error"` event is raised instead, with the same data as the `"RustPanic"` event
+    /// described in [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread]. The
+    /// task is removed after the final event.
+    ///
+    /// If the task is removed before `stream` finishes, e.g. using
+    /// [`RemoveAsynchronousTask`][ref/RemoveAsynchronousTask]<sub>WL</sub>, `stream` is
+    /// dropped without being polled again.
+    ///
+    /// See also [`spawn_future()`][AsyncTaskObject::spawn_future].
+    ///
+    /// *Requires the `"futures"` feature.*
+    ///
+    /// [ref/RemoveAsynchronousTask]: https://reference.wolfram.com/language/ref/RemoveAsynchronousTask.html
+    pub fn spawn_stream<S>(stream: S) -> Self
+    where
+        S: futures::Stream<Item = DataStore> + Send + 'static,
+    {
+        use std::task::Poll;
+
+        let task = AsyncTaskObject::new_without_thread();
+        let id = task.id();
+
+        let run = async move {
+            futures::pin_mut!(stream);
+
+            loop {
+                // Stop polling `stream` if the task has been removed, e.g. using
+                // `RemoveAsynchronousTask`. `stream` is dropped when this returns.
+                if !task.is_alive() {
+                    return;
+                }
+
+                // Poll `stream` inside `call_and_catch_panic()`, so that a panic is
+                // described the same way as a "RustPanic" event.
+                let next = futures::future::poll_fn(|cx| {
+                    match call_and_catch_panic(panic::AssertUnwindSafe(|| {
+                        stream.as_mut().poll_next(cx)
+                    })) {
+                        Ok(Poll::Ready(item)) => Poll::Ready(Ok(item)),
+                        Ok(Poll::Pending) => Poll::Pending,
+                        Err(panic) => Poll::Ready(Err(panic)),
+                    }
+                })
+                .await;
+
+                match next {
+                    Ok(Some(data)) => task.raise_async_event("data", data),
+                    Ok(None) => {
+                        task.raise_async_event("done", DataStore::new());
+                        break;
+                    },
+                    Err(panic) => {
+                        task.raise_async_event("error", panic.to_data_store());
+                        break;
+                    },
+                }
+            }
+
+            // `task` is dropped here, which removes the task.
+        };
+
+        EXECUTOR.spawn_ok(run);
+
+        AsyncTaskObject::from_unowned_id(id)
+    }
 }
 
 fn spawn_async_task_with_thread<F>(task: F) -> AsyncTaskObject
@@ -125,7 +1128,8 @@ where
     //       with this bound.
     F: FnMut(AsyncTaskObject) + Send + 'static + panic::UnwindSafe,
 {
-    // FIXME: This box is being leaked. Where is an appropriate place to drop it?
+    // Ownership of this box is reclaimed by async_task_thread_trampoline(), which
+    // drops it when the task closure returns.
     let boxed_closure = Box::into_raw(Box::new(task));
 
     // Spawn a background thread using the user closure.
@@ -136,7 +1140,7 @@ where
         )
     };
 
-    AsyncTaskObject(task_id)
+    AsyncTaskObject::from_unowned_id(task_id)
 }
 
 unsafe extern "C" fn async_task_thread_trampoline<F>(
@@ -145,20 +1149,24 @@ unsafe extern "C" fn async_task_thread_trampoline<F>(
 ) where
     F: FnMut(AsyncTaskObject) + Send + 'static + panic::UnwindSafe,
 {
-    let boxed_closure: &mut F = &mut *(boxed_closure as *mut F);
+    // The runtime calls this function exactly once for each task, so take back
+    // ownership of the closure, ensuring that it is dropped when the task ends, even
+    // if it panics.
+    let mut boxed_closure: Box<F> = Box::from_raw(boxed_closure as *mut F);
 
-    // static_assertions::assert_impl_all!(F: panic::UnwindSafe);
-
-    // Catch any panics which occur.
+    // Catch any panics which occur, and report them to the Wolfram Language as a
+    // "RustPanic" event.
     //
     // Use AssertUnwindSafe because:
     //   1) `F` is already required to implement UnwindSafe by the definition of AsyncTask.
     //   2) We don't introduce any new potential unwind safety with our minimal closure
     //      here.
-    match panic::catch_unwind(panic::AssertUnwindSafe(|| {
-        boxed_closure(AsyncTaskObject(async_object_id))
-    })) {
-        Ok(()) => (),
-        Err(_) => (),
+    let result = call_and_catch_panic(panic::AssertUnwindSafe(|| {
+        boxed_closure(AsyncTaskObject::from_unowned_id(async_object_id))
+    }));
+
+    if let Err(panic) = result {
+        AsyncTaskObject::from_unowned_id(async_object_id)
+            .raise_async_event("RustPanic", panic.to_data_store());
     }
 }
@@ -0,0 +1,461 @@
+//! Callback functions.
+//!
+//! Callback functions allow a library to call a Wolfram Language
+//! [`CompiledFunction`][ref/CompiledFunction]<sub>WL</sub> directly, without using a
+//! WSTP link to transfer the arguments and result.
+//!
+//! Using [`register_library_callback_manager()`], a library can register a callback
+//! function, which will recieve a [`CallbackConnection`] each time
+//! [`ConnectLibraryCallbackFunction`][ref/ConnectLibraryCallbackFunction]<sub>WL</sub>
+//! is used to connect a compiled function to the manager.
+//!
+//! The connection is typically converted into a typed [`CallbackFunction`] handle, which
+//! can then be called like an ordinary Rust function.
+//!
+//! # Example
+//!
+//! Register a callback manager named `"objective"`, and call the connected function
+//! from an exported function:
+//!
+//! ```
+//! # mod scope {
+//! use std::sync::Mutex;
+//!
+//! use wolfram_library_link::{
+//!     self as wll,
+//!     callback::{CallbackConnection, CallbackFunction},
+//!     export,
+//! };
+//!
+//! static OBJECTIVE: Mutex<Option<CallbackFunction<fn(f64) -> f64>>> = Mutex::new(None);
+//!
+//! fn connect_objective(connection: CallbackConnection) -> bool {
+//!     match CallbackFunction::new(connection) {
+//!         Ok(function) => {
+//!             // Replacing a previously connected function releases it.
+//!             *OBJECTIVE.lock().unwrap() = Some(function);
+//!             true
+//!         },
+//!         // The compiled function does not have the signature `Real -> Real`.
+//!         Err(_) => false,
+//!     }
+//! }
+//!
+//! #[wll::init]
+//! fn init() {
+//!     wll::callback::register_library_callback_manager("objective", connect_objective);
+//! }
+//!
+//! /// Find the minimum of the objective function at integer points in `[start, end]`.
+//! #[export]
+//! fn minimize_objective(start: i64, end: i64) -> f64 {
+//!     let objective = OBJECTIVE.lock().unwrap();
+//!     let objective = objective.as_ref().expect("no objective function is connected");
+//!
+//!     (start..=end)
+//!         .map(|x| objective.call(x as f64).expect("objective function failed"))
+//!         .fold(f64::INFINITY, f64::min)
+//! }
+//! # }
+//! ```
+//!
+//! ```wolfram
+//! ConnectLibraryCallbackFunction["objective", Compile[{{x, _Real}}, (x - 3)^2]]
+//!
+//! LibraryFunctionLoad["...", "minimize_objective", {Integer, Integer}, Real][0, 10]
+//! ```
+//!
+//! # Related links
+//!
+//! * [Library Callback Functions] section of the LibraryLink documentation.
+//!
+//! [Library Callback Functions]: https://reference.wolfram.com/language/LibraryLink/tutorial/InteractionWithWolframLanguage.html#1078484017
+//! [ref/CompiledFunction]: https://reference.wolfram.com/language/ref/CompiledFunction.html
+//! [ref/ConnectLibraryCallbackFunction]: https://reference.wolfram.com/language/ref/ConnectLibraryCallbackFunction.html
+
+use std::{fmt, marker::PhantomData, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use ref_cast::RefCast;
+
+use crate::{
+    managed::{self, Registry, MAX_MANAGERS},
+    rtl,
+    sys::{self, mcomplex, mint, mreal, MArgument},
+    FromArg, IntoArg, Tensor, TensorType,
+};
+
+/// A compiled function that has been connected to a library callback manager.
+///
+/// This is passed to the function registered using
+/// [`register_library_callback_manager()`].
+pub struct CallbackConnection {
+    id: mint,
+    /// The `{type, rank}` of each parameter, followed by the `{type, rank}` of the
+    /// result.
+    types: Vec<(mint, mint)>,
+}
+
+/// Typed handle to a Wolfram Language function connected to a library callback manager.
+///
+/// `F` is a function pointer type, like `fn(f64, f64) -> f64`, that describes the
+/// parameter and return types of the connected function. The parameter and return types
+/// must implement [`CallbackType`].
+///
+/// Use [`CallbackFunction::new()`] to construct a `CallbackFunction` from a
+/// [`CallbackConnection`], and `call()` to call it.
+///
+/// The connected function is released when the `CallbackFunction` is dropped.
+///
+/// # Threading
+///
+/// A `CallbackFunction` may only be called from the thread that the Wolfram Kernel used
+/// to call the current library function.
+pub struct CallbackFunction<F> {
+    id: mint,
+    _sig: PhantomData<F>,
+}
+
+/// Trait implemented for types that can be used as the parameter or return type of a
+/// [`CallbackFunction`].
+///
+/// Those types are:
+///
+///   * [`bool`]
+///   * [`mint`]
+///   * [`mreal`]
+///   * [`mcomplex`]
+///   * [`Tensor<T>`]
+pub trait CallbackType: private::Sealed {
+    /// Returns true if this type matches the compiled function parameter or result with
+    /// the specified `type_` and `rank`.
+    ///
+    /// `type_` is one of the [`MType_*`][sys::MType_Integer] constants.
+    fn matches(type_: mint, rank: mint) -> bool;
+
+    /// Release any data owned by `arg` after it has been passed to a callback function.
+    #[doc(hidden)]
+    unsafe fn release_arg(_arg: MArgument) {}
+}
+
+/// Trait implemented for the function pointer types that can be used as the `F`
+/// parameter of a [`CallbackFunction<F>`].
+///
+/// This trait is implemented for `fn(A1, A2, ...) -> R` with up to 6 parameters, where
+/// each parameter type implements [`CallbackType`] and [`IntoArg`], and the return
+/// type implements [`CallbackType`] and [`FromArg`].
+pub trait CallbackSignature: private::Sealed {
+    /// Returns true if this signature matches the `{type, rank}` of each parameter,
+    /// followed by the `{type, rank}` of the result.
+    fn matches(types: &[(mint, mint)]) -> bool;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Register a new callback function for handling connections to the callback manager
+/// named `name`.
+///
+/// `connect` is called each time
+/// [`ConnectLibraryCallbackFunction`][ref/ConnectLibraryCallbackFunction]<code>[<i>name</i>, <i>cf</i>]</code>
+/// is evaluated. It should return `true` if the connection was accepted.
+///
+/// See the [module documentation][crate::callback] for an example.
+///
+/// # Panics
+///
+/// This function will panic if a callback manager named `name` has already been
+/// registered, or if [`MAX_MANAGERS`][crate::managed::MAX_MANAGERS] callback managers
+/// have been registered.
+///
+/// [ref/ConnectLibraryCallbackFunction]: https://reference.wolfram.com/language/ref/ConnectLibraryCallbackFunction.html
+pub fn register_library_callback_manager(
+    name: &str,
+    connect: fn(CallbackConnection) -> bool,
+) {
+    register_using_next_slot(name, connect)
+}
+
+impl CallbackConnection {
+    /// Get the ID of the connected function.
+    pub fn id(&self) -> mint {
+        self.id
+    }
+
+    /// Get the `{type, rank}` of each parameter of the connected function.
+    ///
+    /// Each type is one of the [`MType_*`][sys::MType_Integer] constants.
+    pub fn parameter_types(&self) -> &[(mint, mint)] {
+        let (_, params) = self.types.split_last().expect("empty callback signature");
+
+        params
+    }
+
+    /// Get the `{type, rank}` of the result of the connected function.
+    pub fn result_type(&self) -> (mint, mint) {
+        *self.types.last().expect("empty callback signature")
+    }
+}
+
+impl fmt::Debug for CallbackConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallbackConnection")
+            .field("id", &self.id)
+            .field("parameter_types", &self.parameter_types())
+            .field("result_type", &self.result_type())
+            .finish()
+    }
+}
+
+impl<F: CallbackSignature> CallbackFunction<F> {
+    /// Construct a typed handle to the function connected by `connection`.
+    ///
+    /// Returns the `connection` as an error if the parameter and result types of the
+    /// connected function do not match `F`.
+    pub fn new(connection: CallbackConnection) -> Result<Self, CallbackConnection> {
+        if !F::matches(&connection.types) {
+            return Err(connection);
+        }
+
+        Ok(CallbackFunction {
+            id: connection.id,
+            _sig: PhantomData,
+        })
+    }
+}
+
+impl<F> CallbackFunction<F> {
+    /// Get the ID of the connected function.
+    pub fn id(&self) -> mint {
+        self.id
+    }
+
+    /// Call the connected function with the raw LibraryLink [`MArgument`] fields.
+    ///
+    /// *LibraryLink C API Documentation:* [`callLibraryCallbackFunction`](https://reference.wolfram.com/language/LibraryLink/ref/callback/callLibraryCallbackFunction.html)
+    unsafe fn call_raw(
+        &self,
+        args: &mut [MArgument],
+        res: MArgument,
+    ) -> Result<(), sys::errcode_t> {
+        let err_code: sys::errcode_t = rtl::callLibraryCallbackFunction(
+            self.id,
+            args.len() as mint,
+            args.as_mut_ptr(),
+            res,
+        );
+
+        if err_code != sys::LIBRARY_NO_ERROR as sys::errcode_t {
+            return Err(err_code);
+        }
+
+        Ok(())
+    }
+}
+
+impl<F> Drop for CallbackFunction<F> {
+    fn drop(&mut self) {
+        // *LibraryLink C API Documentation:* [`releaseLibraryCallbackFunction`](https://reference.wolfram.com/language/LibraryLink/ref/callback/releaseLibraryCallbackFunction.html)
+        unsafe {
+            rtl::releaseLibraryCallbackFunction(self.id);
+        }
+    }
+}
+
+impl<F> fmt::Debug for CallbackFunction<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallbackFunction")
+            .field("id", &self.id)
+            .field("signature", &std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+//======================================
+// CallbackType impls
+//======================================
+
+macro_rules! impl_scalar_callback_type {
+    ($($type:ty => $mtype:expr),* $(,)?) => {
+        $(
+            impl private::Sealed for $type {}
+
+            impl CallbackType for $type {
+                fn matches(type_: mint, rank: mint) -> bool {
+                    type_ == $mtype as mint && rank == 0
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_callback_type!(
+    bool => sys::MType_Boolean,
+    mint => sys::MType_Integer,
+    mreal => sys::MType_Real,
+    mcomplex => sys::MType_Complex,
+);
+
+impl<T: TensorType> private::Sealed for Tensor<T> {}
+
+/// Tensors passed as arguments to a callback function are freed after the call
+/// returns.
+impl<T: TensorType> CallbackType for Tensor<T> {
+    fn matches(type_: mint, rank: mint) -> bool {
+        type_ == T::TYPE.as_raw() && rank > 0
+    }
+
+    unsafe fn release_arg(arg: MArgument) {
+        rtl::MTensor_free(*arg.tensor)
+    }
+}
+
+//======================================
+// CallbackFunction::call() impls
+//======================================
+
+/// Storage for a single [`MArgument`] value, large enough to store any type, including
+/// an `mcomplex`.
+type Slot = [u64; 2];
+
+fn slot_arg(slot: &mut Slot) -> MArgument {
+    // Every MArgument field is a pointer, so it doesn't matter which one is set.
+    MArgument {
+        integer: slot.as_mut_ptr() as *mut mint,
+    }
+}
+
+macro_rules! impl_callback_call {
+    ($($arg:ident: $type:ident),*) => {
+        impl<$($type,)* R> private::Sealed for fn($($type),*) -> R {}
+
+        impl<$($type: CallbackType,)* R: CallbackType> CallbackSignature
+            for fn($($type),*) -> R
+        {
+            fn matches(types: &[(mint, mint)]) -> bool {
+                match types {
+                    [$($arg,)* result] => {
+                        $( $type::matches($arg.0, $arg.1) && )* R::matches(result.0, result.1)
+                    },
+                    _ => false,
+                }
+            }
+        }
+
+        impl<$($type,)* R> CallbackFunction<fn($($type),*) -> R>
+        where
+            $($type: CallbackType + IntoArg,)*
+            R: CallbackType + for<'a> FromArg<'a>,
+        {
+            /// Call the connected function.
+            ///
+            /// Returns the error code returned by the Wolfram Kernel if the call failed.
+            #[allow(unused_mut, unused_variables)]
+            pub fn call(&self, $($arg: $type),*) -> Result<R, sys::errcode_t> {
+                let argc = <[&str]>::len(&[$(stringify!($arg)),*]);
+
+                let mut slots: Vec<Slot> = vec![[0; 2]; argc];
+                let mut args: Vec<MArgument> = slots.iter_mut().map(slot_arg).collect();
+
+                let mut res_slot: Slot = [0; 2];
+                let res = slot_arg(&mut res_slot);
+
+                unsafe {
+                    let mut arg_fields = args.iter();
+                    $( $arg.into_arg(*arg_fields.next().unwrap()); )*
+
+                    let result = self.call_raw(&mut args, res);
+
+                    let mut arg_fields = args.iter();
+                    $( $type::release_arg(*arg_fields.next().unwrap()); )*
+
+                    result.map(|()| R::from_arg(&res))
+                }
+            }
+        }
+    };
+}
+
+impl_callback_call!();
+impl_callback_call!(a1: A1);
+impl_callback_call!(a1: A1, a2: A2);
+impl_callback_call!(a1: A1, a2: A2, a3: A3);
+impl_callback_call!(a1: A1, a2: A2, a3: A3, a4: A4);
+impl_callback_call!(a1: A1, a2: A2, a3: A3, a4: A4, a5: A5);
+impl_callback_call!(a1: A1, a2: A2, a3: A3, a4: A4, a5: A5, a6: A6);
+
+//======================================
+// C wrapper functions
+//======================================
+
+type ConnectFn = fn(CallbackConnection) -> bool;
+
+// See the "Implementation note" on `managed::REGISTRY` for an explanation of this static /
+// "slot" system.
+static REGISTRY: Lazy<Mutex<Registry<ConnectFn>>> =
+    Lazy::new(|| Mutex::new(Registry::new()));
+
+fn register_using_next_slot(name: &str, connect: ConnectFn) {
+    managed::register_using_next_slot(
+        &REGISTRY,
+        "library callback manager",
+        name,
+        connect,
+        |name_cstr, index| unsafe {
+            rtl::registerLibraryCallbackManager(
+                name_cstr.as_ptr(),
+                Some(SLOT_FNS[index / 16][index % 16]),
+            )
+        },
+    )
+}
+
+//--------------------------
+// Static slot_fn functions
+//--------------------------
+
+unsafe fn call_callback_in_slot(slot: usize, id: mint, arg_types: sys::MTensor) -> bool {
+    let user_fn: ConnectFn = match REGISTRY.lock().unwrap().get(slot) {
+        Some(func) => func,
+        None => return false,
+    };
+
+    // `arg_types` is an `n x 2` matrix of `{type, rank}` pairs, and is owned by the
+    // Kernel.
+    let arg_types: &Tensor<mint> = Tensor::ref_cast(&arg_types);
+
+    let types = arg_types
+        .as_slice()
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect();
+
+    user_fn(CallbackConnection { id, types })
+}
+
+type SlotFn =
+    unsafe extern "C" fn(sys::WolframLibraryData, mint, sys::MTensor) -> sys::mbool;
+
+unsafe extern "C" fn slot_fn<const INDEX: usize>(
+    // Assume this library is already initialized.
+    _: sys::WolframLibraryData,
+    id: mint,
+    arg_types: sys::MTensor,
+) -> sys::mbool {
+    let result = crate::catch_panic::call_and_catch_panic(|| {
+        call_callback_in_slot(INDEX, id, arg_types)
+    });
+
+    match result {
+        Ok(accepted) => sys::mbool::from(accepted),
+        Err(panic) => {
+            crate::catch_panic::set_last_panic(panic);
+            sys::mbool::from(false)
+        },
+    }
+}
+
+/// `SLOT_FNS[index / 16][index % 16]` is the wrapper function for the slot `index` of
+/// `REGISTRY`.
+const SLOT_FNS: [[SlotFn; 16]; MAX_MANAGERS / 16] = managed::slot_fns!(slot_fn);
@@ -13,11 +13,26 @@ use backtrace::Backtrace;
 
 use once_cell::sync::Lazy;
 
-use crate::expr::{Expr, Symbol};
+use crate::{
+    expr::{Expr, Symbol},
+    DataStore, KernelThread, MessageName,
+};
 
 static CAUGHT_PANICS: Lazy<Mutex<HashMap<ThreadId, (Instant, CaughtPanic)>>> =
     Lazy::new(|| Default::default());
 
+/// The most recent panic caught in a native [`#[export]`][crate::export] function.
+///
+/// See [`crate::last_panic()`].
+static LAST_PANIC: Lazy<Mutex<Option<CaughtPanic>>> = Lazy::new(|| Mutex::new(None));
+
+/// Message issued with the message of a panic caught in a native function.
+static RUST_PANIC: MessageName = MessageName::new("LibraryFunction::rustpanic")
+    .with_template("Rust LibraryLink function panic: `1`");
+
+#[cfg(feature = "automate-function-loading-boilerplate")]
+crate::register_messages!(RUST_PANIC);
+
 /// Information from a caught panic.
 ///
 /// Returned by [`call_and_catch_panic()`].
@@ -38,6 +53,17 @@ pub struct CaughtPanic {
 }
 
 impl CaughtPanic {
+    /// Issue the message of this panic as a `LibraryFunction::rustpanic` message,
+    /// returning `false` if the message could not be issued.
+    pub(crate) fn issue_message(&self, kernel: &KernelThread) -> bool {
+        let message = self
+            .message
+            .clone()
+            .unwrap_or("Rust panic (no message)".into());
+
+        RUST_PANIC.issue(kernel, &[Expr::string(message)])
+    }
+
     pub(crate) fn to_pretty_expr(&self) -> Expr {
         let CaughtPanic {
             message,
@@ -102,6 +128,40 @@ impl CaughtPanic {
     }
 }
 
+impl CaughtPanic {
+    /// Convert this panic into a [`DataStore`], for use as the data of an
+    /// asynchronous event.
+    ///
+    /// The returned `DataStore` has the named string elements `"message"` and
+    /// `"location"`, and, if backtraces have been requested by the user, a
+    /// `"backtrace"` element.
+    pub(crate) fn to_data_store(&self) -> DataStore {
+        let mut data = DataStore::new();
+
+        data.add_named_str(
+            "message",
+            self.message.as_deref().unwrap_or("Rust panic (no message)"),
+        );
+        data.add_named_str("location", self.location.as_deref().unwrap_or("Unknown"));
+
+        // See the comment in `to_pretty_expr()`.
+        #[cfg(feature = "panic-failure-backtraces")]
+        if should_show_backtrace() {
+            let backtrace = match self.backtrace.clone() {
+                Some(mut backtrace) => {
+                    backtrace.resolve();
+                    format!("{:?}", backtrace)
+                },
+                None => "<unable to capture backtrace>".to_owned(),
+            };
+
+            data.add_named_str("backtrace", &backtrace);
+        }
+
+        data
+    }
+}
+
 fn should_show_backtrace() -> bool {
     std::env::var(crate::BACKTRACE_ENV_VAR).is_ok()
 }
@@ -206,6 +266,17 @@ fn display_backtrace(bt: Option<Backtrace>) -> Expr {
     )])
 }
 
+/// Record `panic` as the most recent panic caught in a native function.
+pub(crate) fn set_last_panic(panic: CaughtPanic) {
+    if let Ok(mut last) = LAST_PANIC.lock() {
+        *last = Some(panic);
+    }
+}
+
+pub(crate) fn last_panic() -> Option<CaughtPanic> {
+    LAST_PANIC.lock().ok()?.clone()
+}
+
 /// Call `func` and catch any unwinding panic which occurs during that call, returning
 /// information from the caught panic in the form of a `CaughtPanic`.
 ///
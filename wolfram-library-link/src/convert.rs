@@ -0,0 +1,464 @@
+//! Conversions between Rust types and Wolfram Language expressions.
+
+use std::{collections::HashMap, fmt, hash::BuildHasher};
+
+use crate::{
+    expr::{Expr, ExprKind, Normal, Symbol},
+    TensorDataType,
+};
+
+/// Trait implemented for types that can be constructed from a Wolfram Language
+/// expression.
+///
+/// This trait is used to decode the arguments of
+/// [`#[export(wstp)]`][crate::export#exportwstp] functions that declare typed parameters.
+///
+/// # Representation
+///
+/// Rust type                   | Wolfram Language expression
+/// ----------------------------|---------------------------------------
+/// [`bool`]                    | `True` or `False`
+/// [`i64`], [`u8`], etc.       | [`Integer`][ref/Integer]
+/// [`f64`], [`f32`]            | [`Real`][ref/Real] or [`Integer`][ref/Integer]
+/// [`String`]                  | [`String`][ref/String]
+/// [`()`][unit]                | `Null`
+/// [`Vec<T>`]                  | `{...}`
+/// [`HashMap<String, T>`]      | `<| "key" -> ..., ... |>`
+/// [`Option<T>`]               | [`Missing[...]`][ref/Missing] for `None`
+/// `(A, B, ...)`               | `{a, b, ...}`
+/// [`Expr`]                    | any expression
+///
+/// # Derive
+///
+/// `FromExpr` can be derived for structs and enums using `#[derive(FromExpr)]`:
+///
+/// * structs with named fields are decoded from an [`Association`][ref/Association]
+///   with a key for each field. A missing key is decoded as `Missing["KeyAbsent", key]`,
+///   so `Option<T>` fields may be omitted.
+/// * tuple structs are decoded from a list with one element per field.
+/// * unit structs are decoded from `Null`.
+/// * enums with only unit variants are decoded from a string containing the variant
+///   name.
+///
+/// ```
+/// use wolfram_library_link::{expr::{Expr, Symbol}, FromExpr};
+///
+/// #[derive(FromExpr)]
+/// struct Config {
+///     iterations: i64,
+///     tolerance: Option<f64>,
+/// }
+///
+/// // <| "iterations" -> 10 |>
+/// let expr = Expr::normal(Symbol::new("System`Association"), vec![
+///     Expr::rule(Expr::string("iterations"), Expr::from(10)),
+/// ]);
+///
+/// let config = Config::from_expr(&expr).unwrap();
+///
+/// assert_eq!(config.iterations, 10);
+/// assert_eq!(config.tolerance, None);
+/// ```
+///
+/// [ref/Integer]: https://reference.wolfram.com/language/ref/Integer.html
+/// [ref/Real]: https://reference.wolfram.com/language/ref/Real.html
+/// [ref/String]: https://reference.wolfram.com/language/ref/String.html
+/// [ref/Missing]: https://reference.wolfram.com/language/ref/Missing.html
+/// [ref/Association]: https://reference.wolfram.com/language/ref/Association.html
+pub trait FromExpr: Sized {
+    /// Construct a value of this type from `expr`.
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError>;
+
+    /// Numeric type that [`KernelThread::evaluate_str_as()`][crate::KernelThread::evaluate_str_as]
+    /// requests from the `evaluateExpression()` runtime function before converting the
+    /// result using [`FromExpr::from_expr()`].
+    ///
+    /// If `None`, the code is evaluated over the WSTP link instead.
+    #[doc(hidden)]
+    const NATIVE_TYPE: Option<TensorDataType> = None;
+}
+
+/// Trait implemented for types that can be converted into a Wolfram Language
+/// expression.
+///
+/// This trait is used to encode the return value of
+/// [`#[export(wstp)]`][crate::export#exportwstp] functions that declare typed parameters.
+///
+/// See [`FromExpr`] for a description of the expression representation of each type.
+/// `None` is encoded as `Missing[]`.
+///
+/// `ToExpr` can be derived for the same kinds of types as [`FromExpr`] using
+/// `#[derive(ToExpr)]`.
+///
+/// ```
+/// use wolfram_library_link::ToExpr;
+///
+/// #[derive(ToExpr)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let point = Point { x: 1.5, y: 2.5 };
+///
+/// assert_eq!(
+///     point.to_expr().to_string(),
+///     r#"System`Association[System`Rule["x", 1.5], System`Rule["y", 2.5]]"#
+/// );
+/// ```
+pub trait ToExpr {
+    /// Construct an expression representing `self`.
+    fn to_expr(&self) -> Expr;
+}
+
+/// Error returned by [`FromExpr::from_expr()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromExprError {
+    message: String,
+}
+
+impl FromExprError {
+    /// Construct a new error with the specified message.
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        FromExprError {
+            message: message.into(),
+        }
+    }
+
+    /// Construct an error for an expression that did not have the expected form.
+    ///
+    /// ```
+    /// use wolfram_library_link::{expr::Expr, FromExprError};
+    ///
+    /// let error = FromExprError::expected("Integer", &Expr::string("five"));
+    ///
+    /// assert_eq!(error.to_string(), r#"expected Integer, got: "five""#);
+    /// ```
+    pub fn expected(description: &str, got: &Expr) -> Self {
+        FromExprError::new(format!("expected {}, got: {}", description, got))
+    }
+
+    /// Add `context` to the start of the message of this error.
+    ///
+    /// This is used to indicate where in a larger expression the error occurred.
+    pub fn context<S: fmt::Display>(self, context: S) -> Self {
+        FromExprError::new(format!("{}: {}", context, self.message))
+    }
+}
+
+impl fmt::Display for FromExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FromExprError {}
+
+//======================================
+// Utilities
+//======================================
+
+fn normal_with_head<'e>(expr: &'e Expr, head: &str) -> Option<&'e Normal> {
+    match expr.kind() {
+        ExprKind::Normal(normal) if normal.has_head(&Symbol::new(head)) => Some(normal),
+        _ => None,
+    }
+}
+
+fn list_elements(expr: &Expr) -> Result<&[Expr], FromExprError> {
+    match normal_with_head(expr, "System`List") {
+        Some(list) => Ok(list.elements()),
+        None => Err(FromExprError::expected("List", expr)),
+    }
+}
+
+/// Returns the `key -> value` rules in an `Association[...]` expression.
+pub fn association_rules(expr: &Expr) -> Result<Vec<(&Expr, &Expr)>, FromExprError> {
+    let assoc = match normal_with_head(expr, "System`Association") {
+        Some(assoc) => assoc,
+        None => return Err(FromExprError::expected("Association", expr)),
+    };
+
+    assoc
+        .elements()
+        .iter()
+        .map(|rule| {
+            let rule_elements = normal_with_head(rule, "System`Rule")
+                .or_else(|| normal_with_head(rule, "System`RuleDelayed"))
+                .map(Normal::elements);
+
+            match rule_elements {
+                Some([key, value]) => Ok((key, value)),
+                _ => Err(FromExprError::expected("Rule", rule)),
+            }
+        })
+        .collect()
+}
+
+//======================================
+// Impls
+//======================================
+
+impl FromExpr for Expr {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        Ok(expr.clone())
+    }
+}
+
+impl ToExpr for Expr {
+    fn to_expr(&self) -> Expr {
+        self.clone()
+    }
+}
+
+impl FromExpr for () {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::Symbol(sym) if sym.as_str() == "System`Null" => Ok(()),
+            _ => Err(FromExprError::expected("Null", expr)),
+        }
+    }
+}
+
+impl ToExpr for () {
+    fn to_expr(&self) -> Expr {
+        Expr::null()
+    }
+}
+
+impl FromExpr for bool {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::Symbol(sym) if sym.as_str() == "System`True" => Ok(true),
+            ExprKind::Symbol(sym) if sym.as_str() == "System`False" => Ok(false),
+            _ => Err(FromExprError::expected("True or False", expr)),
+        }
+    }
+}
+
+impl ToExpr for bool {
+    fn to_expr(&self) -> Expr {
+        Expr::from(*self)
+    }
+}
+
+macro_rules! integer_impls {
+    ($($ty:ty),*) => {
+        $(
+            impl FromExpr for $ty {
+                const NATIVE_TYPE: Option<TensorDataType> = Some(TensorDataType::Integer);
+
+                fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+                    match *expr.kind() {
+                        ExprKind::Integer(value) => <$ty>::try_from(value).map_err(|_| {
+                            FromExprError::new(format!(
+                                "integer {} is out of range for {}",
+                                value,
+                                stringify!($ty)
+                            ))
+                        }),
+                        _ => Err(FromExprError::expected("Integer", expr)),
+                    }
+                }
+            }
+
+            impl ToExpr for $ty {
+                /// # Panics
+                ///
+                /// This function will panic if `self` cannot be represented as an `i64`.
+                fn to_expr(&self) -> Expr {
+                    let value = i64::try_from(*self).unwrap_or_else(|_| {
+                        panic!("ToExpr: integer {} is out of range for i64", self)
+                    });
+
+                    Expr::from(value)
+                }
+            }
+        )*
+    };
+}
+
+integer_impls!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromExpr for f64 {
+    const NATIVE_TYPE: Option<TensorDataType> = Some(TensorDataType::Real);
+
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match *expr.kind() {
+            ExprKind::Real(value) => Ok(value.into_inner()),
+            ExprKind::Integer(value) => Ok(value as f64),
+            _ => Err(FromExprError::expected("Real", expr)),
+        }
+    }
+}
+
+impl ToExpr for f64 {
+    /// `NaN` is converted to `Indeterminate`.
+    fn to_expr(&self) -> Expr {
+        if self.is_nan() {
+            return Expr::symbol(Symbol::new("System`Indeterminate"));
+        }
+
+        Expr::real(*self)
+    }
+}
+
+impl FromExpr for f32 {
+    const NATIVE_TYPE: Option<TensorDataType> = Some(TensorDataType::Real);
+
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        f64::from_expr(expr).map(|value| value as f32)
+    }
+}
+
+impl ToExpr for f32 {
+    fn to_expr(&self) -> Expr {
+        f64::from(*self).to_expr()
+    }
+}
+
+impl FromExpr for String {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::String(string) => Ok(string.clone()),
+            _ => Err(FromExprError::expected("String", expr)),
+        }
+    }
+}
+
+impl ToExpr for String {
+    fn to_expr(&self) -> Expr {
+        Expr::string(self.as_str())
+    }
+}
+
+impl ToExpr for str {
+    fn to_expr(&self) -> Expr {
+        Expr::string(self)
+    }
+}
+
+impl<T: ToExpr + ?Sized> ToExpr for &T {
+    fn to_expr(&self) -> Expr {
+        (**self).to_expr()
+    }
+}
+
+impl<T: FromExpr> FromExpr for Vec<T> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        list_elements(expr)?
+            .iter()
+            .enumerate()
+            .map(|(index, elem)| {
+                T::from_expr(elem)
+                    .map_err(|err| err.context(format!("Part {}", index + 1)))
+            })
+            .collect()
+    }
+}
+
+impl<T: ToExpr> ToExpr for Vec<T> {
+    fn to_expr(&self) -> Expr {
+        self.as_slice().to_expr()
+    }
+}
+
+impl<T: ToExpr> ToExpr for [T] {
+    fn to_expr(&self) -> Expr {
+        Expr::list(self.iter().map(ToExpr::to_expr).collect())
+    }
+}
+
+impl<T: FromExpr, S: BuildHasher + Default> FromExpr for HashMap<String, T, S> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        association_rules(expr)?
+            .into_iter()
+            .map(|(key, value)| {
+                let key = String::from_expr(key)
+                    .map_err(|err| err.context("Association key"))?;
+
+                let value = T::from_expr(value)
+                    .map_err(|err| err.context(format!("Association key {:?}", key)))?;
+
+                Ok((key, value))
+            })
+            .collect()
+    }
+}
+
+impl<T: ToExpr, S> ToExpr for HashMap<String, T, S> {
+    /// The keys of the returned `Association` are sorted, so that the result does not
+    /// depend on the iteration order of the map.
+    fn to_expr(&self) -> Expr {
+        let mut entries: Vec<(&String, &T)> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let rules = entries
+            .into_iter()
+            .map(|(key, value)| Expr::rule(Expr::string(key.as_str()), value.to_expr()))
+            .collect();
+
+        Expr::normal(Symbol::new("System`Association"), rules)
+    }
+}
+
+impl<T: FromExpr> FromExpr for Option<T> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        if normal_with_head(expr, "System`Missing").is_some() {
+            return Ok(None);
+        }
+
+        T::from_expr(expr).map(Some)
+    }
+}
+
+impl<T: ToExpr> ToExpr for Option<T> {
+    fn to_expr(&self) -> Expr {
+        match self {
+            Some(value) => value.to_expr(),
+            None => Expr::normal(Symbol::new("System`Missing"), vec![]),
+        }
+    }
+}
+
+macro_rules! tuple_impls {
+    ($($len:literal => ($($name:ident : $index:tt),+);)*) => {
+        $(
+            impl<$($name: FromExpr),+> FromExpr for ($($name,)+) {
+                fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+                    match list_elements(expr)? {
+                        [$($name),+] => Ok(($(
+                            $name::from_expr($name).map_err(|err| {
+                                err.context(format!("Part {}", $index + 1))
+                            })?,
+                        )+)),
+                        _ => Err(FromExprError::expected(
+                            concat!("List of length ", $len),
+                            expr,
+                        )),
+                    }
+                }
+            }
+
+            impl<$($name: ToExpr),+> ToExpr for ($($name,)+) {
+                fn to_expr(&self) -> Expr {
+                    Expr::list(vec![$(self.$index.to_expr()),+])
+                }
+            }
+        )*
+    };
+}
+
+#[allow(non_snake_case)]
+mod tuples {
+    use super::*;
+
+    tuple_impls! {
+        1 => (A: 0);
+        2 => (A: 0, B: 1);
+        3 => (A: 0, B: 1, C: 2);
+        4 => (A: 0, B: 1, C: 2, D: 3);
+        5 => (A: 0, B: 1, C: 2, D: 3, E: 4);
+        6 => (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+    }
+}
@@ -183,6 +183,20 @@ impl DataStore {
         ds
     }
 
+    /// Get the raw [`wolfram_library_link_sys::DataStore`] pointer wrapped by this
+    /// `DataStore`.
+    ///
+    /// # Safety
+    ///
+    /// The returned data store is still owned by `self`, and must not be deleted, or used
+    /// after `self` has been dropped.
+    #[inline]
+    pub unsafe fn as_raw(&self) -> sys::DataStore {
+        let DataStore(ds) = *self;
+
+        ds
+    }
+
     //==================================
     // Unnamed data
     //==================================
@@ -6,9 +6,16 @@
 
 Suppose you want to generate a Wolfram [`Message[..]`][ref/Message] from within Rust.
 
-The easiest way to accomplish this is to construct an appropriate Wolfram expression
-using the [`Expr`] type, and then use the [`evaluate()`] function to call back into
-Wolfram to evaluate that expression.
+The easiest way to accomplish this is to use the [`message!`][crate::message!] macro,
+which converts its arguments to [`Expr`] and calls back into Wolfram to evaluate the
+`Message[..]` expression. To give a message a default template, define a
+[`MessageName`][crate::MessageName] and call [`MessageName::issue()`][crate::MessageName::issue].
+
+For other kinds of expressions, construct the expression using the [`Expr`] type, and
+then use the [`KernelThread::evaluate()`] method to evaluate it. Calling back into
+Wolfram is only possible from the main Kernel thread, so these all require a
+[`&KernelThread`][KernelThread], which a function can obtain using
+[`KernelThread::current()`].
 
 [ref/Message]: https://reference.wolfram.com/language/ref/Message.html
 
@@ -36,4 +43,4 @@ Wolfram to evaluate that expression.
 
 */
 
-use crate::{evaluate, expr::Expr};
+use crate::{expr::Expr, KernelThread};
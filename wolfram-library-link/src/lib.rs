@@ -159,15 +159,17 @@
 //! evaluation process.
 //!
 //! User libraries can cooperatively include abort checking logic in their library using
-//! the [`aborted()`] function. This enables LibraryLink libraries to provide the same
+//! the [`KernelThread::aborted()`] method. This enables LibraryLink libraries to provide the same
 //! user experience as built in Wolfram Language functions. LibraryLink libraries that may
 //! perform long computations are especially encouraged to do abort checking within loops
 //! that may run for a long time.
 //!
 //! ```no_run
-//! use wolfram_library_link as wll;
+//! use wolfram_library_link::KernelThread;
 //!
-//! if wll::aborted() {
+//! let kernel = KernelThread::current().unwrap();
+//!
+//! if kernel.aborted() {
 //!     // The user aborted this computation, so it doesn't matter what we return.
 //!     panic!("Wolfram abort");
 //! }
@@ -218,14 +220,21 @@
 #![cfg_attr(feature = "nightly", feature(panic_info_message))]
 #![warn(missing_docs)]
 
+mod abort;
 mod args;
 mod async_tasks;
 mod catch_panic;
+mod convert;
 mod data_store;
 mod image;
 mod library_data;
+mod message;
 mod numeric_array;
+mod passing;
+mod sparse_array;
+mod tensor;
 
+pub mod callback;
 /// This module is *semver exempt*. This is not intended to be part of the public API of
 /// wolfram-library-link.
 ///
@@ -236,6 +245,8 @@ pub mod managed;
 pub mod rtl;
 
 pub mod docs;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 
 // Note: This is exported as doc(inline) so that it shows up in the 'Modules' section of
@@ -260,24 +271,37 @@ pub use self::macro_utils::exported_library_functions_association;
 
 
 pub use self::{
+    abort::{AbortError, AbortWatcher},
     args::{FromArg, IntoArg, NativeFunction, WstpFunction},
-    async_tasks::AsyncTaskObject,
+    async_tasks::{
+        AsyncTaskObject, BatchItem, BatchOptions, CancellationToken, EventSender,
+        MainThreadExecutor,
+    },
+    convert::{FromExpr, FromExprError, ToExpr},
     data_store::{DataStore, DataStoreNode, DataStoreNodeValue, Nodes},
     image::{ColorSpace, Image, ImageData, ImageType, Pixel, UninitImage},
-    library_data::{get_library_data, initialize, WolframLibraryData},
+    library_data::{get_library_data, initialize, KernelThread, WolframLibraryData},
+    message::MessageName,
     numeric_array::{
         NumericArray, NumericArrayConvertMethod, NumericArrayDataType, NumericArrayKind,
         NumericArrayType, UninitNumericArray,
     },
+    passing::{Manual, NativeArray, Shared},
+    sparse_array::SparseArray,
+    tensor::{Tensor, TensorDataType, TensorType},
 };
 
 
 
-use std::sync::Mutex;
+use std::{
+    ffi::CString,
+    os::raw::{c_char, c_int, c_void},
+    sync::{Mutex, TryLockError},
+};
 
 use once_cell::sync::Lazy;
 
-use wolfram_library_link_sys::mint;
+use wolfram_library_link_sys::{mint, mreal};
 use wstp::Link;
 
 pub(crate) use self::library_data::assert_main_thread;
@@ -382,6 +406,19 @@ pub use wolfram_library_link_macros::init;
 /// # }
 /// ```
 ///
+/// Return `$Aborted` if an abort is in progress when the function returns, instead of
+/// issuing an error message or reporting a panic. Use
+/// [`KernelThread::check_abort()`] to return early when an abort occurs. This is not
+/// supported for `wstp` functions.
+///
+/// ```
+/// # mod scope {
+/// # use wolfram_library_link::{export, AbortError, KernelThread};
+/// #[export(abortable)]
+/// # fn square(kernel: &KernelThread, x: i64) -> Result<i64, AbortError> { kernel.check_abort()?; Ok(x) }
+/// # }
+/// ```
+///
 /// # Examples
 ///
 /// ### Primitive data types
@@ -456,6 +493,24 @@ pub use wolfram_library_link_macros::init;
 /// ]
 /// ```
 ///
+/// ### Packed arrays
+///
+/// Export a native function with a packed array ([`Tensor`]) argument:
+///
+/// ```
+/// # mod scope {
+/// # use wolfram_library_link::{export, Tensor};
+/// #[export]
+/// fn total_real(list: &Tensor<f64>) -> f64 {
+///     list.as_slice().iter().sum()
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad["...", "total_real", {{Real, _, "Constant"}}, Real]
+/// ```
+///
 /// ### Customize exported function name
 ///
 /// By default, the exported name of a function exported to the Wolfram Langauge
@@ -526,7 +581,14 @@ pub use wolfram_library_link_macros::init;
 /// [`NumericArray`]                   | a. `{LibraryDataType[NumericArray], "Manual"}`[^1] <br/> b. `{LibraryDataType[NumericArray], "Shared"}`[^1]
 /// [`&NumericArray<T>`][NumericArray] | a. `LibraryDataType[NumericArray, `[`"..."`][ref/NumericArray]`]`[^1] <br/> b. `{LibraryDataType[NumericArray, "..."], "Constant"}`[^1]
 /// [`NumericArray<T>`]                | a. `{LibraryDataType[NumericArray, "..."], "Manual"}`[^1] <br/> b. `{LibraryDataType[NumericArray, "..."], "Shared"}`[^1]
+/// [`&Tensor<T>`][Tensor]            | `{`[`"..."`][TensorDataType::name]`, _, "Constant"}`
+/// [`Tensor<T>`]                      | `{`[`"..."`][TensorDataType::name]`, _, "Shared"}`
+/// [`&SparseArray<T>`][SparseArray]  | `{LibraryDataType[SparseArray, `[`...`][TensorDataType::name]`], "Constant"}`
+/// [`SparseArray<T>`]                 | `{LibraryDataType[SparseArray, `[`...`][TensorDataType::name]`], "Shared"}`
+/// [`Shared<A>`]                      | `{..., "Shared"}`, where `...` is the type of `A`
+/// [`Manual<A>`]                      | `{..., "Manual"}`, where `...` is the type of `A`
 /// [`DataStore`]                      | `"DataStore"`
+/// [`&KernelThread`][KernelThread]    | none[^3]
 ///
 /// # Return types
 ///
@@ -549,12 +611,23 @@ pub use wolfram_library_link_macros::init;
 /// [`String`]                         | `String`
 /// [`NumericArray`]                   | `LibraryDataType[NumericArray]`
 /// [`NumericArray<T>`]                | `LibraryDataType[NumericArray, `[`"..."`][ref/NumericArray][^1]`]`
+/// [`Tensor<T>`]                      | `{`[`"..."`][TensorDataType::name]`, _}`
+/// [`SparseArray<T>`]                 | `LibraryDataType[SparseArray, `[`...`][TensorDataType::name]`]`
 /// [`DataStore`]                      | `"DataStore"`
+/// [`Result<T, E>`][Result]           | the return type of `T`[^2]
 ///
 /// [^1]: The Details and Options section of the Wolfram Language
 ///       [`NumericArray` reference page][ref/NumericArray] lists the available element
 ///       types.
 ///
+/// [^2]: If an `Err(..)` is returned, the error is issued as a message and the
+///       function fails with `LIBRARY_FUNCTION_ERROR`. `E` must implement
+///       [`Display`][std::fmt::Display].
+///
+/// [^3]: A `&KernelThread` parameter is provided by the wrapper function generated by
+///       `#[export]`, and is not declared in the `LibraryFunctionLoad` call. It gives
+///       the function access to Kernel callbacks like [`KernelThread::evaluate()`].
+///
 /// [ref/NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
 /// [ref/LibraryFunctionLoad]: https://reference.wolfram.com/language/ref/LibraryFunctionLoad.html
 ///
@@ -566,8 +639,10 @@ pub use wolfram_library_link_macros::init;
 ///
 /// Export the specified functions as native *LibraryLink* WSTP functions.
 ///
-/// To be exported by this macro, the specified function(s) must implement
-/// [`WstpFunction`].
+/// To be exported by this macro, the specified function(s) must either implement
+/// [`WstpFunction`], or have typed parameters that implement
+/// [`FromExpr`][trait@FromExpr] and a return type that implements
+/// [`ToExpr`][trait@ToExpr].
 ///
 /// Functions exported using this macro will automatically:
 ///
@@ -659,113 +734,671 @@ pub use wolfram_library_link_macros::init;
 /// ```wolfram
 /// LibraryFunctionLoad["...", "total_args_i64", LinkObject, LinkObject]
 /// ```
+///
+/// ##### WSTP function with typed parameters:
+///
+/// Functions whose parameters are not a single `&mut Link` or `Vec<Expr>` have each
+/// argument decoded using [`FromExpr`][trait@FromExpr], and their return value encoded
+/// using [`ToExpr`][trait@ToExpr]. If the function is called with the wrong number of
+/// arguments, or an argument cannot be decoded, a [`Failure[...]`][ref/Failure] is
+/// returned.
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{export, FromExpr, ToExpr};
+///
+/// #[derive(FromExpr)]
+/// struct Options {
+///     repeat: i64,
+/// }
+///
+/// #[derive(ToExpr)]
+/// struct Repeated {
+///     text: String,
+///     length: usize,
+/// }
+///
+/// #[export(wstp)]
+/// fn repeat_string(text: String, opts: Options) -> Repeated {
+///     let text = text.repeat(opts.repeat as usize);
+///     let length = text.len();
+///
+///     Repeated { text, length }
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// func = LibraryFunctionLoad["...", "repeat_string", LinkObject, LinkObject];
+///
+/// func["ab", <| "repeat" -> 3 |>]    (* Returns <| "text" -> "ababab", "length" -> 6 |> *)
+/// ```
 pub use wolfram_library_link_macros::export;
 
+/// Export the methods of an `impl` block as a [managed expression][ref/CreateManagedLibraryExpression]
+/// class.
+///
+/// [ref/CreateManagedLibraryExpression]: https://reference.wolfram.com/language/ref/CreateManagedLibraryExpression.html
+///
+/// `#[class]` registers a managed expression manager for the type, and exports:
+///
+/// * the `pub fn new(..) -> Self` associated function as the class constructor, and
+/// * every `pub fn` method taking `&self` or `&mut self` as a class method.
+///
+/// Each instance of the class is stored in a [`ManagedStore`][managed::ManagedStore],
+/// and is dropped when the corresponding managed expression is deallocated by the
+/// Wolfram Language. Parameter and return types of the constructor and methods must
+/// be valid [`NativeFunction`] parameter and return types.
+///
+/// The class name defaults to the name of the type, and can be set using
+/// `#[class(name = "...")]`. If the name is not a fully qualified symbol, the class
+/// symbol is placed in the ``Global` `` context.
+///
+/// `#[class]` requires the `"automate-function-loading-boilerplate"` feature, and the
+/// class is loaded by the function generated by [`generate_loader!`].
+///
+/// The constructor and each method are exported as a native LibraryLink function named
+/// `__wll_class_{Class}__{method}`, e.g. `__wll_class_MyCounter__increment`. Names
+/// starting with `__wll_` are reserved, so these never collide with the names of
+/// [`#[export]`][crate::export] functions.
+///
+/// # Examples
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, class};
+///
+/// struct Counter {
+///     count: i64,
+/// }
+///
+/// #[class(name = "MyCounter")]
+/// impl Counter {
+///     pub fn new(start: i64) -> Self {
+///         Counter { count: start }
+///     }
+///
+///     pub fn increment(&mut self, by: i64) -> i64 {
+///         self.count += by;
+///         self.count
+///     }
+///
+///     pub fn value(&self) -> i64 {
+///         self.count
+///     }
+/// }
+///
+/// wll::generate_loader![load_counter_functions];
+/// # }
+/// ```
+///
+/// ```wolfram
+/// functions = LibraryFunctionLoad["...", "load_counter_functions", {}, LinkObject][];
+///
+/// counter = functions["MyCounter"][5];
+///
+/// counter["increment"][2]    (* Returns 7 *)
+/// counter["value"][]         (* Returns 7 *)
+/// ```
+///
+/// `counter` is a `MyCounter[id]` managed expression, and calling a method on it calls
+/// the Rust method on the instance with that ID. If the constructor fails, for example
+/// because `new()` panicked, it returns the resulting
+/// [`LibraryFunctionError`][ref/LibraryFunctionError]<sub>WL</sub> instead of an
+/// instance.
+///
+/// [ref/LibraryFunctionError]: https://reference.wolfram.com/language/ref/LibraryFunctionError.html
+#[cfg(feature = "automate-function-loading-boilerplate")]
+pub use wolfram_library_link_macros::class;
+
+/// Derive [`FromExpr`][trait@FromExpr] for a struct or enum.
+///
+/// See the [`FromExpr`][trait@FromExpr] trait documentation for the expression
+/// representation used by the derived implementation.
+pub use wolfram_library_link_macros::FromExpr;
+
+/// Derive [`ToExpr`][trait@ToExpr] for a struct or enum.
+///
+/// See the [`FromExpr`][trait@FromExpr] trait documentation for the expression
+/// representation used by the derived implementation.
+pub use wolfram_library_link_macros::ToExpr;
+
 const BACKTRACE_ENV_VAR: &str = "LIBRARY_LINK_RUST_BACKTRACE";
 
+/// Returns information about the most recent panic that occurred in a native
+/// [`#[export]`][crate::export] function, if any.
+///
+/// Unlike [`#[export(wstp)]`][crate::export#exportwstp] functions, which return a
+/// `Failure["RustPanic", ..]` when a panic occurs, native functions can only return an
+/// error code, which causes the Kernel to return
+/// `LibraryFunctionError["LIBRARY_USER_ERROR", 1002]`. The panic message is issued as a
+/// `LibraryFunction::rustpanic` message, and the source location and optional
+/// backtrace can be retrieved afterwards using this function.
+///
+/// The returned expression has the form:
+///
+/// ```wolfram
+/// Failure["RustPanic", <|
+///     "MessageTemplate" -> "Rust LibraryLink function panic: `message`",
+///     "MessageParameters" -> <| "message" -> "..." |>,
+///     "SourceLocation" -> "...",
+///     "Backtrace" -> ...
+/// |>]
+/// ```
+///
+/// The `"Backtrace"` field is only populated if the
+/// [`LIBRARY_LINK_RUST_BACKTRACE`](#show-backtrace-when-a-panic-occurs) environment
+/// variable is set.
+///
+/// # Example
+///
+/// Export a function that returns the most recent panic to the Wolfram Language:
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, export, expr::{Expr, Symbol}};
+///
+/// #[export(wstp)]
+/// fn last_panic(_args: Vec<Expr>) -> Expr {
+///     wll::last_panic().unwrap_or_else(|| Expr::symbol(Symbol::new("System`None")))
+/// }
+/// # }
+/// ```
+pub fn last_panic() -> Option<Expr> {
+    catch_panic::last_panic().map(|panic| panic.to_pretty_expr())
+}
+
 //======================================
 // Callbacks to the Wolfram Kernel
 //======================================
 
-/// Evaluate `expr` by calling back into the Wolfram Kernel.
+/// Evaluate `expr` by calling back into the Wolfram Kernel. See
+/// [`KernelThread::evaluate()`].
 ///
-/// TODO: Specify and document what happens if the evaluation of `expr` triggers a
-///       kernel abort (such as a `Throw[]` in the code).
+/// # Panics
+///
+/// This function will panic if evaluation fails, or if it is not called from the main
+/// Kernel thread.
+#[deprecated(note = "use `KernelThread::evaluate()`, which can only be called from the \
+                     main Kernel thread")]
+#[track_caller]
 pub fn evaluate(expr: &Expr) -> Expr {
-    match try_evaluate(expr) {
-        Ok(returned) => returned,
-        Err(msg) => panic!(
-            "evaluate(): evaluation of expression failed: {}: \n\texpression: {}",
-            msg, expr
-        ),
-    }
+    assert_main_thread().evaluate(expr)
 }
 
-/// Attempt to evaluate `expr`, returning an error if a WSTP transport error occurred
-/// or evaluation failed.
+/// Attempt to evaluate `expr`. See [`KernelThread::try_evaluate()`].
+///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread.
+#[deprecated(note = "use `KernelThread::try_evaluate()`, which can only be called from \
+                     the main Kernel thread")]
+#[track_caller]
 pub fn try_evaluate(expr: &Expr) -> Result<Expr, String> {
-    with_link(|link: &mut Link| {
+    assert_main_thread().try_evaluate(expr)
+}
+
+/// Result of evaluating an expression using
+/// [`KernelThread::evaluate_detailed()`].
+#[derive(Debug, Clone)]
+pub struct EvaluationResult {
+    /// The value of the expression. This is `$Aborted` if the evaluation was aborted.
+    pub value: Expr,
+    /// Messages issued during the evaluation, in the order they were issued.
+    pub messages: Vec<EvaluationMessage>,
+    /// Text printed during the evaluation, e.g. by [`Print`][ref/Print]<sub>WL</sub>,
+    /// in the order it was printed.
+    ///
+    /// [ref/Print]: https://reference.wolfram.com/language/ref/Print.html
+    pub printed: Vec<String>,
+    /// Whether the evaluation was aborted.
+    pub aborted: bool,
+}
+
+/// Message issued during an evaluation. See [`EvaluationResult::messages`].
+#[derive(Debug, Clone)]
+pub struct EvaluationMessage {
+    /// The symbol the message is associated with, e.g. `Power` in `Power::infy`.
+    pub symbol: Symbol,
+    /// The message tag, e.g. `"infy"` in `Power::infy`.
+    pub tag: String,
+    /// The formatted text of the message.
+    pub text: String,
+}
+
+/// Error returned by [`KernelThread::evaluate_as()`] and
+/// [`KernelThread::evaluate_str_as()`].
+#[derive(Debug, Clone)]
+pub enum EvalError {
+    /// A WSTP transport error occurred, or evaluation failed.
+    Link(String),
+    /// The evaluation was aborted.
+    Aborted,
+    /// The `evaluateExpression()` runtime function returned an error code, e.g.
+    /// because the result did not have the requested type.
+    Runtime(sys::errcode_t),
+    /// The result of the evaluation could not be converted to the requested type.
+    Conversion {
+        /// The result of the evaluation.
+        value: Expr,
+        /// The error returned by [`FromExpr::from_expr()`].
+        error: FromExprError,
+    },
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EvalError::Link(message) => write!(f, "evaluation failed: {}", message),
+            EvalError::Aborted => write!(f, "evaluation was aborted"),
+            EvalError::Runtime(code) => {
+                write!(f, "evaluation failed with LibraryLink error code {}", code)
+            },
+            EvalError::Conversion { error, .. } => {
+                write!(f, "unexpected evaluation result: {}", error)
+            },
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Callbacks into the Kernel, which can only be made from the main Kernel thread.
+impl KernelThread {
+    /// Evaluate `expr` by calling back into the Wolfram Kernel.
+    ///
+    /// If the evaluation is aborted, `$Aborted` is returned. Use
+    /// [`KernelThread::evaluate_detailed()`] to also access any messages issued during
+    /// the evaluation.
+    ///
+    /// TODO: Specify and document what happens if the evaluation of `expr` triggers a
+    ///       non-local exit (such as an uncaught `Throw[]` in the code).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if evaluation fails.
+    pub fn evaluate(&self, expr: &Expr) -> Expr {
+        match self.try_evaluate(expr) {
+            Ok(returned) => returned,
+            Err(msg) => panic!(
+                "evaluate(): evaluation of expression failed: {}: \n\texpression: {}",
+                msg, expr
+            ),
+        }
+    }
+
+    /// Attempt to evaluate `expr`, returning an error if a WSTP transport error
+    /// occurred or evaluation failed.
+    ///
+    /// Any messages issued or text printed during the evaluation are discarded. Use
+    /// [`KernelThread::evaluate_detailed()`] to access them.
+    ///
+    /// The evaluation of `expr` may itself call LibraryLink functions that call
+    /// `try_evaluate()`, to any depth. The link to the Kernel is only borrowed while
+    /// writing `expr` and reading the result, and not while `expr` is being evaluated.
+    pub fn try_evaluate(&self, expr: &Expr) -> Result<Expr, String> {
+        self.evaluate_detailed(expr).map(|result| result.value)
+    }
+
+    /// Evaluate `expr`, returning its value along with any messages issued and text
+    /// printed during the evaluation.
+    ///
+    /// Returns an error if a WSTP transport error occurred or evaluation failed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{KernelThread, expr::{Expr, Symbol}};
+    ///
+    /// let kernel = KernelThread::current().unwrap();
+    ///
+    /// // 1/0
+    /// let expr = Expr::normal(Symbol::new("System`Power"), vec![Expr::from(0), Expr::from(-1)]);
+    ///
+    /// let result = kernel.evaluate_detailed(&expr).unwrap();
+    ///
+    /// // Issues the Power::infy message.
+    /// assert_eq!(result.messages[0].tag, "infy");
+    /// ```
+    pub fn evaluate_detailed(&self, expr: &Expr) -> Result<EvaluationResult, String> {
         // Send an EvaluatePacket['expr].
-        let _: () = link
+        let _: () = with_link(self, |link: &mut Link| {
             // .put_expr(&Expr! { EvaluatePacket['expr] })
-            .put_expr(&Expr::normal(Symbol::new("System`EvaluatePacket"), vec![
+            link.put_expr(&Expr::normal(Symbol::new("System`EvaluatePacket"), vec![
                 expr.clone(),
             ]))
-            .map_err(|e| e.to_string())?;
+        })
+        .map_err(|e| e.to_string())?;
+
+        // Note: This must not be called inside `with_link()`, because evaluating `expr`
+        //       may call back into a function that uses the link.
+        process_wstp_link(self)
+    }
 
-        let _: () = process_wstp_link(link)?;
+    /// Evaluate `expr`, and convert the result to `T` using
+    /// [`FromExpr`][trait@FromExpr].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{KernelThread, expr::{Expr, Symbol}};
+    ///
+    /// let kernel = KernelThread::current().unwrap();
+    ///
+    /// // Plus[1, 2]
+    /// let expr = Expr::normal(Symbol::new("System`Plus"), vec![Expr::from(1), Expr::from(2)]);
+    ///
+    /// let sum: i64 = kernel.evaluate_as(&expr).unwrap();
+    ///
+    /// assert_eq!(sum, 3);
+    /// ```
+    pub fn evaluate_as<T: FromExpr>(&self, expr: &Expr) -> Result<T, EvalError> {
+        let result = self.evaluate_detailed(expr).map_err(EvalError::Link)?;
 
-        let return_packet: Expr = link.get_expr().map_err(|e| e.to_string())?;
+        if result.aborted {
+            return Err(EvalError::Aborted);
+        }
+
+        T::from_expr(&result.value).map_err(|error| EvalError::Conversion {
+            value: result.value,
+            error,
+        })
+    }
 
-        let returned_expr = match return_packet.kind() {
-            ExprKind::Normal(normal) => {
-                debug_assert!(normal.has_head(&Symbol::new("System`ReturnPacket")));
-                debug_assert!(normal.elements().len() == 1);
-                normal.elements()[0].clone()
+    /// Evaluate a string of Wolfram Language code, and convert the result to `T` using
+    /// [`FromExpr`][trait@FromExpr].
+    ///
+    /// If `T` is an integer or floating-point type, `code` is evaluated using the
+    /// `evaluateExpression()` LibraryLink runtime function, which returns the result
+    /// without using the WSTP link. An [`EvalError::Runtime`] error is returned if the
+    /// result is not a number of that type.
+    ///
+    /// Otherwise, `code` is parsed and evaluated using
+    /// [`ToExpression`][ref/ToExpression]<sub>WL</sub>.
+    ///
+    /// Use [`KernelThread::evaluate_str_as_tensor()`] to evaluate code whose result is
+    /// a packed array.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `code` contains a NUL byte.
+    ///
+    /// # Example
+    ///
+    /// Get the version of the Kernel that loaded the current library:
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::KernelThread;
+    ///
+    /// let kernel = KernelThread::current().unwrap();
+    ///
+    /// let version: String = kernel.evaluate_str_as("$Version").unwrap();
+    /// let version_number: f64 = kernel.evaluate_str_as("$VersionNumber").unwrap();
+    /// ```
+    ///
+    /// [ref/ToExpression]: https://reference.wolfram.com/language/ref/ToExpression.html
+    pub fn evaluate_str_as<T: FromExpr>(&self, code: &str) -> Result<T, EvalError> {
+        let value = match T::NATIVE_TYPE {
+            Some(TensorDataType::Integer) => {
+                let mut value: mint = 0;
+                self.evaluate_expression(code, TensorDataType::Integer, 0, &mut value)?;
+                Expr::from(value)
             },
+            Some(TensorDataType::Real) => {
+                let mut value: mreal = 0.0;
+                self.evaluate_expression(code, TensorDataType::Real, 0, &mut value)?;
+                Expr::real(value)
+            },
+            // ToExpression["code"]
             _ => {
-                return Err(format!(
-                    "try_evaluate(): returned expression was not ReturnPacket: {}",
-                    return_packet
+                return self.evaluate_as(&Expr::normal(
+                    Symbol::new("System`ToExpression"),
+                    vec![Expr::string(code)],
                 ))
             },
         };
 
-        Ok(returned_expr)
-    })
+        T::from_expr(&value).map_err(|error| EvalError::Conversion { value, error })
+    }
+
+    /// Evaluate a string of Wolfram Language code whose result is a packed array of
+    /// the specified rank.
+    ///
+    /// `code` is evaluated using the `evaluateExpression()` LibraryLink runtime
+    /// function. An [`EvalError::Runtime`] error is returned if the result is not a
+    /// packable array of `T` values with rank `rank`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `rank` is zero or `code` contains a NUL byte.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{KernelThread, Tensor};
+    ///
+    /// let kernel = KernelThread::current().unwrap();
+    ///
+    /// let squares: Tensor<i64> = kernel.evaluate_str_as_tensor("Range[5]^2", 1).unwrap();
+    ///
+    /// assert_eq!(squares.as_slice(), &[1, 4, 9, 16, 25]);
+    /// ```
+    pub fn evaluate_str_as_tensor<T: TensorType>(
+        &self,
+        code: &str,
+        rank: usize,
+    ) -> Result<Tensor<T>, EvalError> {
+        assert!(rank > 0, "evaluate_str_as_tensor(): rank must be at least 1");
+
+        let mut tensor: sys::MTensor = std::ptr::null_mut();
+
+        self.evaluate_expression(code, T::TYPE, rank, &mut tensor)?;
+
+        // Safety: evaluateExpression() returns a new tensor, which we now own.
+        Ok(unsafe { Tensor::from_raw(tensor) })
+    }
+
+    /// Evaluate `code` using the `evaluateExpression()` runtime function, which writes
+    /// the result to `result`.
+    ///
+    /// `result` must point to an [`mint`] or [`mreal`] if `rank` is zero, and to an
+    /// [`MTensor`][sys::MTensor] otherwise.
+    pub(crate) fn evaluate_expression<R>(
+        &self,
+        code: &str,
+        data_type: TensorDataType,
+        rank: usize,
+        result: &mut R,
+    ) -> Result<(), EvalError> {
+        let code = CString::new(code).expect("evaluate_str_as(): code contains NUL byte");
+        let rank = mint::try_from(rank).expect("evaluate_str_as(): rank overflows mint");
+
+        let lib = get_library_data().raw_library_data;
+
+        let err_code: sys::errcode_t = unsafe {
+            rtl::evaluateExpression(
+                lib,
+                code.as_ptr() as *mut c_char,
+                data_type as c_int,
+                rank,
+                result as *mut R as *mut c_void,
+            )
+        };
+
+        if err_code == sys::LIBRARY_NO_ERROR as sys::errcode_t {
+            Ok(())
+        } else if self.aborted() {
+            Err(EvalError::Aborted)
+        } else {
+            Err(EvalError::Runtime(err_code))
+        }
+    }
+
+    /// Returns `true` if the user has requested that the current evaluation be
+    /// aborted.
+    ///
+    /// Programs should finish what they are doing and return control of this thread to
+    /// to the kernel as quickly as possible. They should not exit the process or
+    /// otherwise terminate execution, simply return up the call stack.
+    ///
+    /// Within Rust functions exported using [`#[export]`][crate::export] or
+    /// [`#[export(wstp)]`][crate::export#exportwstp] (which generate a wrapper function
+    /// that catches panics), `panic!()` can be used to quickly unwind the call stack to
+    /// the appropriate place.
+    /// Note that this will not work if the current library is built with
+    /// `panic = "abort"`. See the [`panic`][panic-option] profile configuration option
+    /// for more information.
+    ///
+    /// Prefer to use [`KernelThread::check_abort()`], which can be used with the `?`
+    /// operator, in functions exported using
+    /// [`#[export(abortable)]`][crate::export#advanced]. Use an [`AbortWatcher`] to
+    /// check for aborts from other threads.
+    ///
+    /// [panic-option]: https://doc.rust-lang.org/cargo/reference/profiles.html#panic
+    pub fn aborted(&self) -> bool {
+        let val: mint = unsafe { rtl::AbortQ() };
+        // TODO: What values can `val` be?
+        val == 1
+    }
 }
 
 /// Returns `true` if the user has requested that the current evaluation be aborted.
+/// See [`KernelThread::aborted()`].
 ///
-/// Programs should finish what they are doing and return control of this thread to
-/// to the kernel as quickly as possible. They should not exit the process or
-/// otherwise terminate execution, simply return up the call stack.
-///
-/// Within Rust functions exported using [`#[export]`][crate::export] or
-/// [`#[export(wstp)]`][crate::export#exportwstp] (which generate a wrapper function that
-/// catches panics), `panic!()` can be used to quickly unwind the call stack to the
-/// appropriate place.
-/// Note that this will not work if the current library is built with
-/// `panic = "abort"`. See the [`panic`][panic-option] profile configuration option
-/// for more information.
+/// # Panics
 ///
-/// [panic-option]: https://doc.rust-lang.org/cargo/reference/profiles.html#panic
+/// This function will panic if it is not called from the main Kernel thread.
+#[deprecated(note = "use `KernelThread::aborted()`, which can only be called from the \
+                     main Kernel thread")]
+#[track_caller]
 pub fn aborted() -> bool {
-    // TODO: Is this function thread safe? Can it be called from a thread other than the
-    //       one the LibraryLink wrapper was originally invoked from?
-    let val: mint = unsafe { rtl::AbortQ() };
-    // TODO: What values can `val` be?
-    val == 1
+    assert_main_thread().aborted()
 }
 
 // TODO: Instead of making these public, add new evaluate(..) alternative that
 //       takes a WstpExpr type.
-fn process_wstp_link(link: &mut Link) -> Result<(), String> {
-    assert_main_thread();
+/// Process the packet on the link returned by `getWSLINK()`, and read every packet
+/// sent in response, up to and including the final `ReturnPacket`.
+///
+/// This does not borrow the link while the packet is processed, so that it may be
+/// processed by calling back into a LibraryLink function that itself uses the link.
+fn process_wstp_link(kernel: &KernelThread) -> Result<EvaluationResult, String> {
+    let lib = get_library_data().raw_library_data;
 
-    let raw_link = unsafe { link.raw_link() };
+    let raw_link: sys::WSLINK = unsafe { rtl::getWSLINK(lib) };
 
     // Process the packet on the link.
-    let code: i32 = unsafe { rtl::processWSLINK(raw_link as *mut _) };
+    let code: i32 = unsafe { rtl::processWSLINK(raw_link) };
 
     if code == 0 {
-        let error_message = link
-            .error_message()
+        let error_message = with_link(kernel, |link: &mut Link| link.error_message())
             .unwrap_or_else(|| "unknown error occurred on WSTP Link".into());
 
         return Err(error_message);
     }
 
-    Ok(())
+    let mut messages = Vec::new();
+    let mut printed = Vec::new();
+    let mut answered_menu = false;
+
+    // MessagePacket[symbol, "tag"], whose text is sent in the following TextPacket.
+    let mut pending_message: Option<(Symbol, String)> = None;
+
+    loop {
+        let packet: Expr =
+            with_link(kernel, |link: &mut Link| link.get_expr())
+                .map_err(|e| e.to_string())?;
+
+        let normal = match packet.kind() {
+            ExprKind::Normal(normal) => normal,
+            _ => {
+                return Err(format!(
+                    "evaluate(): received expression that was not a packet: {}",
+                    packet
+                ))
+            },
+        };
+
+        let head = match normal.head().kind() {
+            ExprKind::Symbol(head) => head.as_str(),
+            _ => "",
+        };
+
+        match (head, normal.elements()) {
+            ("System`ReturnPacket" | "System`ReturnExpressionPacket", [value]) => {
+                return Ok(EvaluationResult {
+                    value: value.clone(),
+                    messages,
+                    printed,
+                    // Don't infer this from `value`, which may legitimately be
+                    // `$Aborted` without an abort being in progress.
+                    aborted: kernel.aborted(),
+                });
+            },
+            ("System`MessagePacket", [symbol, tag]) => {
+                if let (ExprKind::Symbol(symbol), ExprKind::String(tag)) =
+                    (symbol.kind(), tag.kind())
+                {
+                    pending_message = Some((symbol.clone(), tag.clone()));
+                }
+            },
+            ("System`TextPacket", [text]) => {
+                let text = match text.kind() {
+                    ExprKind::String(text) => text.clone(),
+                    _ => text.to_string(),
+                };
+
+                match pending_message.take() {
+                    Some((symbol, tag)) => {
+                        messages.push(EvaluationMessage { symbol, tag, text })
+                    },
+                    None => printed.push(text),
+                }
+            },
+            // A MenuPacket is sent when the evaluation is interrupted, and the Kernel
+            // waits for the menu choice before continuing. There is no user to
+            // answer it, so choose to abort the evaluation. The Kernel then finishes
+            // the evaluation with a ReturnPacket.
+            ("System`MenuPacket", _) => {
+                // If the Kernel did not accept the answer, it would keep sending
+                // MenuPackets.
+                if answered_menu {
+                    return Err(format!(
+                        "evaluate(): unable to answer interrupt menu: {}",
+                        packet
+                    ));
+                }
+
+                answered_menu = true;
+
+                with_link(kernel, |link: &mut Link| {
+                    link.put_str("a")?;
+                    link.end_packet()?;
+                    link.flush()
+                })
+                .map_err(|e| e.to_string())?;
+            },
+            // Ignore any other packets, e.g. OutputNamePacket.
+            _ => (),
+        }
+    }
 }
 
 /// Enforce exclusive access to the link returned by `getWSLINK()`.
-fn with_link<F: FnOnce(&mut Link) -> R, R>(f: F) -> R {
-    assert_main_thread();
-
+///
+/// `f` must not call back into the Kernel.
+fn with_link<F: FnOnce(&mut Link) -> R, R>(_: &KernelThread, f: F) -> R {
     static LOCK: Lazy<Mutex<()>> = Lazy::new(|| Default::default());
 
-    let _guard = LOCK.lock().expect("failed to acquire LINK lock");
+    // The link is only used from the main thread, so if the lock is already held, this
+    // is a nested call from within `f`. Report that instead of deadlocking.
+    let _guard = match LOCK.try_lock() {
+        Ok(guard) => guard,
+        Err(TryLockError::WouldBlock) => {
+            panic!("with_link(): nested use of the Kernel link from within with_link()")
+        },
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+    };
 
     let lib = get_library_data().raw_library_data;
 
@@ -1,4 +1,4 @@
-use std::thread;
+use std::{marker::PhantomData, thread};
 
 use once_cell::sync::OnceCell;
 
@@ -100,15 +100,8 @@ pub fn get_library_data() -> WolframLibraryData {
     .library_data
 }
 
-pub(crate) fn is_main_thread() -> bool {
-    let data = LIBRARY_DATA
-        .get()
-        .expect("global LIBRARY_DATA static is not initialized");
-
-    data.main_thread_id == thread::current().id()
-}
-
-/// Assert that the current thread is the main Kernel thread.
+/// Assert that the current thread is the main Kernel thread, returning a
+/// [`KernelThread`] token.
 ///
 /// # Panics
 ///
@@ -117,15 +110,91 @@ pub(crate) fn is_main_thread() -> bool {
 /// Use this function to enforce that callbacks into the Kernel happen from the
 /// main thread.
 #[track_caller]
-pub(crate) fn assert_main_thread() {
+pub(crate) fn assert_main_thread() -> KernelThread {
     let loc = std::panic::Location::caller();
 
-    assert!(
-        is_main_thread(),
-        "error: attempted to call back into the Wolfram Kernel from a non-main thread at {}:{}",
-        loc.file(),
-        loc.line()
-    );
+    match KernelThread::current() {
+        Some(kernel) => kernel,
+        None => panic!(
+            "error: attempted to call back into the Wolfram Kernel from a non-main thread at {}:{}",
+            loc.file(),
+            loc.line()
+        ),
+    }
+}
+
+/// Proof that the current thread is the main Wolfram Kernel thread.
+///
+/// Callbacks into the Kernel, like [`KernelThread::evaluate()`], can only be made from
+/// the main Kernel thread. A `KernelThread` can only be obtained on that thread, and it
+/// is neither [`Send`] nor [`Sync`], so the APIs that require a `&KernelThread` cannot
+/// be called from any other thread.
+///
+/// A function exported using [`#[export]`][crate::export] can take a `&KernelThread`
+/// parameter, which is provided by the generated wrapper function. Use
+/// [`KernelThread::current()`] to obtain a `KernelThread` inside other functions, like
+/// those exported using [`#[export(wstp)]`][crate::export#exportwstp].
+///
+/// The deprecated free functions like [`evaluate()`][crate::evaluate] check that they
+/// are called from the main Kernel thread at runtime instead, and panic if they are
+/// not.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, KernelThread, expr::Expr};
+///
+/// #[wll::export]
+/// fn kernel_version(kernel: &KernelThread) -> String {
+///     kernel.evaluate_str_as("$Version").unwrap()
+/// }
+/// # }
+/// ```
+///
+/// A `KernelThread` cannot be moved into a spawned thread:
+///
+/// ```compile_fail
+/// use wolfram_library_link::{KernelThread, expr::Expr};
+///
+/// let kernel = KernelThread::current().unwrap();
+///
+/// std::thread::spawn(move || kernel.evaluate(&Expr::from(1)));
+/// ```
+#[derive(Debug)]
+pub struct KernelThread {
+    // Make this type !Send and !Sync.
+    _marker: PhantomData<*const ()>,
+}
+
+impl KernelThread {
+    /// Returns a `KernelThread` if the current thread is the main Kernel thread.
+    ///
+    /// Returns `None` if the current thread is not the main Kernel thread, or if
+    /// [`initialize()`] has not been called.
+    pub fn current() -> Option<KernelThread> {
+        let data = LIBRARY_DATA.get()?;
+
+        if data.main_thread_id != thread::current().id() {
+            return None;
+        }
+
+        Some(KernelThread {
+            _marker: PhantomData,
+        })
+    }
+
+    /// Returns a `KernelThread` without checking the current thread.
+    ///
+    /// # Safety
+    ///
+    /// The current thread must be a thread the Kernel called a LibraryLink function
+    /// from.
+    pub(crate) unsafe fn new_unchecked() -> KernelThread {
+        KernelThread {
+            _marker: PhantomData,
+        }
+    }
 }
 
 #[allow(non_snake_case)]
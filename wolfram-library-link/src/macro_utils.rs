@@ -3,10 +3,10 @@ use std::os::raw::c_int;
 use wstp::{self, Link};
 
 use crate::{
-    catch_panic::{call_and_catch_panic, CaughtPanic},
-    expr::{Expr, Symbol},
+    catch_panic::{self, call_and_catch_panic, CaughtPanic},
+    expr::{Expr, ExprKind, Symbol},
     sys::{self, MArgument, LIBRARY_NO_ERROR},
-    NativeFunction, WstpFunction,
+    FromExpr, FromExprError, MessageName, NativeFunction, WstpFunction,
 };
 
 /// Error codes returned by macro-generated wrapper code.
@@ -29,8 +29,9 @@ mod error_code {
     pub const FAILED_TO_INIT: c_int = OFFSET + 1;
 
     /// The library code panicked.
-    //
-    // TODO: Wherever this code is set, also set a $LastError-like variable.
+    ///
+    /// For native functions, the panic can be retrieved using
+    /// [`last_panic()`][crate::last_panic].
     pub const FAILED_WITH_PANIC: c_int = OFFSET + 2;
 }
 
@@ -132,6 +133,47 @@ pub unsafe fn call_native_wolfram_library_function<'a, F: NativeFunction<'a>>(
     argc: sys::mint,
     res: MArgument,
     func: F,
+) -> c_int {
+    call_native_function(lib_data, args, argc, res, func, false)
+}
+
+/// Implementation of the `wll_run_main_thread_job` function exported by
+/// [`export_main_thread_executor!`][crate::export_main_thread_executor].
+pub unsafe fn run_main_thread_job(
+    lib_data: sys::WolframLibraryData,
+    args: *mut MArgument,
+    argc: sys::mint,
+    res: MArgument,
+) -> c_int {
+    let func: fn(_, sys::mint) = crate::async_tasks::run_main_thread_job;
+
+    call_native_wolfram_library_function(lib_data, args, argc, res, func)
+}
+
+/// Call a function exported using `#[export(abortable)]`.
+///
+/// If an abort is in progress when `func` returns or panics, `LIBRARY_FUNCTION_ERROR`
+/// is returned, and the Kernel returns `$Aborted` from the evaluation.
+pub unsafe fn call_abortable_native_wolfram_library_function<
+    'a,
+    F: NativeFunction<'a>,
+>(
+    lib_data: sys::WolframLibraryData,
+    args: *mut MArgument,
+    argc: sys::mint,
+    res: MArgument,
+    func: F,
+) -> c_int {
+    call_native_function(lib_data, args, argc, res, func, true)
+}
+
+unsafe fn call_native_function<'a, F: NativeFunction<'a>>(
+    lib_data: sys::WolframLibraryData,
+    args: *mut MArgument,
+    argc: sys::mint,
+    res: MArgument,
+    func: F,
+    abortable: bool,
 ) -> c_int {
     use std::panic::AssertUnwindSafe;
 
@@ -150,12 +192,38 @@ pub unsafe fn call_native_wolfram_library_function<'a, F: NativeFunction<'a>>(
     //        E.g. `fn foo(link: &'static mut str) { ... }`
     let args: &[MArgument] = std::slice::from_raw_parts(args, argc);
 
-    if call_and_catch_panic(AssertUnwindSafe(move || func.call(args, res))).is_err() {
-        // TODO: Store the panic into a "LAST_ERROR" static, and provide an accessor to
-        //       get it from WL? E.g. RustLink`GetLastError[<optional func name>].
+    // Clear any error left over from a previous call that panicked after its return
+    // value was set.
+    let _ = crate::args::take_returned_error();
+
+    let was_abortable = crate::args::set_abortable(abortable);
+
+    let result = call_and_catch_panic(AssertUnwindSafe(move || func.call(args, res)));
+
+    let _ = crate::args::set_abortable(was_abortable);
+
+    // The return value (or panic) of an aborted evaluation is discarded by the Kernel.
+    // Safety: The Kernel calls LibraryLink functions from the main thread.
+    if abortable && crate::KernelThread::new_unchecked().aborted() {
+        let _ = crate::args::take_returned_error();
+        return sys::LIBRARY_FUNCTION_ERROR as c_int;
+    }
+
+    if let Err(panic) = result {
+        // Native functions can't return a `Failure[..]` like WSTP functions do, so issue
+        // the panic message as a message, and save the panic so that its location and
+        // backtrace can be retrieved using `last_panic()`.
+        // Safety: The Kernel calls LibraryLink functions from the main thread.
+        let _: bool = panic.issue_message(&crate::KernelThread::new_unchecked());
+        catch_panic::set_last_panic(panic);
         return error_code::FAILED_WITH_PANIC;
     };
 
+    // The function returned `Err(..)`. See `impl IntoArg for Result<T, E>`.
+    if crate::args::take_returned_error() {
+        return sys::LIBRARY_FUNCTION_ERROR as c_int;
+    }
+
     sys::LIBRARY_NO_ERROR as c_int
 }
 
@@ -175,10 +243,159 @@ pub unsafe fn call_wstp_wolfram_library_function<
     )
 }
 
+/// Call a typed [`#[export(wstp)]`][crate::export#exportwstp] function.
+///
+/// `func` is a closure generated by `#[export(wstp)]` that decodes the arguments using
+/// [`wstp_arg()`], calls the user function, and encodes the result using [`ToExpr`][crate::ToExpr].
+/// If the arguments are invalid, `func` returns a `Failure[..]` describing the problem
+/// instead.
+pub unsafe fn call_typed_wstp_wolfram_library_function<F>(
+    libdata: sys::WolframLibraryData,
+    unsafe_link: wstp::sys::WSLINK,
+    func: F,
+) -> c_int
+where
+    F: FnOnce(Vec<Expr>) -> Expr + std::panic::UnwindSafe,
+{
+    call_wstp_link_wolfram_library_function(
+        libdata,
+        unsafe_link,
+        move |link: &mut Link| {
+            let args: Vec<Expr> = match crate::args::get_args_list(link) {
+                Ok(args) => args,
+                Err(message) => panic!("WstpFunction: {}", message),
+            };
+
+            let result: Expr = func(args);
+
+            match link.put_expr(&result) {
+                Ok(()) => (),
+                Err(err) => panic!(
+                    "WstpFunction: WSTP error writing return expression to link: {}",
+                    err
+                ),
+            }
+        },
+    )
+}
+
+/// Check that a typed WSTP function was called with `expected` arguments, or return a
+/// `Failure["ArgumentCount", ..]` to write to the link instead of the result.
+pub fn check_wstp_arg_count(args: &[Expr], expected: usize) -> Result<(), Expr> {
+    if args.len() == expected {
+        return Ok(());
+    }
+
+    Err(argument_failure(
+        "ArgumentCount",
+        "Expected `expected` argument(s), got `actual`.",
+        vec![
+            ("expected", Expr::from(expected as i64)),
+            ("actual", Expr::from(args.len() as i64)),
+        ],
+    ))
+}
+
+/// Decode the argument at `index` of a typed WSTP function, or return a
+/// `Failure["ArgumentConversion", ..]` to write to the link instead of the result.
+pub fn wstp_arg<T: FromExpr>(args: &[Expr], index: usize) -> Result<T, Expr> {
+    T::from_expr(&args[index]).map_err(|err| {
+        argument_failure(
+            "ArgumentConversion",
+            "Argument `position` could not be converted: `message`",
+            vec![
+                ("position", Expr::from(index as i64 + 1)),
+                ("message", Expr::string(err.to_string())),
+            ],
+        )
+    })
+}
+
+/// `Failure[tag, <| "MessageTemplate" -> template, "MessageParameters" -> <| .. |> |>]`
+fn argument_failure(tag: &str, template: &str, parameters: Vec<(&str, Expr)>) -> Expr {
+    let parameters = parameters
+        .into_iter()
+        .map(|(name, value)| Expr::rule(Expr::string(name), value))
+        .collect();
+
+    Expr::normal(Symbol::new("System`Failure"), vec![
+        Expr::string(tag),
+        Expr::normal(Symbol::new("System`Association"), vec![
+            Expr::rule(Expr::string("MessageTemplate"), Expr::string(template)),
+            Expr::rule(
+                Expr::string("MessageParameters"),
+                Expr::normal(Symbol::new("System`Association"), parameters),
+            ),
+        ]),
+    ])
+}
+
+//======================================
+// #[derive(FromExpr, ToExpr)] helpers
+//======================================
+
+pub use crate::convert::association_rules;
+
+/// Decode the value of the field `name` from the rules of an `Association`.
+///
+/// If there is no rule with that key, the value is decoded from
+/// `Missing["KeyAbsent", name]`, so that `Option<T>` fields may be omitted.
+pub fn association_field<T: FromExpr>(
+    rules: &[(&Expr, &Expr)],
+    name: &str,
+) -> Result<T, FromExprError> {
+    let value = rules
+        .iter()
+        .rev()
+        .find_map(|(key, value)| match key.kind() {
+            ExprKind::String(key) if key == name => Some(*value),
+            _ => None,
+        });
+
+    let result = match value {
+        Some(value) => T::from_expr(value),
+        None => T::from_expr(&Expr::normal(Symbol::new("System`Missing"), vec![
+            Expr::string("KeyAbsent"),
+            Expr::string(name),
+        ])),
+    };
+
+    result.map_err(|err| err.context(format!("field `{}`", name)))
+}
+
+/// Returns the elements of `expr`, which must be a list of length `len`.
+pub fn list_elements(expr: &Expr, len: usize) -> Result<&[Expr], FromExprError> {
+    match expr.kind() {
+        ExprKind::Normal(list)
+            if list.has_head(&Symbol::new("System`List"))
+                && list.elements().len() == len =>
+        {
+            Ok(list.elements())
+        },
+        _ => Err(FromExprError::expected(
+            &format!("List of length {}", len),
+            expr,
+        )),
+    }
+}
+
+/// Construct an `Association` from field names and values.
+pub fn association(fields: Vec<(&str, Expr)>) -> Expr {
+    let rules = fields
+        .into_iter()
+        .map(|(name, value)| Expr::rule(Expr::string(name), value))
+        .collect();
+
+    Expr::normal(Symbol::new("System`Association"), rules)
+}
+
 //======================================
 // Automatic Loader
 //======================================
 
+/// Returns the `LibraryFunctionLoad` argument and return types of a native function.
+pub type SignatureFn = fn() -> Result<(Vec<Expr>, Expr), String>;
+
 pub enum LibraryLinkFunction {
     Native {
         name: &'static str,
@@ -210,16 +427,61 @@ pub enum LibraryLinkFunction {
         /// that is constructed in the macro-generated code (and where the concrete
         /// function type is still available) to avoid trying and failing to box up or
         /// return the `NativeFunction` trait object.
-        signature: fn() -> Result<(Vec<Expr>, Expr), String>,
+        signature: SignatureFn,
     },
     Wstp {
         name: &'static str,
     },
+    /// A class exported using [`#[class]`][crate::class].
+    Class {
+        /// The name of the class, and of its library expression manager.
+        name: &'static str,
+        /// The fully qualified name of the Wolfram Language symbol that instances of
+        /// this class are wrapped in.
+        symbol: &'static str,
+        /// Register the [`ManagedStore`][crate::managed::ManagedStore] for this class.
+        /// This must be safe to call more than once.
+        register: fn(),
+        constructor: ClassFunction,
+        methods: &'static [ClassFunction],
+    },
+}
+
+/// Constructor or method of a class exported using [`#[class]`][crate::class].
+pub struct ClassFunction {
+    /// The Wolfram Language name of the method.
+    pub method: &'static str,
+    /// The name of the native function that implements this method.
+    pub exported_name: &'static str,
+    /// See the implementation note on [`LibraryLinkFunction::Native::signature`].
+    pub signature: SignatureFn,
 }
 
 #[cfg(feature = "automate-function-loading-boilerplate")]
 inventory::collect!(LibraryLinkFunction);
 
+/// A `static` [`MessageName`] registered using
+/// [`register_messages!`][crate::register_messages].
+pub struct RegisteredMessage(pub &'static MessageName);
+
+#[cfg(feature = "automate-function-loading-boilerplate")]
+inventory::collect!(RegisteredMessage);
+
+/// Define the template of every message registered using
+/// [`register_messages!`][crate::register_messages].
+///
+/// # Safety
+///
+/// This function must be called from the main Kernel thread.
+#[cfg(feature = "automate-function-loading-boilerplate")]
+pub unsafe fn define_registered_messages() {
+    let kernel = crate::KernelThread::new_unchecked();
+
+    for message in inventory::iter::<RegisteredMessage> {
+        let _: bool = message.0.define(&kernel);
+    }
+}
+
 #[cfg(feature = "automate-function-loading-boilerplate")]
 pub unsafe fn load_library_functions_impl(
     lib_data: sys::WolframLibraryData,
@@ -243,6 +505,9 @@ pub unsafe fn load_library_functions_impl(
             std::path::PathBuf::from(path.as_str())
         };
 
+        // Safety: The Kernel calls the loader from the main thread.
+        define_registered_messages();
+
         let expr = exported_library_functions_association(Some(path));
 
         link.put_expr(&expr)
@@ -452,6 +717,10 @@ pub fn exported_library_functions_association(
     let rule = Symbol::new("System`Rule");
 
     for func in inventory::iter::<LibraryLinkFunction> {
+        if let LibraryLinkFunction::Class { register, .. } = func {
+            register();
+        }
+
         let code = match func.loading_code(&library) {
             Ok(code) => code,
             // TODO: Generate a message? Return a Failure[..]? Doing nothing seems
@@ -475,6 +744,7 @@ impl LibraryLinkFunction {
         match self {
             LibraryLinkFunction::Native { name, .. } => name,
             LibraryLinkFunction::Wstp { name } => name,
+            LibraryLinkFunction::Class { name, .. } => name,
         }
     }
 
@@ -491,16 +761,20 @@ impl LibraryLinkFunction {
                 .expect("unable to convert library file path to str"),
         );
 
+        let load_native = |name: &str, signature: SignatureFn| -> Result<Expr, String> {
+            let (args, ret) = signature()?;
+
+            Ok(Expr::normal(&lib_func_load, vec![
+                library.clone(),
+                Expr::string(name),
+                Expr::normal(sys("List"), args),
+                ret,
+            ]))
+        };
+
         let code = match self {
             LibraryLinkFunction::Native { name, signature } => {
-                let (args, ret) = signature()?;
-
-                Expr::normal(&lib_func_load, vec![
-                    library.clone(),
-                    Expr::string(*name),
-                    Expr::normal(sys("List"), args),
-                    ret,
-                ])
+                load_native(name, *signature)?
             },
             /*
                 With[{
@@ -556,6 +830,123 @@ impl LibraryLinkFunction {
                     )]),
                 ])
             },
+            /*
+                With[{func = LibraryFunctionLoad[...]},
+                    symbol[id_Integer]["method"][args___] := func[id, args]
+                ];
+                ...;
+                With[{new = LibraryFunctionLoad[...]},
+                    Function[
+                        Module[{obj, result},
+                            obj = CreateManagedLibraryExpression["name", symbol];
+                            result = new[ManagedLibraryExpressionID[obj], ##];
+                            If[MatchQ[result, _LibraryFunctionError | _Failure],
+                                result,
+                                obj
+                            ]
+                        ]
+                    ]
+                ]
+            */
+            LibraryLinkFunction::Class {
+                name,
+                symbol,
+                register: _,
+                constructor,
+                methods,
+            } => {
+                let symbol = Expr::from(Symbol::new(symbol));
+                let func = Expr::from(Symbol::new("RustLink`Private`classFunc"));
+                let id = Expr::from(Symbol::new("RustLink`Private`id"));
+                let args = Expr::from(Symbol::new("RustLink`Private`args"));
+                let obj = Expr::from(Symbol::new("RustLink`Private`obj"));
+                let result = Expr::from(Symbol::new("RustLink`Private`result"));
+
+                let with = |load_call: Expr, body: Expr| {
+                    Expr::normal(sys("With"), vec![
+                        Expr::normal(sys("List"), vec![Expr::normal(sys("Set"), vec![
+                            func.clone(),
+                            load_call,
+                        ])]),
+                        body,
+                    ])
+                };
+
+                let mut statements = Vec::new();
+
+                for method in methods.iter() {
+                    // symbol[id_Integer]["method"][args___]
+                    let lhs = Expr::normal(
+                        Expr::normal(
+                            Expr::normal(symbol.clone(), vec![Expr::normal(
+                                sys("Pattern"),
+                                vec![
+                                    id.clone(),
+                                    Expr::normal(sys("Blank"), vec![Expr::from(sys(
+                                        "Integer",
+                                    ))]),
+                                ],
+                            )]),
+                            vec![Expr::string(method.method)],
+                        ),
+                        vec![Expr::normal(sys("Pattern"), vec![
+                            args.clone(),
+                            Expr::normal(sys("BlankNullSequence"), vec![]),
+                        ])],
+                    );
+
+                    // func[id, args]
+                    let rhs = Expr::normal(func.clone(), vec![id.clone(), args.clone()]);
+
+                    statements.push(with(
+                        load_native(method.exported_name, method.signature)?,
+                        Expr::normal(sys("SetDelayed"), vec![lhs, rhs]),
+                    ));
+                }
+
+                // If the constructor failed, return its LibraryFunctionError[..] or
+                // Failure[..] instead of the uninitialized instance, which is then
+                // released when `obj` goes out of scope.
+                let failed = Expr::normal(sys("MatchQ"), vec![
+                    result.clone(),
+                    Expr::normal(sys("Alternatives"), vec![
+                        Expr::normal(sys("Blank"), vec![Expr::from(sys(
+                            "LibraryFunctionError",
+                        ))]),
+                        Expr::normal(sys("Blank"), vec![Expr::from(sys("Failure"))]),
+                    ]),
+                ]);
+
+                let constructor_body = Expr::normal(sys("Module"), vec![
+                    Expr::normal(sys("List"), vec![obj.clone(), result.clone()]),
+                    Expr::normal(sys("CompoundExpression"), vec![
+                        Expr::normal(sys("Set"), vec![
+                            obj.clone(),
+                            Expr::normal(sys("CreateManagedLibraryExpression"), vec![
+                                Expr::string(*name),
+                                symbol.clone(),
+                            ]),
+                        ]),
+                        Expr::normal(sys("Set"), vec![
+                            result.clone(),
+                            Expr::normal(func.clone(), vec![
+                                Expr::normal(sys("ManagedLibraryExpressionID"), vec![
+                                    obj.clone(),
+                                ]),
+                                Expr::normal(sys("SlotSequence"), vec![Expr::from(1)]),
+                            ]),
+                        ]),
+                        Expr::normal(sys("If"), vec![failed, result, obj]),
+                    ]),
+                ]);
+
+                statements.push(with(
+                    load_native(constructor.exported_name, constructor.signature)?,
+                    Expr::normal(sys("Function"), vec![constructor_body]),
+                ));
+
+                Expr::normal(sys("CompoundExpression"), statements)
+            },
         };
 
         Ok(code)
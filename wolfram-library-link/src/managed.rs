@@ -17,6 +17,10 @@
 //! In this way, managed expressions allow memory-management of Rust objects to be
 //! performed indirectly based on the lifetime of a Wolfram Language expression.
 //!
+//! [`ManagedStore<T>`] implements this pattern for a Rust type `T`, and [`Managed<T>`]
+//! can be used to access an instance of `T` from an [`#[export]`][crate::export]
+//! function.
+//!
 //  TODO: Expand and polish this section: # Alternatives
 //
 //  * Canonical WL expression representation
@@ -40,11 +44,25 @@
 //! [Managed Library Expressions]: https://reference.wolfram.com/language/LibraryLink/tutorial/InteractionWithWolframLanguage.html#353220453
 //! [ref/CreateManagedLibraryExpression]: https://reference.wolfram.com/language/ref/CreateManagedLibraryExpression.html
 
-use std::{ffi::CString, sync::Mutex};
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    ffi::{CStr, CString},
+    fmt,
+    ops::{Deref, DerefMut},
+    os::raw::c_int,
+    sync::{Arc, Mutex, MutexGuard, TryLockError},
+    thread::{self, ThreadId},
+};
 
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 
-use crate::{rtl, sys};
+use crate::{
+    expr::{Expr, Symbol},
+    rtl,
+    sys::{self, MArgument},
+    FromArg,
+};
 
 /// Lifecycle events triggered by the creation and deallocation of managed expressions.
 pub enum ManagedExpressionEvent {
@@ -78,17 +96,483 @@ impl ManagedExpressionEvent {
 pub type Id = u32;
 
 /// Register a new callback function for handling managed expression events.
-pub fn register_library_expression_manager(
+///
+/// `manage_instance` can be a function or a closure.
+///
+/// ```
+/// use std::{collections::HashMap, sync::Mutex};
+///
+/// use wolfram_library_link::managed::{self, Id, ManagedExpressionEvent};
+///
+/// struct Counter {
+///     count: u64,
+/// }
+///
+/// # fn register() {
+/// let counters: Mutex<HashMap<Id, Counter>> = Mutex::new(HashMap::new());
+///
+/// managed::register_library_expression_manager("Counter", move |event| match event {
+///     ManagedExpressionEvent::Create(id) => {
+///         counters.lock().unwrap().insert(id, Counter { count: 0 });
+///     },
+///     ManagedExpressionEvent::Drop(id) => {
+///         counters.lock().unwrap().remove(&id);
+///     },
+/// });
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// This function will panic if a library expression manager with the name `name` has
+/// already been registered, or if [`MAX_MANAGERS`] managers are currently registered.
+pub fn register_library_expression_manager<F>(name: &str, manage_instance: F)
+where
+    F: Fn(ManagedExpressionEvent) + Send + Sync + 'static,
+{
+    register_library_expression_manager_in_slot(name, Arc::new(manage_instance))
+}
+
+/// Unregister the library expression manager named `name`.
+///
+/// After this function returns `Ok(())`, no further events will be delivered to the
+/// callback function that was registered for `name`, and the name may be registered
+/// again.
+///
+/// If the Kernel returns an error, the callback function stays registered, because the
+/// Kernel may still deliver events to it.
+///
+/// *LibraryLink C API Documentation:* [`unregisterLibraryExpressionManager`](https://reference.wolfram.com/language/LibraryLink/ref/callback/unregisterLibraryExpressionManager.html)
+pub fn unregister_library_expression_manager(name: &str) -> Result<(), sys::errcode_t> {
+    let name_cstr = CString::new(name).expect("failed to allocate C string");
+
+    let err_code: c_int =
+        unsafe { rtl::unregisterLibraryExpressionManager(name_cstr.as_ptr()) };
+
+    if err_code != 0 {
+        return Err(err_code);
+    }
+
+    // Only free the slot once the Kernel no longer calls its wrapper function, so that
+    // a manager that reuses the slot doesn't receive events meant for this one.
+    REGISTRY.lock().unwrap().remove(name);
+
+    Ok(())
+}
+
+/// Release the managed expression instance with the specified `id`, which was created
+/// by the library expression manager named `name`.
+///
+/// This causes a [`ManagedExpressionEvent::Drop`] event to be delivered to the manager.
+/// Any remaining Wolfram Language references to the managed expression become invalid.
+///
+/// *LibraryLink C API Documentation:* [`releaseManagedLibraryExpression`](https://reference.wolfram.com/language/LibraryLink/ref/callback/releaseManagedLibraryExpression.html)
+pub fn release_managed_library_expression(
     name: &str,
-    manage_instance: fn(ManagedExpressionEvent),
-) {
-    register_using_next_slot(name, manage_instance)
+    id: Id,
+) -> Result<(), sys::errcode_t> {
+    let name_cstr = CString::new(name).expect("failed to allocate C string");
+
+    let err_code: c_int = unsafe {
+        rtl::releaseManagedLibraryExpression(name_cstr.as_ptr(), sys::mint::from(id))
+    };
+
+    if err_code != 0 {
+        return Err(err_code);
+    }
+
+    Ok(())
+}
+
+//======================================
+// ManagedStore<T>
+//======================================
+
+/// Storage for the instances of a Rust type associated with managed expressions.
+///
+/// A `ManagedStore` is typically stored in a `static`. When registered as the handler
+/// for a library expression manager using [`ManagedStore::register()`], it will
+/// automatically create a new instance of `T` each time a managed expression is created,
+/// and drop that instance when the managed expression is deallocated.
+///
+/// Instances can be accessed from [`#[export]`][crate::export] functions using a
+/// [`Managed<T>`] parameter, or by using [`ManagedStore::lock()`].
+///
+/// # Example
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{
+///     self as wll,
+///     managed::{Managed, ManagedStore},
+/// };
+///
+/// struct Counter {
+///     count: i64,
+/// }
+///
+/// static COUNTERS: ManagedStore<Counter> = ManagedStore::new();
+///
+/// #[wll::init]
+/// fn init() {
+///     COUNTERS.register("Counter", |_id| Counter { count: 0 });
+/// }
+///
+/// #[wll::export]
+/// fn increment_counter(mut counter: Managed<Counter>) -> i64 {
+///     counter.count += 1;
+///     counter.count
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// increment = LibraryFunctionLoad["...", "increment_counter", {Integer}, Integer];
+///
+/// counter = CreateManagedLibraryExpression["Counter", Counter];
+///
+/// increment[ManagedLibraryExpressionID[counter]]    (* Returns 1 *)
+/// ```
+pub struct ManagedStore<T> {
+    instances: Mutex<BTreeMap<Id, T>>,
+    /// The thread holding the [`Managed<T>`] that currently locks `instances`, if any.
+    borrowed_by: Mutex<Option<ThreadId>>,
+    /// The name of the library expression manager this store is registered with.
+    name: OnceCell<String>,
+}
+
+/// Exclusive reference to an instance stored in a [`ManagedStore<T>`].
+///
+/// `Managed<T>` can be used as the parameter type of an [`#[export]`][crate::export]
+/// function. The Wolfram Language passes the managed expression ID as an
+/// [`Integer`][ref/Integer], typically obtained using
+/// [`ManagedLibraryExpressionID`][ref/ManagedLibraryExpressionID]`[expr]`, and the
+/// function receives a reference to the corresponding `T`.
+///
+/// If there is no instance with that ID, the function is not called. Instead, a
+/// `LibraryFunction::rusterr` message describing the error is issued, and the function
+/// returns `LIBRARY_FUNCTION_ERROR`, the same as if it had returned an `Err(..)`.
+///
+/// The [`ManagedStore<T>`] that contains the instance is locked while the `Managed<T>`
+/// exists. A function that takes more than one `Managed<T>` parameter with the same
+/// `T` fails with an error.
+///
+/// While the store is locked, managed expressions of type `T` cannot be created or
+/// released on the same thread, e.g. by evaluating `CreateManagedLibraryExpression`
+/// from the function. The Create or Drop event fails with a panic, which can be
+/// retrieved using [`last_panic()`][crate::last_panic], instead of deadlocking.
+///
+/// [ref/Integer]: https://reference.wolfram.com/language/ref/Integer.html
+/// [ref/ManagedLibraryExpressionID]: https://reference.wolfram.com/language/ref/ManagedLibraryExpressionID.html
+pub struct Managed<T: 'static> {
+    id: Id,
+    store: &'static ManagedStore<T>,
+    instances: MutexGuard<'static, BTreeMap<Id, T>>,
+}
+
+/// [`ManagedStore`]s registered using [`ManagedStore::register()`], keyed by the
+/// [`TypeId`] of the stored type.
+static STORES: Lazy<Mutex<HashMap<TypeId, &'static (dyn Any + Send + Sync)>>> =
+    Lazy::new(Default::default);
+
+impl<T: Send + 'static> ManagedStore<T> {
+    /// Construct a new, empty store.
+    pub const fn new() -> Self {
+        ManagedStore {
+            instances: Mutex::new(BTreeMap::new()),
+            borrowed_by: Mutex::new(None),
+            name: OnceCell::new(),
+        }
+    }
+
+    /// Register this store as the handler for the library expression manager named
+    /// `name`.
+    ///
+    /// `create` is called to construct the instance associated with each new managed
+    /// expression.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if this store has already been registered, if
+    /// another `ManagedStore<T>` for the same type `T` has been registered, or if
+    /// [`register_library_expression_manager()`] panics.
+    pub fn register<F>(&'static self, name: &str, create: F)
+    where
+        F: Fn(Id) -> T + Send + Sync + 'static,
+    {
+        self.register_impl(name, Some(create))
+    }
+
+    /// Register this store as the handler for the library expression manager named
+    /// `name`, without creating an instance when a managed expression is created.
+    ///
+    /// Instances must instead be added explicitly using [`ManagedStore::insert()`].
+    /// They are still dropped automatically when the managed expression is deallocated.
+    ///
+    /// # Panics
+    ///
+    /// This function panics under the same conditions as [`ManagedStore::register()`].
+    pub fn register_deferred(&'static self, name: &str) {
+        self.register_impl::<fn(Id) -> T>(name, None)
+    }
+
+    fn register_impl<F>(&'static self, name: &str, create: Option<F>)
+    where
+        F: Fn(Id) -> T + Send + Sync + 'static,
+    {
+        if self.name.set(name.to_owned()).is_err() {
+            panic!(
+                "ManagedStore<{}> has already been registered with name '{}'",
+                type_name::<T>(),
+                self.name.get().unwrap()
+            );
+        }
+
+        {
+            let mut stores = STORES.lock().unwrap();
+
+            if stores.contains_key(&TypeId::of::<T>()) {
+                drop(stores);
+                panic!(
+                    "a ManagedStore<{}> has already been registered",
+                    type_name::<T>()
+                );
+            }
+
+            stores.insert(TypeId::of::<T>(), self);
+        }
+
+        register_library_expression_manager(name, move |event| {
+            // Locking the store would deadlock if this event was caused by the function
+            // that holds a `Managed<T>` from it.
+            if self.is_borrowed_by_current_thread() {
+                panic!(
+                    "cannot create or drop managed expression instance {}: \
+                    ManagedStore<{}> is locked by a Managed<{}> on this thread",
+                    event.id(),
+                    type_name::<T>(),
+                    type_name::<T>()
+                );
+            }
+
+            match event {
+                ManagedExpressionEvent::Create(id) => {
+                    if let Some(ref create) = create {
+                        // Construct the instance before locking, in case `create`
+                        // accesses this store.
+                        let instance = create(id);
+
+                        self.lock().insert(id, instance);
+                    }
+                },
+                ManagedExpressionEvent::Drop(id) => {
+                    let instance = self.lock().remove(&id);
+
+                    // Drop the instance after the lock has been released.
+                    drop(instance);
+                },
+            }
+        });
+    }
+
+    /// Get the name of the library expression manager this store was registered with.
+    pub fn name(&self) -> Option<&str> {
+        self.name.get().map(String::as_str)
+    }
+
+    /// Lock this store, and return the map containing every instance.
+    pub fn lock(&self) -> MutexGuard<'_, BTreeMap<Id, T>> {
+        self.instances.lock().unwrap()
+    }
+
+    /// Insert `instance` as the instance associated with the managed expression `id`,
+    /// returning the previous instance, if any.
+    pub fn insert(&self, id: Id, instance: T) -> Option<T> {
+        self.lock().insert(id, instance)
+    }
+
+    /// Returns true if this store contains an instance with the specified `id`.
+    pub fn contains(&self, id: Id) -> bool {
+        self.lock().contains_key(&id)
+    }
+
+    /// Get an exclusive reference to the instance with the specified `id`, or `None` if
+    /// there is no such instance.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the current thread already holds a [`Managed<T>`]
+    /// from this store.
+    pub fn get(&'static self, id: Id) -> Option<Managed<T>> {
+        match self.try_get(id) {
+            Ok(instance) => instance,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Get an exclusive reference to the instance with the specified `id`, or return an
+    /// error instead of deadlocking if the current thread already holds a `Managed<T>`
+    /// from this store.
+    fn try_get(&'static self, id: Id) -> Result<Option<Managed<T>>, String> {
+        let instances = match self.instances.try_lock() {
+            Ok(instances) => instances,
+            Err(TryLockError::WouldBlock) => {
+                if self.is_borrowed_by_current_thread() {
+                    return Err(format!(
+                        "Managed<{}>: ManagedStore is already locked by a Managed<{}> on \
+                        this thread",
+                        type_name::<T>(),
+                        type_name::<T>()
+                    ));
+                }
+
+                // Another thread holds the lock, so wait for it to be released.
+                self.lock()
+            },
+            Err(TryLockError::Poisoned(err)) => panic!("{}", err),
+        };
+
+        if !instances.contains_key(&id) {
+            return Ok(None);
+        }
+
+        *self.borrowed_by.lock().unwrap() = Some(thread::current().id());
+
+        Ok(Some(Managed {
+            id,
+            store: self,
+            instances,
+        }))
+    }
+
+    fn is_borrowed_by_current_thread(&self) -> bool {
+        *self.borrowed_by.lock().unwrap() == Some(thread::current().id())
+    }
+}
+
+impl<T: Send + 'static> Default for ManagedStore<T> {
+    fn default() -> Self {
+        ManagedStore::new()
+    }
+}
+
+impl<T> fmt::Debug for ManagedStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ManagedStore")
+            .field("name", &self.name.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Get the store registered for `T` using [`ManagedStore::register()`].
+fn registered_store<T: Send + 'static>() -> Option<&'static ManagedStore<T>> {
+    let store: &'static (dyn Any + Send + Sync) =
+        *STORES.lock().unwrap().get(&TypeId::of::<T>())?;
+
+    store.downcast_ref()
+}
+
+impl<T> Managed<T> {
+    /// Get the managed expression ID of this instance.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
+
+impl<T> Drop for Managed<T> {
+    fn drop(&mut self) {
+        // `instances` is unlocked after this returns.
+        *self.store.borrowed_by.lock().unwrap() = None;
+    }
+}
+
+impl<T> Deref for Managed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.instances
+            .get(&self.id)
+            .expect("Managed<T>: instance was removed while locked")
+    }
+}
+
+impl<T> DerefMut for Managed<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.instances
+            .get_mut(&self.id)
+            .expect("Managed<T>: instance was removed while locked")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Managed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Managed")
+            .field("id", &self.id)
+            .field("value", &**self)
+            .finish()
+    }
+}
+
+impl<'a, T: Send + 'static> FromArg<'a> for Managed<T> {
+    unsafe fn from_arg(arg: &'a MArgument) -> Managed<T> {
+        match Managed::try_from_arg(arg) {
+            Ok(instance) => instance,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    unsafe fn try_from_arg(arg: &'a MArgument) -> Result<Managed<T>, String> {
+        let store: &'static ManagedStore<T> = match registered_store::<T>() {
+            Some(store) => store,
+            None => {
+                return Err(format!(
+                    "Managed<{}>: no ManagedStore has been registered for this type",
+                    type_name::<T>()
+                ))
+            },
+        };
+
+        let raw_id: sys::mint = *arg.integer;
+
+        let instance = match Id::try_from(raw_id) {
+            Ok(id) => store.try_get(id)?,
+            Err(_) => None,
+        };
+
+        instance.ok_or_else(|| {
+            format!(
+                "Managed<{}>: no '{}' managed expression instance with ID {}",
+                type_name::<T>(),
+                store.name().unwrap_or_default(),
+                raw_id
+            )
+        })
+    }
+
+    fn parameter_type() -> Expr {
+        Expr::from(Symbol::new("System`Integer"))
+    }
 }
 
 //======================================
 // C wrapper functions
 //======================================
 
+/// The maximum number of library expression managers that can be registered at the
+/// same time.
+///
+/// The C callback passed to `registerLibraryExpressionManager()` carries no user data,
+/// so each registered manager is called through its own static `extern "C"` function.
+/// Those functions are generated ahead of time, which fixes the number of managers.
+///
+/// Unregistering a manager using [`unregister_library_expression_manager()`] makes its
+/// slot available again.
+pub const MAX_MANAGERS: usize = 256;
+
+type ManagerFn = Arc<dyn Fn(ManagedExpressionEvent) + Send + Sync>;
+
+/// Registered library expression managers.
+///
 /// # Implementation note on the reason for this static / "slot" system.
 ///
 /// Having this static is not a direct requirement of the library expression
@@ -102,10 +586,10 @@ pub fn register_library_expression_manager(
 ///
 /// however, for the purpose of providing a more ergonomic and safe wrapper to the user,
 /// we want the user to be able to pass `register_library_expression_manager()` a callback
-/// function with the type:
+/// closure with the type:
 ///
 /// ```ignore
-///     fn(ManagedExpressionAction)
+///     Fn(ManagedExpressionEvent)
 /// ```
 ///
 /// This specific problem is an instance of the more general problem of how to expose a
@@ -131,112 +615,145 @@ pub fn register_library_expression_manager(
 /// case, because their is no way to pass a custom argument to the callback expected by
 /// registerLibraryExpressionManager().
 ///
-/// In both the (1.) and (2.) strategies, the solution is to create a single wrapper
-/// function for each user function, which is hard-coded to call the user function that
-/// it wraps.
-///
 /// The technique used here is a third strategy:
 ///
-/// 3. Store the user-provided function pointer into a static array, and, instead of
-///    having a single `extern "C"` wrapper function, have multiple `extern "C"` wrapper
+/// 3. Store the user-provided closure into a static array, and, instead of having a
+///    single `extern "C"` wrapper function, have multiple `extern "C"` wrapper
 ///    functions, each of which statically access a different index in the static array.
 ///
 ///    By using different `extern "C"` functions that access different static data, we
 ///    can essentially "fake" having an extra function argument that we control.
 ///
-///    This depends on the observation that the callback function pointer is itself a
-///    value we control.
-///
-///    This technique is limited by the fact that the static function pointers must be
-///    declared ahead of time (see `def_slot_fn!` below), and so practically there is a
-///    somewhat arbitrary limit on how many callbacks can be registered at a time.
+///    The wrapper functions are the instantiations of the generic `slot_fn::<INDEX>()`
+///    function listed in `SLOT_FNS`, so the number of managers that can be registered at
+///    the same time is limited to [`MAX_MANAGERS`]. Slots are reused after a manager is
+///    unregistered.
 ///
 /// In our case, the *only* data we are able pass through the C API is the static function
 /// pointer we are registering; so strategy (3.) is the way to go.
 ///
-/// `SLOTS` has 8 elements, and we define 8 `extern "C" fn slot_<X>(..)` functions that
-/// access only the corresponding element in `SLOTS`.
-///
-/// 8 was picked arbitrarily, on the assumption that 8 different registered types should
-/// be sufficient for the vast majority of libraries. Libraries that want to register more
-/// than 8 types can use `rtl::registerLibraryExpressionManager` directly as a workaround.
-///
 /// TODO: Also store the "name" of this manager, and pass it to the user function?
-static SLOTS: Lazy<Mutex<[Option<fn(ManagedExpressionEvent)>; 8]>> =
-    Lazy::new(|| Mutex::new([None; 8]));
+static REGISTRY: Lazy<Mutex<Registry<ManagerFn>>> =
+    Lazy::new(|| Mutex::new(Registry::new()));
 
-fn register_using_next_slot(name: &str, manage_instance: fn(ManagedExpressionEvent)) {
-    let name_cstr = CString::new(name).expect("failed to allocate C string");
+/// Functions registered by name, each stored in the slot that is called by the static
+/// `extern "C"` wrapper function with the same index. See the implementation note on
+/// [`REGISTRY`].
+///
+/// This is also used to store library callback managers, in [`crate::callback`].
+pub(crate) struct Registry<F> {
+    /// Map from name to the index of its slot in `slots`.
+    names: HashMap<String, usize>,
+    slots: Vec<Option<F>>,
+}
 
-    let mut slots = SLOTS.lock().unwrap();
+impl<F: Clone> Registry<F> {
+    pub(crate) fn new() -> Self {
+        Registry {
+            names: HashMap::new(),
+            slots: vec![None; MAX_MANAGERS],
+        }
+    }
 
-    let available_slot: Option<(usize, &mut Option<_>)> = slots
-        .iter_mut()
-        .enumerate()
-        .filter(|(_, slot)| slot.is_none())
-        .next();
+    /// Get the function stored in `slot`, if any.
+    pub(crate) fn get(&self, slot: usize) -> Option<F> {
+        self.slots[slot].clone()
+    }
 
-    let result = if let Some((index, slot)) = available_slot {
-        *slot = Some(manage_instance);
-        register_using_slot(name_cstr, index)
-    } else {
-        // Drop `slots` to avoid poisoning SLOTS when we panic.
+    /// Free the slot used by `name`, if any.
+    fn remove(&mut self, name: &str) {
+        if let Some(index) = self.names.remove(name) {
+            self.slots[index] = None;
+        }
+    }
+}
+
+/// Store `func` in the next free slot of `registry`, and register the wrapper function
+/// for that slot with the Kernel by calling `register(name, index)`.
+///
+/// `kind` describes the manager in panic messages, e.g. "library expression manager".
+///
+/// # Panics
+///
+/// This function will panic if `name` has already been registered, if every slot is
+/// in use, or if `register()` returns a non-zero error code, for example because the
+/// Kernel already has a manager named `name` that was not registered using `registry`.
+pub(crate) fn register_using_next_slot<F: Clone>(
+    registry: &Mutex<Registry<F>>,
+    kind: &str,
+    name: &str,
+    func: F,
+    register: impl FnOnce(&CStr, usize) -> c_int,
+) {
+    let name_cstr = CString::new(name).expect("failed to allocate C string");
+
+    let mut slots = registry.lock().unwrap();
+
+    if slots.names.contains_key(name) {
+        // Drop `slots` to avoid poisoning `registry` when we panic.
         drop(slots);
-        panic!("maxiumum number of library expression managers have been registered");
+        panic!("{} with name '{}' has already been registered", kind, name);
+    }
+
+    let index = match slots.slots.iter().position(Option::is_none) {
+        Some(index) => index,
+        None => {
+            drop(slots);
+            panic!(
+                "maximum number of {}s ({}) have been registered",
+                kind, MAX_MANAGERS
+            );
+        },
     };
 
+    slots.slots[index] = Some(func);
+    slots.names.insert(name.to_owned(), index);
+
+    // Don't hold the lock while calling into the Kernel.
     drop(slots);
 
-    if let Err(()) = result {
+    let err_code: c_int = register(&name_cstr, index);
+
+    if err_code != 0 {
+        // Free the slot again, so that it can be reused.
+        registry.lock().unwrap().remove(name);
+
         panic!(
-            "library expression manager with name '{}' has already been registered",
-            name
+            "failed to register {} with name '{}': Kernel returned error code {}",
+            kind, name, err_code
         );
     }
 }
 
-fn register_using_slot(name_cstr: CString, index: usize) -> Result<(), ()> {
-    let static_slot_fn: unsafe extern "C" fn(_, _, _) = match index {
-        0 => slot_0,
-        1 => slot_1,
-        2 => slot_2,
-        3 => slot_3,
-        4 => slot_4,
-        5 => slot_5,
-        6 => slot_6,
-        7 => slot_7,
-        8 => slot_8,
-        _ => unreachable!(),
-    };
-
-    let err_code: i32 = unsafe {
-        rtl::registerLibraryExpressionManager(name_cstr.as_ptr(), Some(static_slot_fn))
-    };
-
-    if err_code != 0 {
-        Err(())
-    } else {
-        Ok(())
-    }
+fn register_library_expression_manager_in_slot(name: &str, manage_instance: ManagerFn) {
+    register_using_next_slot(
+        &REGISTRY,
+        "library expression manager",
+        name,
+        manage_instance,
+        |name_cstr, index| unsafe {
+            rtl::registerLibraryExpressionManager(
+                name_cstr.as_ptr(),
+                Some(SLOT_FNS[index / 16][index % 16]),
+            )
+        },
+    )
 }
 
 //--------------------------
-// Static slot_<X> functions
+// Static slot_fn functions
 //--------------------------
 
 fn call_callback_in_slot(slot: usize, mode: sys::mbool, id: sys::mint) {
-    let slots = SLOTS.lock().unwrap();
-
-    let user_fn: fn(ManagedExpressionEvent) = match slots[slot] {
+    // Don't hold the lock on REGISTRY while calling `user_fn`, to avoid poisoning
+    // REGISTRY in the case `user_fn` panics, and to allow `user_fn` to register or
+    // release managers.
+    let user_fn: ManagerFn = match REGISTRY.lock().unwrap().get(slot) {
         Some(func) => func,
         // TODO: Set something like "RustLink`$LibraryLastError" with a descriptive error?
         None => return,
     };
 
-    // Ensure we're not holding a lock on `slots`, to avoid poisoning SLOTS in the case
-    // `user_fn` panics.
-    drop(slots);
-
     let id: u32 = match u32::try_from(id) {
         Ok(id) => id,
         // TODO: Set something like "RustLink`$LibraryLastError" with a descriptive error?
@@ -252,32 +769,42 @@ fn call_callback_in_slot(slot: usize, mode: sys::mbool, id: sys::mint) {
     user_fn(action)
 }
 
-macro_rules! def_slot_fn {
-    ($name:ident, $index:literal) => {
-        unsafe extern "C" fn $name(
-            // Assume this library is already initialized.
-            _: sys::WolframLibraryData,
-            mode: sys::mbool,
-            id: sys::mint,
-        ) {
-            let result = crate::catch_panic::call_and_catch_panic(|| {
-                call_callback_in_slot($index, mode, id)
-            });
-
-            if let Err(_) = result {
-                // Do nothing.
-                // TODO: Set something like "RustLink`$LibraryLastError" with this panic?
-            }
-        }
+type SlotFn = unsafe extern "C" fn(sys::WolframLibraryData, sys::mbool, sys::mint);
+
+unsafe extern "C" fn slot_fn<const INDEX: usize>(
+    // Assume this library is already initialized.
+    _: sys::WolframLibraryData,
+    mode: sys::mbool,
+    id: sys::mint,
+) {
+    let result = crate::catch_panic::call_and_catch_panic(|| {
+        call_callback_in_slot(INDEX, mode, id)
+    });
+
+    if let Err(panic) = result {
+        crate::catch_panic::set_last_panic(panic);
+    }
+}
+
+/// Construct a `[[F; 16]; MAX_MANAGERS / 16]` table of the instantiations of the
+/// generic function `$slot_fn::<INDEX>()`, where `table[index / 16][index % 16]` is the
+/// wrapper function for slot `index`.
+macro_rules! slot_fns {
+    ($slot_fn:ident) => {
+        $crate::managed::slot_fns!(@table $slot_fn;
+            0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15;
+            [0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15]
+        )
+    };
+    (@table $slot_fn:ident; $($row:literal)*; $columns:tt) => {
+        [$( $crate::managed::slot_fns!(@row $slot_fn; $row; $columns) ),*]
+    };
+    (@row $slot_fn:ident; $row:literal; [$($column:literal)*]) => {
+        [$( $slot_fn::<{ $row * 16 + $column }> ),*]
     };
 }
 
-def_slot_fn!(slot_0, 0);
-def_slot_fn!(slot_1, 1);
-def_slot_fn!(slot_2, 2);
-def_slot_fn!(slot_3, 3);
-def_slot_fn!(slot_4, 4);
-def_slot_fn!(slot_5, 5);
-def_slot_fn!(slot_6, 6);
-def_slot_fn!(slot_7, 7);
-def_slot_fn!(slot_8, 8);
+pub(crate) use slot_fns;
+
+/// `SLOT_FNS[index / 16][index % 16]` is the wrapper function for `slots[index]`.
+const SLOT_FNS: [[SlotFn; 16]; MAX_MANAGERS / 16] = slot_fns!(slot_fn);
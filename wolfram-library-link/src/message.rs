@@ -0,0 +1,406 @@
+use crate::{
+    expr::{Expr, ExprKind, Symbol},
+    sys::mint,
+    KernelThread, TensorDataType,
+};
+
+/// Name of a Wolfram Language message, such as `MyFunction::badarg`, optionally with a
+/// default message template.
+///
+/// Use [`MessageName::issue()`] or the [`message!`][crate::message!] macro to issue the
+/// message from a LibraryLink function. Messages can only be issued from the main
+/// Kernel thread, so both require a [`&KernelThread`][KernelThread]. Code that might
+/// run on another thread can use [`MessageName::try_issue()`] instead.
+///
+/// The symbol part of the name is resolved when the message is issued, using the
+/// current value of [`$Context`][ref/$Context]<sub>WL</sub> and
+/// [`$ContextPath`][ref/$ContextPath]<sub>WL</sub>. Prefer fully qualified names, like
+/// ``"MyPackage`MyFunction::badarg"``, for messages associated with library functions.
+///
+/// A message with a template is defined when it is first issued. Use
+/// [`register_messages!`][crate::register_messages] to define it when the library is
+/// loaded instead.
+///
+/// # Example
+///
+/// Define a message with a template, and issue it from an exported function:
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, KernelThread, MessageName, ToExpr};
+///
+/// static BADARG: MessageName = MessageName::new("MyPackage`MyFunction::badarg")
+///     .with_template("Argument `1` is not a positive integer.");
+///
+/// #[wll::export]
+/// fn my_function(kernel: &KernelThread, x: i64) -> i64 {
+///     if x <= 0 {
+///         BADARG.issue(kernel, &[x.to_expr()]);
+///         return 0;
+///     }
+///
+///     x * 2
+/// }
+/// # }
+/// ```
+///
+/// [ref/$Context]: https://reference.wolfram.com/language/ref/$Context.html
+/// [ref/$ContextPath]: https://reference.wolfram.com/language/ref/$ContextPath.html
+#[derive(Debug, Copy, Clone)]
+pub struct MessageName {
+    name: &'static str,
+    template: Option<&'static str>,
+}
+
+impl MessageName {
+    /// Construct a new message name from a string of the form `"symbol::tag"`.
+    ///
+    /// # Panics
+    ///
+    /// [`issue()`][MessageName::issue] and [`define()`][MessageName::define] will panic
+    /// if `name` does not contain `"::"`.
+    pub const fn new(name: &'static str) -> Self {
+        MessageName {
+            name,
+            template: None,
+        }
+    }
+
+    /// Set the template used for this message if the message is not already defined.
+    ///
+    /// The template may reference the message arguments using `` `1` ``, `` `2` ``, etc.
+    pub const fn with_template(self, template: &'static str) -> Self {
+        MessageName {
+            name: self.name,
+            template: Some(template),
+        }
+    }
+
+    /// The name of this message, as passed to [`MessageName::new()`].
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The template of this message, if one was specified.
+    pub fn template(&self) -> Option<&'static str> {
+        self.template
+    }
+
+    /// Define the template of this message, overwriting any existing definition.
+    ///
+    /// This is typically called once when the library is loaded, so that the message
+    /// text is available even before the message is first issued. Returns `false`
+    /// without doing anything if this message has no template, or if the evaluation
+    /// failed.
+    pub fn define(&self, kernel: &KernelThread) -> bool {
+        let define = match self.define_expr() {
+            Some(define) => define,
+            None => return false,
+        };
+
+        evaluate_with_runtime(kernel, &define)
+    }
+
+    /// Issue this message with the specified arguments.
+    ///
+    /// If this message has a template and is not already defined, the template is
+    /// defined before the message is issued.
+    ///
+    /// The message is issued by evaluating [`Message`][ref/Message]<sub>WL</sub> using
+    /// the `evaluateExpression()` runtime function, so this does not use the WSTP link
+    /// and can be called from any LibraryLink function.
+    ///
+    /// Use [`MessageName::try_issue()`] to issue a message from code that might not
+    /// run on the main Kernel thread.
+    ///
+    /// Returns `false` if the evaluation failed.
+    ///
+    /// [ref/Message]: https://reference.wolfram.com/language/ref/Message.html
+    pub fn issue(&self, kernel: &KernelThread, args: &[Expr]) -> bool {
+        evaluate_with_runtime(kernel, &self.issue_expr(args))
+    }
+
+    /// Issue this message with the specified arguments if the current thread is the
+    /// main Kernel thread.
+    ///
+    /// Unlike [`MessageName::issue()`], this does not require a
+    /// [`&KernelThread`][KernelThread], and can be called from any thread. Returns an
+    /// error without issuing the message if the current thread is not the main Kernel
+    /// thread, or if the evaluation failed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use wolfram_library_link::{self as wll, MessageName, NumericArray, ToExpr};
+    ///
+    /// static PROGRESS: MessageName = MessageName::new("MyPackage`MyFunction::progress")
+    ///     .with_template("Processed `1` items.");
+    ///
+    /// fn process(items: &[i64]) {
+    ///     // ...
+    ///
+    ///     // Only reported if `process()` was called from the main Kernel thread.
+    ///     let _ = PROGRESS.try_issue(&[items.len().to_expr()]);
+    /// }
+    ///
+    /// #[wll::export]
+    /// fn my_function(items: &NumericArray<i64>) {
+    ///     let items = items.as_slice();
+    ///
+    ///     std::thread::scope(|scope| {
+    ///         let (head, tail) = items.split_at(items.len() / 2);
+    ///
+    ///         scope.spawn(|| process(head));
+    ///         process(tail);
+    ///     });
+    /// }
+    /// # }
+    /// ```
+    pub fn try_issue(&self, args: &[Expr]) -> Result<(), String> {
+        let kernel = match KernelThread::current() {
+            Some(kernel) => kernel,
+            None => {
+                return Err(format!(
+                    "unable to issue message {}: not called from the main Kernel thread",
+                    self.name
+                ))
+            },
+        };
+
+        if !self.issue(&kernel, args) {
+            return Err(format!(
+                "unable to issue message {}: evaluation failed",
+                self.name
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `With[{s = Symbol["symbol"]}, MessageName[s, "tag"] = "template"]`, or `None`
+    /// if this message has no template.
+    fn define_expr(&self) -> Option<Expr> {
+        let template = self.template?;
+
+        let set = Expr::normal(Symbol::new("System`Set"), vec![
+            self.message_name_expr(),
+            Expr::string(template),
+        ]);
+
+        Some(self.with_symbol(set))
+    }
+
+    /// Code that issues this message, defining the template first if necessary.
+    fn issue_expr(&self, args: &[Expr]) -> Expr {
+        let message_name = self.message_name_expr();
+
+        let mut body = Vec::with_capacity(2);
+
+        if let Some(template) = self.template {
+            // If[!StringQ[MessageName[s, "tag"]], MessageName[s, "tag"] = "template"]
+            body.push(Expr::normal(Symbol::new("System`If"), vec![
+                Expr::normal(Symbol::new("System`Not"), vec![Expr::normal(
+                    Symbol::new("System`StringQ"),
+                    vec![message_name.clone()],
+                )]),
+                Expr::normal(Symbol::new("System`Set"), vec![
+                    message_name.clone(),
+                    Expr::string(template),
+                ]),
+            ]));
+        }
+
+        // Message[MessageName[s, "tag"], args...]
+        let mut message_args = Vec::with_capacity(args.len() + 1);
+        message_args.push(message_name);
+        message_args.extend(args.iter().cloned());
+
+        body.push(Expr::normal(Symbol::new("System`Message"), message_args));
+
+        let body = Expr::normal(Symbol::new("System`CompoundExpression"), body);
+
+        self.with_symbol(body)
+    }
+
+    /// Split this message name into its symbol name and tag.
+    fn parts(&self) -> (&'static str, &'static str) {
+        match self.name.split_once("::") {
+            Some(parts) => parts,
+            None => panic!(
+                "invalid message name: expected \"symbol::tag\", got {:?}",
+                self.name
+            ),
+        }
+    }
+
+    /// `MessageName[s, "tag"]`, where `s` is bound by [`MessageName::with_symbol()`].
+    fn message_name_expr(&self) -> Expr {
+        let (_, tag) = self.parts();
+
+        Expr::normal(Symbol::new("System`MessageName"), vec![
+            Expr::from(Symbol::new("RustLink`Private`messageSymbol")),
+            Expr::string(tag),
+        ])
+    }
+
+    /// `With[{s = Symbol["symbol"]}, body]`
+    ///
+    /// `MessageName` holds its first argument, so the symbol is resolved using `With`,
+    /// which substitutes into held expressions.
+    fn with_symbol(&self, body: Expr) -> Expr {
+        let (symbol, _) = self.parts();
+
+        Expr::normal(Symbol::new("System`With"), vec![
+            Expr::normal(Symbol::new("System`List"), vec![Expr::normal(
+                Symbol::new("System`Set"),
+                vec![
+                    Expr::from(Symbol::new("RustLink`Private`messageSymbol")),
+                    Expr::normal(Symbol::new("System`Symbol"), vec![Expr::string(
+                        symbol,
+                    )]),
+                ],
+            )]),
+            body,
+        ])
+    }
+}
+
+/// Evaluate `expr` for its side effects using the `evaluateExpression()` runtime
+/// function, returning `false` if the evaluation failed.
+fn evaluate_with_runtime(kernel: &KernelThread, expr: &Expr) -> bool {
+    // expr; 0
+    let code = format!("{}; 0", input_form(expr));
+
+    let mut result: mint = 0;
+
+    kernel
+        .evaluate_expression(&code, TensorDataType::Integer, 0, &mut result)
+        .is_ok()
+}
+
+/// Format `expr` as Wolfram Language code that can be parsed by
+/// [`ToExpression`][ref/ToExpression]<sub>WL</sub>.
+///
+/// Unlike the [`Display`][std::fmt::Display] implementation of [`Expr`], this escapes
+/// strings using Wolfram Language syntax, and writes reals using `*^` exponents.
+///
+/// [ref/ToExpression]: https://reference.wolfram.com/language/ref/ToExpression.html
+fn input_form(expr: &Expr) -> String {
+    match expr.kind() {
+        ExprKind::Normal(normal) => {
+            let elements: Vec<String> =
+                normal.elements().iter().map(input_form).collect();
+
+            format!("{}[{}]", input_form(normal.head()), elements.join(", "))
+        },
+        ExprKind::String(string) => {
+            let mut quoted = String::with_capacity(string.len() + 2);
+
+            quoted.push('"');
+
+            for c in string.chars() {
+                match c {
+                    '"' | '\\' => {
+                        quoted.push('\\');
+                        quoted.push(c);
+                    },
+                    ' '..='~' => quoted.push(c),
+                    // \|XXXXXX is the long form of a character with hex code XXXXXX.
+                    _ => quoted.push_str(&format!("\\|{:06x}", u32::from(c))),
+                }
+            }
+
+            quoted.push('"');
+            quoted
+        },
+        ExprKind::Real(real) => real_input_form(real.into_inner()),
+        ExprKind::Integer(_) | ExprKind::Symbol(_) => expr.to_string(),
+    }
+}
+
+/// Format `real` as Wolfram Language code.
+///
+/// Infinite values are written as [`DirectedInfinity`][ref/DirectedInfinity]<sub>WL</sub>.
+/// An [`Expr`] cannot contain NaN; [`ToExpr`][crate::ToExpr] converts it to
+/// [`Indeterminate`][ref/Indeterminate]<sub>WL</sub>.
+///
+/// [ref/DirectedInfinity]: https://reference.wolfram.com/language/ref/DirectedInfinity.html
+/// [ref/Indeterminate]: https://reference.wolfram.com/language/ref/Indeterminate.html
+fn real_input_form(real: f64) -> String {
+    if real == f64::INFINITY {
+        "System`DirectedInfinity[1]".to_owned()
+    } else if real == f64::NEG_INFINITY {
+        "System`DirectedInfinity[-1]".to_owned()
+    } else {
+        format!("{:?}", real).replace('e', "*^")
+    }
+}
+
+/// Issue a Wolfram Language message from Rust.
+///
+/// The first argument is a [`&KernelThread`][KernelThread], and the second is the
+/// message name, of the form `"symbol::tag"`. Any further arguments are converted to
+/// [`Expr`] using [`ToExpr`][crate::ToExpr] and passed as the message arguments.
+///
+/// `message!(kernel, name, args..)` is equivalent to
+/// [`MessageName::new(name).issue(kernel, &[args..])`][MessageName::issue].
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, expr::Expr, KernelThread};
+///
+/// #[wll::export(wstp)]
+/// fn my_function(args: Vec<Expr>) {
+///     let kernel = KernelThread::current().unwrap();
+///
+///     if args.len() != 1 {
+///         // Issues the message `MyPackage`MyFunction::argx`.
+///         wll::message!(&kernel, "MyPackage`MyFunction::argx", "MyFunction", args.len());
+///     }
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! message {
+    ($kernel:expr, $name:expr $(, $arg:expr)* $(,)?) => {
+        $crate::MessageName::new($name)
+            .issue($kernel, &[$($crate::ToExpr::to_expr(&$arg)),*])
+    };
+}
+
+/// Define the templates of `static` [`MessageName`]s when the library is loaded.
+///
+/// Each argument is the path of a `static` `MessageName`. The templates are defined by
+/// the loader function generated by [`generate_loader!`][crate::generate_loader], so
+/// that the text of each message is available before it is first issued.
+///
+/// *Requires the `"automate-function-loading-boilerplate"` feature.*
+///
+/// # Example
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, MessageName};
+///
+/// static BADARG: MessageName = MessageName::new("MyPackage`MyFunction::badarg")
+///     .with_template("Argument `1` is not a positive integer.");
+///
+/// wll::register_messages!(BADARG);
+///
+/// wll::generate_loader!(load_my_library_functions);
+/// # }
+/// ```
+#[cfg(feature = "automate-function-loading-boilerplate")]
+#[macro_export]
+macro_rules! register_messages {
+    ($($message:path),* $(,)?) => {
+        $(
+            $crate::inventory::submit! {
+                $crate::macro_utils::RegisteredMessage(&$message)
+            }
+        )*
+    };
+}
@@ -530,6 +530,20 @@ impl<T> NumericArray<T> {
         raw
     }
 
+    /// Get the raw [`MNumericArray`][sys::MNumericArray] object wrapped by this
+    /// `NumericArray`.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is still owned by `self`, and must not be freed or disowned,
+    /// or used after `self` has been dropped.
+    #[inline]
+    pub unsafe fn as_raw(&self) -> sys::MNumericArray {
+        let NumericArray(raw, PhantomData) = *self;
+
+        raw
+    }
+
     /// *LibraryLink C API Documentation:* [`MNumericArray_getData`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MNumericArray_getData.html)
     pub fn data_ptr(&self) -> *mut c_void {
         let NumericArray(numeric_array, _) = *self;
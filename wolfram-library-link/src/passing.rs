@@ -0,0 +1,424 @@
+//! Wrapper types that select the *LibraryLink* memory management strategy used to pass
+//! an array argument.
+//!
+//! *LibraryLink* supports several strategies for passing a [`Tensor`], [`SparseArray`],
+//! [`NumericArray`], or [`Image`] argument to a library function:
+//!
+//! Strategy     | Rust parameter type
+//! -------------|-----------------------------------------------------------------
+//! `"Constant"` | `&NumericArray<T>`, `&Tensor<T>`, `&SparseArray<T>`, `&Image<T>`
+//! `"Shared"`   | [`Shared<A>`]
+//! `"Manual"`   | [`Manual<A>`]
+//!
+//! See the [Memory Management][memory-management] section of the *LibraryLink* User
+//! Guide for a description of each strategy.
+//!
+//! [memory-management]: https://reference.wolfram.com/language/LibraryLink/tutorial/InteractionWithWolframLanguage.html#97446640
+
+use std::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    expr::{Expr, Symbol},
+    rtl,
+    sys::MArgument,
+    FromArg, Image, IntoArg, NumericArray, SparseArray, Tensor,
+};
+
+/// Array argument passed using the `"Shared"` memory management strategy.
+///
+/// The array data is shared between the Wolfram Language and the library function. No
+/// copy is made, and any changes made to the array from Rust will be visible to the
+/// Wolfram Language.
+///
+/// When a `Shared` value is dropped, the library's share of the array is released
+/// using `MTensor_disown()`, `MSparseArray_disown()`, `MNumericArray_disown()`, or
+/// `MImage_disown()`.
+///
+/// # Example
+///
+/// Double every element of a numeric array in place:
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{export, NumericArray, Shared};
+///
+/// #[export]
+/// fn double_in_place(mut array: Shared<NumericArray<f64>>) {
+///     if let Some(data) = array.as_slice_mut() {
+///         for elem in data {
+///             *elem *= 2.0;
+///         }
+///     }
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad[
+///     "...", "double_in_place",
+///     {{LibraryDataType[NumericArray, "Real64"], "Shared"}},
+///     "Void"
+/// ]
+/// ```
+pub struct Shared<A: NativeArray>(ManuallyDrop<A>);
+
+/// Array argument passed using the `"Manual"` memory management strategy.
+///
+/// The library function takes ownership of the array, and is responsible for freeing
+/// it. When a `Manual` value is dropped, the array is freed using `MTensor_free()`,
+/// `MSparseArray_free()`, `MNumericArray_free()`, or `MImage_free()`.
+///
+/// A `Manual` array can be returned from a library function, in which case ownership is
+/// passed back to the Wolfram Language and the array is not freed.
+pub struct Manual<A: NativeArray>(ManuallyDrop<A>);
+
+/// Trait implemented for the native array types that can be wrapped in [`Shared`] or
+/// [`Manual`].
+///
+/// Those types are:
+///
+///   * [`NumericArray<T>`]
+///   * [`Tensor<T>`]
+///   * [`SparseArray<T>`]
+///   * [`Image<T>`]
+pub trait NativeArray: private::NativeArrayImpl {}
+
+mod private {
+    use crate::{expr::Expr, sys::MArgument};
+
+    pub trait NativeArrayImpl: Sized {
+        /// The leading elements of the `LibraryFunctionLoad` parameter specification
+        /// for this type, not including the memory management strategy.
+        fn type_spec() -> Vec<Expr>;
+
+        unsafe fn from_arg(arg: &MArgument) -> Self;
+
+        unsafe fn disown(&self);
+
+        unsafe fn free(&self);
+
+        fn share_count(&self) -> usize;
+    }
+}
+
+//======================================
+// Impls
+//======================================
+
+impl<A: NativeArray> Shared<A> {
+    /// Returns the number of times the underlying array is shared, including the share
+    /// held by this value.
+    pub fn share_count(&self) -> usize {
+        self.0.share_count()
+    }
+
+    /// Release the `Shared` wrapper, returning the underlying array.
+    ///
+    /// The returned array will still disown its share when dropped.
+    pub fn into_inner(self) -> A {
+        let mut this = ManuallyDrop::new(self);
+
+        // Safety: `this` will not be used or dropped again.
+        unsafe { ManuallyDrop::take(&mut this.0) }
+    }
+}
+
+impl<A: NativeArray> Manual<A> {
+    /// Release the `Manual` wrapper, returning the underlying array.
+    pub fn into_inner(self) -> A {
+        let mut this = ManuallyDrop::new(self);
+
+        // Safety: `this` will not be used or dropped again.
+        unsafe { ManuallyDrop::take(&mut this.0) }
+    }
+}
+
+impl<T: crate::NumericArrayType> Shared<NumericArray<T>> {
+    /// Access the elements of this shared array as a mutable flat buffer.
+    ///
+    /// Modifications will be visible to the Wolfram Language.
+    ///
+    /// If this library holds more than one share of the underlying array (for example,
+    /// because it was also stored by an earlier call), this function will return `None`
+    /// to avoid creating aliasing mutable references.
+    pub fn as_slice_mut(&mut self) -> Option<&mut [T]> {
+        if self.share_count() == 1 {
+            unsafe { Some(self.0.as_slice_mut_unchecked()) }
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: crate::TensorType> Shared<Tensor<T>> {
+    /// Access the elements of this shared tensor as a mutable flat buffer.
+    ///
+    /// Modifications will be visible to the Wolfram Language.
+    ///
+    /// If this library holds more than one share of the underlying tensor (for example,
+    /// because it was also stored by an earlier call), this function will return `None`
+    /// to avoid creating aliasing mutable references.
+    pub fn as_slice_mut(&mut self) -> Option<&mut [T]> {
+        if self.share_count() == 1 {
+            unsafe { Some(self.0.as_slice_mut_unchecked()) }
+        } else {
+            None
+        }
+    }
+}
+
+//======================================
+// Trait Impls
+//======================================
+
+impl<A: NativeArray> Deref for Shared<A> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A: NativeArray> Deref for Manual<A> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A: NativeArray> DerefMut for Manual<A> {
+    fn deref_mut(&mut self) -> &mut A {
+        &mut self.0
+    }
+}
+
+impl<A: NativeArray> Drop for Shared<A> {
+    fn drop(&mut self) {
+        unsafe { self.0.disown() }
+    }
+}
+
+impl<A: NativeArray> Drop for Manual<A> {
+    fn drop(&mut self) {
+        unsafe { self.0.free() }
+    }
+}
+
+impl<'a, A: NativeArray> FromArg<'a> for Shared<A> {
+    unsafe fn from_arg(arg: &'a MArgument) -> Shared<A> {
+        Shared(ManuallyDrop::new(A::from_arg(arg)))
+    }
+
+    fn parameter_type() -> Expr {
+        // {<type spec>, "Shared"}
+        let mut spec = A::type_spec();
+        spec.push(Expr::string("Shared"));
+
+        Expr::normal(Symbol::new("System`List"), spec)
+    }
+}
+
+impl<'a, A: NativeArray> FromArg<'a> for Manual<A> {
+    unsafe fn from_arg(arg: &'a MArgument) -> Manual<A> {
+        Manual(ManuallyDrop::new(A::from_arg(arg)))
+    }
+
+    fn parameter_type() -> Expr {
+        // {<type spec>, "Manual"}
+        let mut spec = A::type_spec();
+        spec.push(Expr::string("Manual"));
+
+        Expr::normal(Symbol::new("System`List"), spec)
+    }
+}
+
+impl<A: NativeArray + IntoArg> IntoArg for Manual<A> {
+    unsafe fn into_arg(self, arg: MArgument) {
+        self.into_inner().into_arg(arg)
+    }
+
+    fn return_type() -> Expr {
+        A::return_type()
+    }
+}
+
+//======================================
+// NativeArray Impls
+//======================================
+
+//--------------------------------------
+// NumericArray
+//--------------------------------------
+
+impl<T> NativeArray for NumericArray<T> where NumericArray<T>: private::NativeArrayImpl {}
+
+impl<T: crate::NumericArrayType> private::NativeArrayImpl for NumericArray<T> {
+    fn type_spec() -> Vec<Expr> {
+        // LibraryDataType[NumericArray, "<T>"]
+        vec![Expr::normal(Symbol::new("System`LibraryDataType"), vec![
+            Expr::from(Symbol::new("System`NumericArray")),
+            Expr::string(T::TYPE.name()),
+        ])]
+    }
+
+    unsafe fn from_arg(arg: &MArgument) -> Self {
+        NumericArray::from_raw(*arg.numeric)
+    }
+
+    unsafe fn disown(&self) {
+        rtl::MNumericArray_disown(self.as_raw())
+    }
+
+    unsafe fn free(&self) {
+        rtl::MNumericArray_free(self.as_raw())
+    }
+
+    fn share_count(&self) -> usize {
+        NumericArray::share_count(self)
+    }
+}
+
+impl private::NativeArrayImpl for NumericArray<()> {
+    fn type_spec() -> Vec<Expr> {
+        // NumericArray
+        vec![Expr::from(Symbol::new("System`NumericArray"))]
+    }
+
+    unsafe fn from_arg(arg: &MArgument) -> Self {
+        NumericArray::from_raw(*arg.numeric)
+    }
+
+    unsafe fn disown(&self) {
+        rtl::MNumericArray_disown(self.as_raw())
+    }
+
+    unsafe fn free(&self) {
+        rtl::MNumericArray_free(self.as_raw())
+    }
+
+    fn share_count(&self) -> usize {
+        NumericArray::share_count(self)
+    }
+}
+
+//--------------------------------------
+// Tensor
+//--------------------------------------
+
+impl<T: crate::TensorType> NativeArray for Tensor<T> {}
+
+impl<T: crate::TensorType> private::NativeArrayImpl for Tensor<T> {
+    fn type_spec() -> Vec<Expr> {
+        crate::args::tensor_type::<T>()
+    }
+
+    unsafe fn from_arg(arg: &MArgument) -> Self {
+        Tensor::from_raw(*arg.tensor)
+    }
+
+    unsafe fn disown(&self) {
+        rtl::MTensor_disown(self.as_raw())
+    }
+
+    unsafe fn free(&self) {
+        rtl::MTensor_free(self.as_raw())
+    }
+
+    fn share_count(&self) -> usize {
+        Tensor::share_count(self)
+    }
+}
+
+//--------------------------------------
+// SparseArray
+//--------------------------------------
+
+impl<T: crate::TensorType> NativeArray for SparseArray<T> {}
+
+impl<T: crate::TensorType> private::NativeArrayImpl for SparseArray<T> {
+    fn type_spec() -> Vec<Expr> {
+        vec![crate::args::sparse_array_type::<T>()]
+    }
+
+    unsafe fn from_arg(arg: &MArgument) -> Self {
+        SparseArray::from_raw(*arg.sparse)
+    }
+
+    unsafe fn disown(&self) {
+        rtl::MSparseArray_disown(self.as_raw())
+    }
+
+    unsafe fn free(&self) {
+        rtl::MSparseArray_free(self.as_raw())
+    }
+
+    fn share_count(&self) -> usize {
+        SparseArray::share_count(self)
+    }
+}
+
+//--------------------------------------
+// Image
+//--------------------------------------
+
+impl<T> NativeArray for Image<T> where Image<T>: private::NativeArrayImpl {}
+
+impl<T: crate::ImageData> private::NativeArrayImpl for Image<T> {
+    fn type_spec() -> Vec<Expr> {
+        // LibraryDataType[Image | Image3D, "<T>"]
+        vec![Expr::normal(Symbol::new("System`LibraryDataType"), vec![
+            image_heads(),
+            Expr::string(T::TYPE.name()),
+        ])]
+    }
+
+    unsafe fn from_arg(arg: &MArgument) -> Self {
+        Image::from_raw(*arg.image)
+    }
+
+    unsafe fn disown(&self) {
+        rtl::MImage_disown(self.as_raw())
+    }
+
+    unsafe fn free(&self) {
+        rtl::MImage_free(self.as_raw())
+    }
+
+    fn share_count(&self) -> usize {
+        Image::share_count(self)
+    }
+}
+
+impl private::NativeArrayImpl for Image<()> {
+    fn type_spec() -> Vec<Expr> {
+        // Image | Image3D
+        vec![image_heads()]
+    }
+
+    unsafe fn from_arg(arg: &MArgument) -> Self {
+        Image::from_raw(*arg.image)
+    }
+
+    unsafe fn disown(&self) {
+        rtl::MImage_disown(self.as_raw())
+    }
+
+    unsafe fn free(&self) {
+        rtl::MImage_free(self.as_raw())
+    }
+
+    fn share_count(&self) -> usize {
+        Image::share_count(self)
+    }
+}
+
+fn image_heads() -> Expr {
+    Expr::normal(Symbol::new("System`Alternatives"), vec![
+        Expr::from(Symbol::new("System`Image")),
+        Expr::from(Symbol::new("System`Image3D")),
+    ])
+}
@@ -147,6 +147,10 @@ rtl_func![
     #[doc] pub getWSLINK: unsafe extern "C" fn(arg1: sys::WolframLibraryData) -> WSLINK,
     #[doc] pub processWSLINK: unsafe extern "C" fn(arg1: WSLINK) -> ::std::os::raw::c_int,
 
+    // Evaluates a string of code, and writes the result to the last argument. The
+    // `int` argument is the `MType_*` of the result, and the `mint` argument its rank.
+    // The result is an `mint` or `mreal` if the rank is zero, and an `MTensor`
+    // otherwise. See `KernelThread::evaluate_str_as()`.
     pub evaluateExpression: unsafe extern "C" fn(
         arg1: sys::WolframLibraryData,
         arg2: *mut ::std::os::raw::c_char,
@@ -155,6 +159,16 @@ rtl_func![
         arg5: *mut ::std::os::raw::c_void,
     ) -> ::std::os::raw::c_int,
 
+    // TODO: Provide safe wrappers for the stream method functions, so that a Rust
+    //       `Read` or `Write` implementation can back a Wolfram Language stream opened
+    //       using e.g. `OpenRead["...", Method -> "MyRustStream"]`.
+    //
+    //       This is currently blocked on the layout of the `st_MInputStream` and
+    //       `st_MOutputStream` structs. They are opaque in the LibraryLink headers used
+    //       to generate the wolfram-library-link-sys bindings, so there is no way to
+    //       install the read/write/close methods on the stream object passed to the
+    //       `ctor` callback. Registering a method whose constructor leaves the stream
+    //       uninitialized would not be sound.
     pub registerInputStreamMethod: unsafe extern "C" fn(
         name: *const ::std::os::raw::c_char,
         ctor: Option<
@@ -0,0 +1,464 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+use ref_cast::RefCast;
+use static_assertions::assert_not_impl_any;
+
+use crate::{
+    rtl,
+    sys::{self, mint},
+    Tensor, TensorType,
+};
+
+/// Native Wolfram [`SparseArray`][ref/SparseArray]<sub>WL</sub>.
+///
+/// This type is an ABI-compatible wrapper around
+/// [`wolfram_library_link_sys::MSparseArray`].
+///
+/// A [`SparseArray`] can contain any type `T` which satisfies the trait [`TensorType`]:
+/// [`mint`], [`mreal`][sys::mreal], and [`mcomplex`][sys::mcomplex].
+///
+/// The explicitly specified elements of a sparse array are stored in
+/// [compressed sparse row (CSR)][csr] format, which can be accessed using
+/// [`row_pointers()`][SparseArray::row_pointers],
+/// [`column_indices()`][SparseArray::column_indices], and
+/// [`explicit_values()`][SparseArray::explicit_values]. Elements that are not explicitly
+/// specified have the value [`implicit_value()`][SparseArray::implicit_value].
+///
+/// # Example
+///
+/// Compute the sum of the explicitly specified elements of a sparse matrix:
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{export, SparseArray};
+///
+/// #[export]
+/// fn explicit_total(matrix: &SparseArray<f64>) -> f64 {
+///     match matrix.explicit_values() {
+///         Some(values) => values.as_slice().iter().sum(),
+///         None => 0.0,
+///     }
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad[
+///     "...", "explicit_total",
+///     {{LibraryDataType[SparseArray, Real], "Constant"}},
+///     Real
+/// ]
+/// ```
+///
+/// [ref/SparseArray]: https://reference.wolfram.com/language/ref/SparseArray.html
+/// [csr]: https://reference.wolfram.com/language/LibraryLink/tutorial/InteractionWithWolframLanguage.html#1225567048
+#[repr(transparent)]
+#[derive(ref_cast::RefCast)]
+pub struct SparseArray<T = ()>(sys::MSparseArray, PhantomData<T>);
+
+// Guard against accidental `derive(Copy)` annotations.
+assert_not_impl_any!(SparseArray<f64>: Copy);
+
+//======================================
+// Impls
+//======================================
+
+impl<T: TensorType> SparseArray<T> {
+    /// Construct a new sparse array from a matrix of explicit positions, the values at
+    /// those positions, the dimensions of the array, and the implicit value.
+    ///
+    /// `positions` must be an `n x rank` integer tensor of **1-based** positions, and
+    /// `values` must be a tensor of length `n`.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_fromExplicitPositions`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_fromExplicitPositions.html)
+    pub fn from_explicit_positions(
+        positions: &Tensor<mint>,
+        values: &Tensor<T>,
+        dimensions: &[usize],
+        implicit_value: T,
+    ) -> Result<SparseArray<T>, sys::errcode_t> {
+        let dimensions = dimensions_tensor(dimensions);
+        let implicit_value = Tensor::from_array(&[], &[implicit_value]);
+
+        let mut raw: sys::MSparseArray = std::ptr::null_mut();
+
+        let err_code: sys::errcode_t = unsafe {
+            rtl::MSparseArray_fromExplicitPositions(
+                positions.as_raw(),
+                values.as_raw(),
+                dimensions.as_raw(),
+                implicit_value.as_raw(),
+                &mut raw,
+            )
+        };
+
+        if err_code != 0 || raw.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { SparseArray::from_raw(raw) })
+    }
+
+    /// Construct a new sparse matrix with an implicit value of zero from a list of
+    /// `(row, column, value)` triplets (also known as coordinate or COO format).
+    ///
+    /// Unlike [`SparseArray::from_explicit_positions()`], the `row` and `column` indices
+    /// are **0-based**.
+    ///
+    /// # Example
+    ///
+    /// Construct the sparse representation of the 3x3 identity matrix:
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::SparseArray;
+    /// let identity = SparseArray::from_triplets(
+    ///     [3, 3],
+    ///     &[(0, 0, 1.0), (1, 1, 1.0), (2, 2, 1.0)],
+    /// ).unwrap();
+    ///
+    /// assert_eq!(identity.dimensions(), &[3, 3]);
+    /// ```
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_fromExplicitPositions`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_fromExplicitPositions.html)
+    pub fn from_triplets(
+        [rows, columns]: [usize; 2],
+        triplets: &[(usize, usize, T)],
+    ) -> Result<SparseArray<T>, sys::errcode_t> {
+        let mut positions: Vec<mint> = Vec::with_capacity(2 * triplets.len());
+        let mut values: Vec<T> = Vec::with_capacity(triplets.len());
+
+        for &(row, column, value) in triplets {
+            assert!(
+                row < rows && column < columns,
+                "SparseArray::from_triplets(): position ({}, {}) is out of bounds",
+                row,
+                column
+            );
+
+            positions.push(one_based(row));
+            positions.push(one_based(column));
+            values.push(value);
+        }
+
+        let positions = Tensor::try_from_array(&[triplets.len(), 2], &positions)?;
+        let values = Tensor::try_from_slice(&values)?;
+        let dimensions = dimensions_tensor(&[rows, columns]);
+
+        let mut raw: sys::MSparseArray = std::ptr::null_mut();
+
+        // Passing a NULL implicit value tensor uses an implicit value of 0.
+        let err_code: sys::errcode_t = unsafe {
+            rtl::MSparseArray_fromExplicitPositions(
+                positions.as_raw(),
+                values.as_raw(),
+                dimensions.as_raw(),
+                std::ptr::null_mut(),
+                &mut raw,
+            )
+        };
+
+        if err_code != 0 || raw.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { SparseArray::from_raw(raw) })
+    }
+
+    /// Construct a new sparse array from the elements of a dense tensor.
+    ///
+    /// If `implicit_value` is `None`, an implicit value of zero is used.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_fromMTensor`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_fromMTensor.html)
+    pub fn from_tensor(
+        tensor: &Tensor<T>,
+        implicit_value: Option<T>,
+    ) -> Result<SparseArray<T>, sys::errcode_t> {
+        let implicit_value: Option<Tensor<T>> =
+            implicit_value.map(|value| Tensor::from_array(&[], &[value]));
+
+        let implicit_value_raw: sys::MTensor = match implicit_value {
+            Some(ref tensor) => unsafe { tensor.as_raw() },
+            None => std::ptr::null_mut(),
+        };
+
+        let mut raw: sys::MSparseArray = std::ptr::null_mut();
+
+        let err_code: sys::errcode_t = unsafe {
+            rtl::MSparseArray_fromMTensor(tensor.as_raw(), implicit_value_raw, &mut raw)
+        };
+
+        if err_code != 0 || raw.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { SparseArray::from_raw(raw) })
+    }
+
+    /// Get the implicit value of this sparse array.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getImplicitValue`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getImplicitValue.html)
+    pub fn implicit_value(&self) -> Option<T> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let tensor: &Tensor<T> =
+            unsafe { tensor_ref(rtl::MSparseArray_getImplicitValue(raw)) }?;
+
+        tensor.as_slice().first().copied()
+    }
+
+    /// Get the explicitly specified values of this sparse array, in row-major order.
+    ///
+    /// Returns `None` if this sparse array has no explicit values.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getExplicitValues`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getExplicitValues.html)
+    pub fn explicit_values(&self) -> Option<&Tensor<T>> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        unsafe { tensor_ref(rtl::MSparseArray_getExplicitValues(raw)) }
+    }
+
+    /// Convert this sparse array into a dense tensor.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_toMTensor`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_toMTensor.html)
+    pub fn to_tensor(&self) -> Result<Tensor<T>, sys::errcode_t> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let mut tensor: sys::MTensor = std::ptr::null_mut();
+
+        let err_code: sys::errcode_t =
+            unsafe { rtl::MSparseArray_toMTensor(raw, &mut tensor) };
+
+        if err_code != 0 || tensor.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { Tensor::from_raw(tensor) })
+    }
+
+    /// Construct a copy of this sparse array with a different implicit value.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_resetImplicitValue`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_resetImplicitValue.html)
+    pub fn with_implicit_value(
+        &self,
+        implicit_value: T,
+    ) -> Result<SparseArray<T>, sys::errcode_t> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let implicit_value = Tensor::from_array(&[], &[implicit_value]);
+
+        let mut new_raw: sys::MSparseArray = std::ptr::null_mut();
+
+        let err_code: sys::errcode_t = unsafe {
+            rtl::MSparseArray_resetImplicitValue(
+                raw,
+                implicit_value.as_raw(),
+                &mut new_raw,
+            )
+        };
+
+        if err_code != 0 || new_raw.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { SparseArray::from_raw(new_raw) })
+    }
+}
+
+impl<T> SparseArray<T> {
+    /// Construct a `SparseArray<T>` from a raw [`MSparseArray`][sys::MSparseArray].
+    ///
+    /// # Safety
+    ///
+    /// `array` must be a valid sparse array object whose element type is the same as
+    /// `T`.
+    pub unsafe fn from_raw(array: sys::MSparseArray) -> SparseArray<T> {
+        SparseArray(array, PhantomData)
+    }
+
+    /// Convert this `SparseArray` into a raw [`MSparseArray`][sys::MSparseArray] object.
+    ///
+    /// # Safety
+    ///
+    /// The caller takes over responsibility for freeing or disowning the returned
+    /// sparse array.
+    pub unsafe fn into_raw(self) -> sys::MSparseArray {
+        let SparseArray(raw, PhantomData) = self;
+
+        // Don't run Drop on `self`; ownership of this value is being given to the caller.
+        std::mem::forget(self);
+
+        raw
+    }
+
+    /// Get the raw [`MSparseArray`][sys::MSparseArray] object wrapped by this
+    /// `SparseArray`.
+    ///
+    /// # Safety
+    ///
+    /// The returned array is still owned by `self`, and must not be freed or disowned,
+    /// or used after `self` has been dropped.
+    #[inline]
+    pub unsafe fn as_raw(&self) -> sys::MSparseArray {
+        let SparseArray(raw, PhantomData) = *self;
+
+        raw
+    }
+
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getRank`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getRank.html)
+    pub fn rank(&self) -> usize {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let rank: mint = unsafe { rtl::MSparseArray_getRank(raw) };
+
+        usize::try_from(rank).expect("SparseArray rank overflows usize")
+    }
+
+    /// Get the dimensions of this `SparseArray`.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getDimensions`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getDimensions.html)
+    pub fn dimensions(&self) -> &[usize] {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let rank = self.rank();
+
+        let dims: *const mint = unsafe { rtl::MSparseArray_getDimensions(raw) };
+
+        const _: () = assert!(mem::size_of::<mint>() == mem::size_of::<usize>());
+        let dims = dims as *const usize;
+
+        debug_assert!(!dims.is_null());
+
+        unsafe { std::slice::from_raw_parts(dims, rank) }
+    }
+
+    /// Get the CSR row pointers of this sparse array.
+    ///
+    /// The explicit values in row `i` (1-based) are stored at the 0-based indices
+    /// `row_pointers[i - 1] .. row_pointers[i]` of
+    /// [`explicit_values()`][SparseArray::explicit_values].
+    ///
+    /// Returns `None` if this sparse array has no explicit values.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getRowPointers`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getRowPointers.html)
+    pub fn row_pointers(&self) -> Option<&Tensor<mint>> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        unsafe { tensor_ref(rtl::MSparseArray_getRowPointers(raw)) }
+    }
+
+    /// Get the CSR column indices of this sparse array.
+    ///
+    /// The returned tensor has dimensions `{n, rank - 1}`, where `n` is the number of
+    /// explicit values. The indices are **1-based**.
+    ///
+    /// Returns `None` if this sparse array has no explicit values.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getColumnIndices`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getColumnIndices.html)
+    pub fn column_indices(&self) -> Option<&Tensor<mint>> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        unsafe { tensor_ref(rtl::MSparseArray_getColumnIndices(raw)) }
+    }
+
+    /// Get the **1-based** positions of the explicitly specified elements of this sparse
+    /// array, as an `{n, rank}` integer tensor.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_getExplicitPositions`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_getExplicitPositions.html)
+    pub fn explicit_positions(&self) -> Result<Tensor<mint>, sys::errcode_t> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let mut tensor: sys::MTensor = std::ptr::null_mut();
+
+        let err_code: sys::errcode_t =
+            unsafe { rtl::MSparseArray_getExplicitPositions(raw, &mut tensor) };
+
+        if err_code != 0 || tensor.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { Tensor::from_raw(tensor) })
+    }
+
+    /// Returns the share count of this `SparseArray`.
+    ///
+    /// If this `SparseArray` is not shared, the share count is 0.
+    ///
+    /// *LibraryLink C API Documentation:* [`MSparseArray_shareCount`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MSparseArray_shareCount.html)
+    pub fn share_count(&self) -> usize {
+        let SparseArray(raw, PhantomData) = *self;
+
+        let count: mint = unsafe { rtl::MSparseArray_shareCount(raw) };
+
+        usize::try_from(count).expect("SparseArray share count mint overflows usize")
+    }
+}
+
+/// Borrow the tensor stored at `ptr`, which is owned by a sparse array.
+unsafe fn tensor_ref<'a, T>(ptr: *mut sys::MTensor) -> Option<&'a Tensor<T>> {
+    if ptr.is_null() || (*ptr).is_null() {
+        return None;
+    }
+
+    Some(Tensor::ref_cast(&*ptr))
+}
+
+fn dimensions_tensor(dimensions: &[usize]) -> Tensor<mint> {
+    let dimensions: Vec<mint> = dimensions
+        .iter()
+        .map(|&dim| mint::try_from(dim).expect("SparseArray dimension overflows mint"))
+        .collect();
+
+    Tensor::from_slice(&dimensions)
+}
+
+fn one_based(index: usize) -> mint {
+    mint::try_from(index + 1).expect("SparseArray index overflows mint")
+}
+
+//======================================
+// Trait Impls
+//======================================
+
+impl<T> Clone for SparseArray<T> {
+    fn clone(&self) -> SparseArray<T> {
+        let SparseArray(raw, PhantomData) = *self;
+
+        unsafe {
+            let mut new: sys::MSparseArray = std::ptr::null_mut();
+            let err_code: sys::errcode_t = rtl::MSparseArray_clone(raw, &mut new);
+
+            if err_code != 0 || new.is_null() {
+                panic!("SparseArray clone failed with error code: {}", err_code);
+            }
+
+            SparseArray::<T>::from_raw(new)
+        }
+    }
+}
+
+impl<T> Drop for SparseArray<T> {
+    fn drop(&mut self) {
+        let SparseArray(raw, PhantomData) = *self;
+
+        if self.share_count() > 0 {
+            // This is a "Shared" sparse array, so we should decrement the reference
+            // count.
+            unsafe { rtl::MSparseArray_disown(raw) }
+        } else {
+            // This is a "Manual" sparse array (or one created within Rust), so we should
+            // free its memory directly.
+            unsafe { rtl::MSparseArray_free(raw) }
+        }
+    }
+}
+
+impl<T> fmt::Debug for SparseArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SparseArray")
+            .field("raw", &self.0)
+            .field("dimensions", &self.dimensions())
+            .finish()
+    }
+}
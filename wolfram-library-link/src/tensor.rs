@@ -0,0 +1,523 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+use static_assertions::assert_not_impl_any;
+
+use crate::{
+    rtl,
+    sys::{self, mcomplex, mint, mreal},
+};
+
+/// Native Wolfram packed array, passed to and from *LibraryLink* functions as an
+/// [`MTensor`][sys::MTensor].
+///
+/// This type is an ABI-compatible wrapper around [`wolfram_library_link_sys::MTensor`].
+///
+/// A [`Tensor`] can contain any type `T` which satisfies the trait [`TensorType`]:
+/// [`mint`], [`mreal`], and [`mcomplex`].
+///
+/// Use [`Tensor::try_into_kind()`] to dynamically resolve a `Tensor` with unknown
+/// element type into a `Tensor<T>` with explicit element type.
+///
+/// # Example
+///
+/// Export a function that computes the sum of a packed array of reals:
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{export, Tensor};
+///
+/// #[export]
+/// fn total_real(list: &Tensor<f64>) -> f64 {
+///     list.as_slice().iter().sum()
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad["...", "total_real", {{Real, _, "Constant"}}, Real]
+/// ```
+#[repr(transparent)]
+#[derive(ref_cast::RefCast)]
+pub struct Tensor<T = ()>(sys::MTensor, PhantomData<T>);
+
+// Guard against accidental `derive(Copy)` annotations.
+assert_not_impl_any!(Tensor: Copy);
+assert_not_impl_any!(Tensor<f64>: Copy);
+
+//======================================
+// Traits
+//======================================
+
+/// Trait implemented for types that can be stored in a [`Tensor`].
+///
+/// Those types are:
+///
+///   * [`mint`]
+///   * [`mreal`]
+///   * [`mcomplex`]
+///
+/// [`TensorDataType`] is an enumeration of all the types which satisfy this trait.
+pub trait TensorType: private::Sealed + Copy {
+    /// The [`TensorDataType`] which dynamically represents the type which this
+    /// trait is implemented for.
+    const TYPE: TensorDataType;
+}
+
+mod private {
+    use crate::sys;
+
+    pub trait Sealed {}
+
+    impl Sealed for sys::mint {}
+    impl Sealed for sys::mreal {}
+    impl Sealed for sys::mcomplex {}
+}
+
+impl TensorType for mint {
+    const TYPE: TensorDataType = TensorDataType::Integer;
+}
+
+impl TensorType for mreal {
+    const TYPE: TensorDataType = TensorDataType::Real;
+}
+
+impl TensorType for mcomplex {
+    const TYPE: TensorDataType = TensorDataType::Complex;
+}
+
+//======================================
+// Enums
+//======================================
+
+/// The type of the data being stored in a [`Tensor`].
+///
+/// This is an enumeration of all the types which satisfy [`TensorType`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+#[allow(missing_docs)]
+pub enum TensorDataType {
+    Integer = sys::MType_Integer,
+    Real = sys::MType_Real,
+    Complex = sys::MType_Complex,
+}
+
+//======================================
+// Impls
+//======================================
+
+impl Tensor {
+    /// Attempt to resolve this `Tensor` into a `Tensor<T>` of the specified element
+    /// type.
+    ///
+    /// If the element type of this tensor does not match `T`, the original untyped
+    /// tensor will be returned as the error value.
+    pub fn try_into_kind<T: TensorType>(self) -> Result<Tensor<T>, Tensor> {
+        if self.data_type() == T::TYPE {
+            return Ok(unsafe { Tensor::from_raw(self.into_raw()) });
+        }
+
+        Err(self)
+    }
+}
+
+impl<T: TensorType> Tensor<T> {
+    /// Construct a new one-dimensional [`Tensor`] from a slice.
+    ///
+    /// Use [`Tensor::from_array()`] to construct multidimensional tensors.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if [`Tensor::try_from_array()`] returns an error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Tensor;
+    /// let tensor: Tensor<i64> = Tensor::from_slice(&[1, 2, 3, 4, 5]);
+    /// ```
+    pub fn from_slice(data: &[T]) -> Tensor<T> {
+        Tensor::try_from_slice(data).expect("failed to create Tensor from slice")
+    }
+
+    /// Fallible alternative to [`Tensor::from_slice()`].
+    pub fn try_from_slice(data: &[T]) -> Result<Tensor<T>, sys::errcode_t> {
+        Tensor::try_from_array(&[data.len()], data)
+    }
+
+    /// Construct a new multidimensional [`Tensor`] from a list of dimensions and the
+    /// flat slice of data.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if [`Tensor::try_from_array()`] returns an error.
+    ///
+    /// # Example
+    ///
+    /// Construct the 2x2 packed array `{{1., 2.}, {3., 4.}}` from a list of dimensions
+    /// and a flat buffer.
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Tensor;
+    /// let tensor = Tensor::from_array(&[2, 2], &[1.0, 2.0, 3.0, 4.0]);
+    ///
+    /// assert_eq!(tensor.dimensions(), &[2, 2]);
+    /// ```
+    pub fn from_array(dimensions: &[usize], data: &[T]) -> Tensor<T> {
+        Tensor::try_from_array(dimensions, data)
+            .expect("failed to create Tensor from array")
+    }
+
+    /// Fallible alternative to [`Tensor::from_array()`].
+    ///
+    /// An empty `dimensions` slice constructs a rank 0 (scalar) tensor, in which case
+    /// `data` must contain exactly one element.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `data.len()` is not equal to the product of
+    /// `dimensions`.
+    pub fn try_from_array(
+        dimensions: &[usize],
+        data: &[T],
+    ) -> Result<Tensor<T>, sys::errcode_t> {
+        assert_eq!(
+            dimensions.iter().copied().product::<usize>(),
+            data.len(),
+            "Tensor data length does not match the product of its dimensions"
+        );
+
+        let mut tensor = Tensor::<T>::try_new(dimensions)?;
+
+        // Safety: `tensor` was created by this function, so it cannot be shared.
+        unsafe { tensor.as_slice_mut_unchecked() }.copy_from_slice(data);
+
+        Ok(tensor)
+    }
+
+    /// Allocate a new tensor with the specified dimensions. The elements of the returned
+    /// tensor are initialized to zero by the Wolfram runtime.
+    ///
+    /// *LibraryLink C API Documentation:* [`MTensor_new`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_new.html)
+    fn try_new(dimensions: &[usize]) -> Result<Tensor<T>, sys::errcode_t> {
+        const _: () = assert!(mem::size_of::<mint>() == mem::size_of::<usize>());
+
+        let rank = mint::try_from(dimensions.len()).expect("Tensor rank overflows mint");
+
+        let mut raw: sys::MTensor = std::ptr::null_mut();
+
+        let err_code: sys::errcode_t = unsafe {
+            rtl::MTensor_new(
+                T::TYPE.as_raw(),
+                rank,
+                dimensions.as_ptr() as *const mint,
+                &mut raw,
+            )
+        };
+
+        if err_code != 0 || raw.is_null() {
+            return Err(err_code);
+        }
+
+        Ok(unsafe { Tensor::from_raw(raw) })
+    }
+
+    /// Access the elements stored in this [`Tensor`] as a flat buffer.
+    ///
+    /// The returned slice will have a length equal to
+    /// [`flattened_length()`][Tensor::flattened_length].
+    pub fn as_slice(&self) -> &[T] {
+        let ptr: *mut T = self.data_ptr();
+
+        debug_assert!(!ptr.is_null());
+
+        unsafe { std::slice::from_raw_parts(ptr, self.flattened_length()) }
+    }
+
+    /// Access the elements stored in this [`Tensor`] as a mutable flat buffer.
+    ///
+    /// If the [`share_count()`][Tensor::share_count] of this tensor is >= 1, this
+    /// function will return `None`.
+    pub fn as_slice_mut(&mut self) -> Option<&mut [T]> {
+        if self.share_count() == 0 {
+            // This is not a shared tensor. We have unique access to it's data.
+            unsafe { Some(self.as_slice_mut_unchecked()) }
+        } else {
+            None
+        }
+    }
+
+    /// Access the elements stored in this [`Tensor`] as a mutable flat buffer.
+    ///
+    /// # Safety
+    ///
+    /// Packed arrays are immutable shared data structures in the Wolfram Language. The
+    /// caller must ensure that no other reference to the data of this tensor, from
+    /// either Rust or the Wolfram Language, can observe the mutation.
+    pub unsafe fn as_slice_mut_unchecked(&mut self) -> &mut [T] {
+        let ptr: *mut T = self.data_ptr();
+
+        debug_assert!(!ptr.is_null());
+
+        std::slice::from_raw_parts_mut(ptr, self.flattened_length())
+    }
+
+    /// Get a pointer to the first element of this tensor.
+    ///
+    /// *LibraryLink C API Documentation:* [`MTensor_getIntegerData`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getIntegerData.html),
+    /// [`MTensor_getRealData`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getRealData.html),
+    /// [`MTensor_getComplexData`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getComplexData.html)
+    pub fn data_ptr(&self) -> *mut T {
+        let Tensor(raw, PhantomData) = *self;
+
+        debug_assert_eq!(self.data_type(), T::TYPE);
+
+        // Each of these getters returns a pointer to the same underlying buffer; the
+        // only difference is the pointee type.
+        unsafe {
+            match T::TYPE {
+                TensorDataType::Integer => rtl::MTensor_getIntegerData(raw) as *mut T,
+                TensorDataType::Real => rtl::MTensor_getRealData(raw) as *mut T,
+                TensorDataType::Complex => rtl::MTensor_getComplexData(raw) as *mut T,
+            }
+        }
+    }
+}
+
+impl<T> Tensor<T> {
+    /// Erase the concrete `T` data type associated with this `Tensor`.
+    ///
+    /// Use [`Tensor::try_into_kind()`] to convert back into a `Tensor<T>`.
+    pub fn into_generic(self) -> Tensor {
+        unsafe { Tensor::from_raw(self.into_raw()) }
+    }
+
+    /// Construct a `Tensor<T>` from a raw [`MTensor`][sys::MTensor].
+    ///
+    /// # Safety
+    ///
+    /// The following conditions must be met for safe usage of this function:
+    ///
+    /// * `tensor` must be a fully initialized and valid tensor object
+    /// * `T` must either:
+    ///   - be `()`, representing a tensor with dynamic element type, or
+    ///   - `T` must satisfy [`TensorType`], and the element type of `tensor` must be
+    ///     the same as `T`.
+    pub unsafe fn from_raw(tensor: sys::MTensor) -> Tensor<T> {
+        Tensor(tensor, PhantomData)
+    }
+
+    /// Convert this `Tensor` into a raw [`MTensor`][sys::MTensor] object.
+    ///
+    /// # Safety
+    ///
+    /// The caller takes over responsibility for freeing or disowning the returned
+    /// tensor.
+    pub unsafe fn into_raw(self) -> sys::MTensor {
+        let Tensor(raw, PhantomData) = self;
+
+        // Don't run Drop on `self`; ownership of this value is being given to the caller.
+        std::mem::forget(self);
+
+        raw
+    }
+
+    /// Get the raw [`MTensor`][sys::MTensor] object wrapped by this `Tensor`.
+    ///
+    /// # Safety
+    ///
+    /// The returned tensor is still owned by `self`, and must not be freed or disowned,
+    /// or used after `self` has been dropped.
+    #[inline]
+    pub unsafe fn as_raw(&self) -> sys::MTensor {
+        let Tensor(raw, PhantomData) = *self;
+
+        raw
+    }
+
+    /// Get the element type of this tensor.
+    pub fn data_type(&self) -> TensorDataType {
+        TensorDataType::try_from(self.data_type_raw())
+            .expect("Tensor type is not a known TensorDataType variant")
+    }
+
+    /// *LibraryLink C API Documentation:* [`MTensor_getType`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getType.html)
+    pub fn data_type_raw(&self) -> mint {
+        let Tensor(raw, PhantomData) = *self;
+
+        unsafe { rtl::MTensor_getType(raw) }
+    }
+
+    /// The number of elements in the underlying flat data array.
+    ///
+    /// This is the product of the dimension lengths of this [`Tensor`].
+    ///
+    /// *LibraryLink C API Documentation:* [`MTensor_getFlattenedLength`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getFlattenedLength.html)
+    pub fn flattened_length(&self) -> usize {
+        let Tensor(raw, PhantomData) = *self;
+
+        let len: mint = unsafe { rtl::MTensor_getFlattenedLength(raw) };
+
+        let len = usize::try_from(len).expect("Tensor flattened length overflows usize");
+
+        // Check that the stored length matches the length computed from the dimensions.
+        debug_assert!(len == self.dimensions().iter().copied().product::<usize>());
+
+        len
+    }
+
+    /// *LibraryLink C API Documentation:* [`MTensor_getRank`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getRank.html)
+    pub fn rank(&self) -> usize {
+        let Tensor(raw, PhantomData) = *self;
+
+        let rank: mint = unsafe { rtl::MTensor_getRank(raw) };
+
+        usize::try_from(rank).expect("Tensor rank overflows usize")
+    }
+
+    /// Get the dimensions of this `Tensor`.
+    ///
+    /// A rank 0 (scalar) tensor has no dimensions.
+    ///
+    /// *LibraryLink C API Documentation:* [`MTensor_getDimensions`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_getDimensions.html)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Tensor;
+    /// let tensor = Tensor::from_array(&[2, 3], &[1, 2, 3, 4, 5, 6]);
+    ///
+    /// assert_eq!(tensor.dimensions(), &[2, 3]);
+    /// assert_eq!(tensor.rank(), tensor.dimensions().len());
+    /// ```
+    pub fn dimensions(&self) -> &[usize] {
+        let Tensor(raw, PhantomData) = *self;
+
+        let rank = self.rank();
+
+        if rank == 0 {
+            return &[];
+        }
+
+        let dims: *const mint = unsafe { rtl::MTensor_getDimensions(raw) };
+
+        const _: () = assert!(mem::size_of::<mint>() == mem::size_of::<usize>());
+        let dims = dims as *const usize;
+
+        debug_assert!(!dims.is_null());
+
+        unsafe { std::slice::from_raw_parts(dims, rank) }
+    }
+
+    /// Returns the share count of this `Tensor`.
+    ///
+    /// If this `Tensor` is not shared, the share count is 0.
+    ///
+    /// If this `Tensor` was passed into the current library "by reference" due to
+    /// use of the `Automatic` or `"Constant"` memory management strategy, that reference
+    /// is not reflected in the `share_count()`.
+    ///
+    /// *LibraryLink C API Documentation:* [`MTensor_shareCount`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MTensor_shareCount.html)
+    pub fn share_count(&self) -> usize {
+        let Tensor(raw, PhantomData) = *self;
+
+        let count: mint = unsafe { rtl::MTensor_shareCount(raw) };
+
+        usize::try_from(count).expect("Tensor share count mint overflows usize")
+    }
+
+    /// Returns true if `self` and `other` are pointers to the same underlying tensor
+    /// object.
+    pub fn ptr_eq<T2>(&self, other: &Tensor<T2>) -> bool {
+        let Tensor(this, PhantomData) = *self;
+        let Tensor(other, PhantomData) = *other;
+
+        this == other
+    }
+}
+
+impl TensorDataType {
+    #[allow(missing_docs)]
+    pub fn as_raw(self) -> mint {
+        mint::from(self as u32)
+    }
+
+    /// Get the name of this type, suitable for use as the element type in a
+    /// [`LibraryFunctionLoad`][ref/LibraryFunctionLoad] tensor specification
+    /// <code>{<i>type</i>, <i>rank</i>}</code>.
+    ///
+    /// [ref/LibraryFunctionLoad]: https://reference.wolfram.com/language/ref/LibraryFunctionLoad.html
+    pub fn name(&self) -> &'static str {
+        match self {
+            TensorDataType::Integer => "Integer",
+            TensorDataType::Real => "Real",
+            TensorDataType::Complex => "Complex",
+        }
+    }
+}
+
+//======================================
+// Trait Impls
+//======================================
+
+impl<T> Clone for Tensor<T> {
+    fn clone(&self) -> Tensor<T> {
+        let Tensor(raw, PhantomData) = *self;
+
+        unsafe {
+            let mut new: sys::MTensor = std::ptr::null_mut();
+            let err_code: sys::errcode_t = rtl::MTensor_clone(raw, &mut new);
+
+            if err_code != 0 || new.is_null() {
+                panic!("Tensor clone failed with error code: {}", err_code);
+            }
+
+            Tensor::<T>::from_raw(new)
+        }
+    }
+}
+
+impl<T> Drop for Tensor<T> {
+    fn drop(&mut self) {
+        let Tensor(raw, PhantomData) = *self;
+
+        if self.share_count() > 0 {
+            // This is a "Shared" tensor, so we should decrement the reference count.
+            unsafe { rtl::MTensor_disown(raw) }
+        } else {
+            // This is a "Manual" tensor (or one created within Rust), so we should free
+            // its memory directly.
+            unsafe { rtl::MTensor_free(raw) }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Tensor<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tensor")
+            .field("raw", &self.0)
+            .field("data_type", &self.data_type())
+            .field("dimensions", &self.dimensions())
+            .finish()
+    }
+}
+
+//======================================
+// Conversion Impls
+//======================================
+
+impl TryFrom<mint> for TensorDataType {
+    type Error = ();
+
+    fn try_from(value: mint) -> Result<Self, Self::Error> {
+        let ok = match u32::try_from(value) {
+            Ok(sys::MType_Integer) => TensorDataType::Integer,
+            Ok(sys::MType_Real) => TensorDataType::Real,
+            Ok(sys::MType_Complex) => TensorDataType::Complex,
+            _ => return Err(()),
+        };
+
+        Ok(ok)
+    }
+}
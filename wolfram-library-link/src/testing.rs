@@ -0,0 +1,678 @@
+//! Mock Wolfram runtime, for testing LibraryLink functions without a Wolfram Kernel.
+//!
+//! *This module is only available if the `"testing"` feature is enabled.*
+//!
+//! Types like [`NumericArray`], [`Image`], and [`DataStore`] are implemented by calling
+//! back into the Wolfram runtime, via the function pointers stored in a
+//! [`sys::WolframLibraryData`] instance provided by the Kernel. This module provides a
+//! pure-Rust implementation of those functions, which makes it possible to use
+//! these types, and to call [`#[export]`][crate::export] functions, from ordinary
+//! `cargo test` tests.
+//!
+//! Use [`initialize()`] to install the mock runtime, and [`call()`] to call a
+//! LibraryLink function the same way the Kernel would.
+//!
+//! # Example
+//!
+//! ```
+//! # mod scope {
+//! use wolfram_library_link::{
+//!     export,
+//!     testing::{self, Arg, Passing},
+//!     NumericArray,
+//! };
+//!
+//! #[export]
+//! fn total(array: &NumericArray<i64>) -> i64 {
+//!     array.as_slice().iter().sum()
+//! }
+//!
+//! # pub fn test() {
+//! testing::initialize();
+//!
+//! let array = NumericArray::<i64>::from_slice(&[1, 2, 3]);
+//!
+//! let result = unsafe {
+//!     testing::call(total::total, &[Arg::numeric_array(&array, Passing::Constant)])
+//! };
+//!
+//! assert_eq!(unsafe { result.unwrap().get::<i64>() }, 6);
+//! # }
+//! # }
+//! # fn main() { scope::test() }
+//! ```
+//!
+//! # Limitations
+//!
+//! The mock runtime is not a Wolfram Language evaluator. Functions that need one, like
+//! [`KernelThread::evaluate()`][crate::KernelThread::evaluate], WSTP functions, and
+//! input and output stream methods, are not supported, and calling them will abort the
+//! test process.
+//!
+//! `MNumericArray_convertType()`, `MImage_convertType()`, `MTensor_getMTensor()`, and
+//! `MTensor_setMTensor()` are also not supported.
+//!
+//! The mock runtime detects some misuse of the runtime functions, for example freeing an
+//! array that is still shared, or using an array after it has been freed. These errors
+//! abort the test process.
+
+mod arrays;
+mod io;
+mod kernel;
+mod sparse;
+
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    os::raw::{c_char, c_int, c_void},
+    sync::{atomic::Ordering, Mutex},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    sys::{self, mbool, mcomplex, mint, mreal, MArgument},
+    AsyncTaskObject, DataStore, FromArg, Image, NumericArray, SparseArray, Tensor,
+};
+
+//======================================
+// Public API
+//======================================
+
+/// Signature of the `extern "C"` functions generated by [`#[export]`][crate::export].
+pub type LibraryFunction = unsafe extern "C" fn(
+    sys::WolframLibraryData,
+    mint,
+    *mut MArgument,
+    MArgument,
+) -> c_int;
+
+/// Initialize the global [`rtl`][crate::rtl] bindings to use the mock runtime.
+///
+/// This function can be called any number of times. It is called automatically by
+/// [`call()`].
+///
+/// # Panics
+///
+/// The [`rtl`][crate::rtl] bindings can only be initialized once per process. This
+/// function will panic if they have already been initialized with a different
+/// [`sys::WolframLibraryData`] instance.
+pub fn initialize() {
+    unsafe { crate::initialize(library_data()) }
+        .expect("testing::initialize(): failed to initialize mock runtime");
+
+    assert!(
+        crate::get_library_data().raw_library_data == library_data(),
+        "testing::initialize(): library data was already initialized by a Kernel"
+    );
+}
+
+/// Get the mock [`sys::WolframLibraryData`] instance.
+pub fn library_data() -> sys::WolframLibraryData {
+    LIBRARY_DATA.0
+}
+
+/// Memory management strategy used to pass an array argument.
+///
+/// This must match the strategy declared by the parameter type of the function being
+/// called. See [`FromArg::parameter_type()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Passing {
+    /// Pass the array itself, without changing its share count.
+    Constant,
+    /// Increment the share count of the array, and pass the array itself.
+    Shared,
+    /// Pass a copy of the array, which is owned by the called function.
+    Manual,
+}
+
+/// Argument passed to a LibraryLink function by [`call()`].
+pub struct Arg<'a>(ArgKind<'a>);
+
+enum ArgKind<'a> {
+    Boolean(bool),
+    Integer(mint),
+    Real(mreal),
+    Complex(mcomplex),
+    String(&'a str),
+    /// An `MTensor`, `MNumericArray`, or `MImage`.
+    Array(*mut c_void, Passing),
+    SparseArray(sys::MSparseArray, Passing),
+    DataStore(sys::DataStore),
+}
+
+impl<'a> Arg<'a> {
+    fn new(kind: ArgKind<'a>) -> Self {
+        Arg(kind)
+    }
+
+    #[allow(missing_docs)]
+    pub fn boolean(value: bool) -> Self {
+        Arg::new(ArgKind::Boolean(value))
+    }
+
+    #[allow(missing_docs)]
+    pub fn integer(value: mint) -> Self {
+        Arg::new(ArgKind::Integer(value))
+    }
+
+    #[allow(missing_docs)]
+    pub fn real(value: mreal) -> Self {
+        Arg::new(ArgKind::Real(value))
+    }
+
+    #[allow(missing_docs)]
+    pub fn complex(value: mcomplex) -> Self {
+        Arg::new(ArgKind::Complex(value))
+    }
+
+    /// Pass a copy of `value`.
+    ///
+    /// The copy is freed after the call returns, unless the called function disowned
+    /// it.
+    pub fn string(value: &'a str) -> Self {
+        Arg::new(ArgKind::String(value))
+    }
+
+    #[allow(missing_docs)]
+    pub fn tensor<T>(tensor: &'a Tensor<T>, passing: Passing) -> Self {
+        Arg::new(ArgKind::Array(
+            unsafe { tensor.as_raw() } as *mut c_void,
+            passing,
+        ))
+    }
+
+    #[allow(missing_docs)]
+    pub fn numeric_array<T>(array: &'a NumericArray<T>, passing: Passing) -> Self {
+        Arg::new(ArgKind::Array(
+            unsafe { array.as_raw() } as *mut c_void,
+            passing,
+        ))
+    }
+
+    #[allow(missing_docs)]
+    pub fn image<T>(image: &'a Image<T>, passing: Passing) -> Self {
+        Arg::new(ArgKind::Array(
+            unsafe { image.as_raw() } as *mut c_void,
+            passing,
+        ))
+    }
+
+    #[allow(missing_docs)]
+    pub fn sparse_array<T>(array: &'a SparseArray<T>, passing: Passing) -> Self {
+        Arg::new(ArgKind::SparseArray(unsafe { array.as_raw() }, passing))
+    }
+
+    /// Pass a copy of `data`, which is owned by the called function.
+    pub fn data_store(data: &'a DataStore) -> Self {
+        Arg::new(ArgKind::DataStore(unsafe { data.as_raw() }))
+    }
+}
+
+/// Value returned by a LibraryLink function called using [`call()`].
+pub struct ReturnValue {
+    /// Storage for the returned value, which `arg` points into. This is large enough to
+    /// store any [`MArgument`] value, including an `mcomplex`.
+    _slot: Box<[u64; 2]>,
+    arg: MArgument,
+}
+
+impl ReturnValue {
+    /// Get the returned value.
+    ///
+    /// # Safety
+    ///
+    /// `R` must be the return type of the function that was called.
+    ///
+    /// If `R` takes ownership of the returned value (e.g. [`NumericArray`]), this
+    /// function must be called at most once.
+    ///
+    /// Returned strings are only valid until the next call to a function that returns a
+    /// string on the same thread.
+    pub unsafe fn get<'r, R: FromArg<'r>>(&'r self) -> R {
+        R::from_arg(&self.arg)
+    }
+}
+
+/// Call a LibraryLink function the same way the Kernel would.
+///
+/// This function returns the error code returned by `function`, if it is not
+/// [`LIBRARY_NO_ERROR`][sys::LIBRARY_NO_ERROR]. For example, if an
+/// [`#[export]`][crate::export] function panics, `call()` will return an error.
+///
+/// # Safety
+///
+/// The types of `args` must match the parameter types of `function`, including the
+/// memory management strategy used for array arguments.
+pub unsafe fn call(
+    function: LibraryFunction,
+    args: &[Arg],
+) -> Result<ReturnValue, c_int> {
+    initialize();
+
+    // One slot per argument, each large enough to store any MArgument value.
+    let mut slots: Vec<[u64; 2]> = vec![[0; 2]; args.len()];
+    let mut strings: Vec<*mut c_char> = Vec::new();
+
+    for (arg, slot) in args.iter().zip(&mut slots) {
+        let slot = slot.as_mut_ptr();
+
+        match arg.0 {
+            ArgKind::Boolean(value) => write(slot, mbool::from(value)),
+            ArgKind::Integer(value) => write(slot, value),
+            ArgKind::Real(value) => write(slot, value),
+            ArgKind::Complex(value) => write(slot, value),
+            ArgKind::String(value) => {
+                let string = kernel::new_string(value);
+                strings.push(string);
+                write(slot, string)
+            },
+            ArgKind::Array(array, passing) => {
+                let array = match passing {
+                    Passing::Constant => array,
+                    Passing::Shared => {
+                        arrays::share(array);
+                        array
+                    },
+                    Passing::Manual => arrays::duplicate(array),
+                };
+
+                write(slot, array)
+            },
+            ArgKind::SparseArray(array, passing) => {
+                let array = match passing {
+                    Passing::Constant => array,
+                    Passing::Shared => {
+                        sparse::share(array);
+                        array
+                    },
+                    Passing::Manual => {
+                        let mut copy: sys::MSparseArray = std::ptr::null_mut();
+                        sparse::MSparseArray_clone(array, &mut copy);
+                        copy
+                    },
+                };
+
+                write(slot, array)
+            },
+            ArgKind::DataStore(data) => write(slot, io::copyDataStore(data)),
+        }
+    }
+
+    // Every MArgument field is a pointer, so it doesn't matter which one is set.
+    let mut args: Vec<MArgument> = slots
+        .iter_mut()
+        .map(|slot| MArgument {
+            integer: slot.as_mut_ptr() as *mut mint,
+        })
+        .collect();
+
+    let mut slot = Box::new([0; 2]);
+    let res = MArgument {
+        integer: slot.as_mut_ptr() as *mut mint,
+    };
+
+    let err_code = function(library_data(), args.len() as mint, args.as_mut_ptr(), res);
+
+    for string in strings {
+        kernel::free_string(string);
+    }
+
+    if err_code != sys::LIBRARY_NO_ERROR as c_int {
+        return Err(err_code);
+    }
+
+    Ok(ReturnValue {
+        _slot: slot,
+        arg: res,
+    })
+}
+
+/// Take the messages issued on the current thread using the `Message()` runtime
+/// function.
+pub fn take_messages() -> Vec<String> {
+    kernel::MESSAGES.with(|messages| std::mem::take(&mut *messages.borrow_mut()))
+}
+
+/// Take the code evaluated on the current thread using the `evaluateExpression()`
+/// runtime function.
+///
+/// The mock runtime can only evaluate numeric literals, like `42` or `1.5`, and lists
+/// of numeric literals, like `{1, 2, 3}`. Evaluating any other code fails, but the
+/// code is still recorded.
+pub fn take_evaluations() -> Vec<String> {
+    kernel::EVALUATIONS.with(|evaluations| std::mem::take(&mut *evaluations.borrow_mut()))
+}
+
+/// Set the value returned by the `AbortQ()` runtime function, which is used by
+/// [`KernelThread::aborted()`][crate::KernelThread::aborted].
+///
+/// This setting is global, and affects every thread.
+pub fn set_aborted(aborted: bool) {
+    kernel::ABORT.store(aborted, Ordering::SeqCst)
+}
+
+/// Create a new instance of a managed library expression, as if
+/// [`CreateManagedLibraryExpression`][ref/CreateManagedLibraryExpression]<sub>WL</sub>
+/// had been evaluated.
+///
+/// Returns the ID of the new instance, or `None` if there is no library expression
+/// manager registered with the specified name.
+///
+/// Use [`releaseManagedLibraryExpression`][crate::rtl::releaseManagedLibraryExpression]
+/// to release the instance.
+///
+/// [ref/CreateManagedLibraryExpression]: https://reference.wolfram.com/language/ref/CreateManagedLibraryExpression.html
+pub fn create_managed_instance(manager: &str) -> Option<mint> {
+    kernel::create_managed_instance(manager)
+}
+
+/// Connect `func` to a library callback manager, as if
+/// [`ConnectLibraryCallbackFunction`][ref/ConnectLibraryCallbackFunction]<sub>WL</sub>
+/// had been evaluated with a compiled function.
+///
+/// `types` is the `{type, rank}` of each parameter of the compiled function, followed by
+/// the `{type, rank}` of its result. Each type is one of the
+/// [`MType_*`][sys::MType_Integer] constants.
+///
+/// `func` is called with the arguments and result [`MArgument`]s each time the
+/// connected function is called using
+/// [`callLibraryCallbackFunction`][crate::rtl::callLibraryCallbackFunction]. It must
+/// write a value of the result type to the result [`MArgument`].
+///
+/// Returns the ID of the connected function, or `None` if there is no callback
+/// manager registered with the specified name, or the manager did not accept the
+/// connection.
+///
+/// [ref/ConnectLibraryCallbackFunction]: https://reference.wolfram.com/language/ref/ConnectLibraryCallbackFunction.html
+pub fn connect_callback_function<F>(
+    manager: &str,
+    types: &[(mint, mint)],
+    func: F,
+) -> Option<mint>
+where
+    F: Fn(&[MArgument], MArgument) + Send + Sync + 'static,
+{
+    kernel::connect_callback_function(manager, types, std::sync::Arc::new(func))
+}
+
+/// Returns true if the function connected using [`connect_callback_function()`] with
+/// the specified ID has not yet been released.
+pub fn is_callback_function_connected(id: mint) -> bool {
+    kernel::is_callback_function_connected(id)
+}
+
+/// Event raised by an asynchronous task using
+/// [`AsyncTaskObject::raise_async_event()`].
+#[derive(Debug)]
+pub struct AsyncEvent {
+    /// The name of the event.
+    pub name: String,
+    /// The data attached to the event.
+    pub data: DataStore,
+}
+
+/// Take the events raised so far by `task`.
+pub fn take_async_events(task: &AsyncTaskObject) -> Vec<AsyncEvent> {
+    let mut events = io::EVENTS.lock().unwrap();
+
+    let (taken, remaining) = std::mem::take(&mut *events)
+        .into_iter()
+        .partition(|event| event.task_id == task.id());
+
+    *events = remaining;
+
+    taken
+        .into_iter()
+        .map(|event| AsyncEvent {
+            name: event.name,
+            data: unsafe { DataStore::from_raw(event.data) },
+        })
+        .collect()
+}
+
+/// Wait for the background thread of `task` to finish.
+///
+/// This does not stop the task. Use [`AsyncTaskObject::remove()`] to signal that the
+/// task should stop.
+pub fn wait_for_async_task(task: &AsyncTaskObject) {
+    io::join_task(task.id())
+}
+
+//======================================
+// Object registry
+//======================================
+
+/// Every object allocated by the mock runtime, and its type.
+///
+/// This is used to detect use-after-free bugs, double frees, and type confusion.
+static OBJECTS: Lazy<Mutex<HashMap<usize, TypeId>>> = Lazy::new(Default::default);
+
+fn alloc<T: 'static>(value: T) -> *mut T {
+    let ptr = Box::into_raw(Box::new(value));
+
+    OBJECTS
+        .lock()
+        .unwrap()
+        .insert(ptr as usize, TypeId::of::<T>());
+
+    ptr
+}
+
+unsafe fn object<'a, T: 'static>(ptr: *mut T) -> &'a mut T {
+    let type_id = OBJECTS.lock().unwrap().get(&(ptr as usize)).copied();
+
+    assert!(
+        type_id == Some(TypeId::of::<T>()),
+        "mock runtime: invalid or freed object: {:?}",
+        ptr
+    );
+
+    &mut *ptr
+}
+
+unsafe fn release<T: 'static>(ptr: *mut T) -> Box<T> {
+    let _: &mut T = object(ptr);
+
+    OBJECTS.lock().unwrap().remove(&(ptr as usize));
+
+    Box::from_raw(ptr)
+}
+
+unsafe fn write<T>(slot: *mut u64, value: T) {
+    std::ptr::write(slot as *mut T, value)
+}
+
+unsafe fn slice<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        return &[];
+    }
+
+    std::slice::from_raw_parts(ptr, len)
+}
+
+//======================================
+// Function tables
+//======================================
+
+struct MockLibraryData(sys::WolframLibraryData);
+
+// Safety: The mock library data is never modified after it is created.
+unsafe impl Send for MockLibraryData {}
+unsafe impl Sync for MockLibraryData {}
+
+static LIBRARY_DATA: Lazy<MockLibraryData> =
+    Lazy::new(|| MockLibraryData(Box::into_raw(Box::new(new_library_data()))));
+
+fn new_library_data() -> sys::st_WolframLibraryData {
+    use self::{arrays::*, io::*, kernel::*, sparse::*};
+
+    let numeric_arrays = sys::st_WolframNumericArrayLibrary_Functions {
+        MNumericArray_new: Some(MNumericArray_new),
+        MNumericArray_free: Some(MNumericArray_free),
+        MNumericArray_clone: Some(MNumericArray_clone),
+        MNumericArray_disown: Some(MNumericArray_disown),
+        MNumericArray_disownAll: Some(MNumericArray_disownAll),
+        MNumericArray_shareCount: Some(MNumericArray_shareCount),
+        MNumericArray_getType: Some(MNumericArray_getType),
+        MNumericArray_getRank: Some(MNumericArray_getRank),
+        MNumericArray_getDimensions: Some(MNumericArray_getDimensions),
+        MNumericArray_getFlattenedLength: Some(MNumericArray_getFlattenedLength),
+        MNumericArray_getData: Some(MNumericArray_getData),
+        MNumericArray_convertType: Some(MNumericArray_convertType),
+    };
+
+    let images = sys::st_WolframImageLibrary_Functions {
+        MImage_new2D: Some(MImage_new2D),
+        MImage_new3D: Some(MImage_new3D),
+        MImage_clone: Some(MImage_clone),
+        MImage_free: Some(MImage_free),
+        MImage_disown: Some(MImage_disown),
+        MImage_disownAll: Some(MImage_disownAll),
+        MImage_shareCount: Some(MImage_shareCount),
+        MImage_getDataType: Some(MImage_getDataType),
+        MImage_getRowCount: Some(MImage_getRowCount),
+        MImage_getColumnCount: Some(MImage_getColumnCount),
+        MImage_getSliceCount: Some(MImage_getSliceCount),
+        MImage_getRank: Some(MImage_getRank),
+        MImage_getChannels: Some(MImage_getChannels),
+        MImage_alphaChannelQ: Some(MImage_alphaChannelQ),
+        MImage_interleavedQ: Some(MImage_interleavedQ),
+        MImage_getColorSpace: Some(MImage_getColorSpace),
+        MImage_getFlattenedLength: Some(MImage_getFlattenedLength),
+        MImage_getBit: Some(MImage_getBit),
+        MImage_getByte: Some(MImage_getByte),
+        MImage_getBit16: Some(MImage_getBit16),
+        MImage_getReal32: Some(MImage_getReal32),
+        MImage_getReal: Some(MImage_getReal),
+        MImage_setBit: Some(MImage_setBit),
+        MImage_setByte: Some(MImage_setByte),
+        MImage_setBit16: Some(MImage_setBit16),
+        MImage_setReal32: Some(MImage_setReal32),
+        MImage_setReal: Some(MImage_setReal),
+        MImage_getRawData: Some(MImage_getRawData),
+        MImage_getBitData: Some(MImage_getBitData),
+        MImage_getByteData: Some(MImage_getByteData),
+        MImage_getBit16Data: Some(MImage_getBit16Data),
+        MImage_getReal32Data: Some(MImage_getReal32Data),
+        MImage_getRealData: Some(MImage_getRealData),
+        // Not supported by the mock runtime.
+        MImage_convertType: None,
+    };
+
+    let sparse_arrays = sys::st_WolframSparseLibrary_Functions {
+        MSparseArray_clone: Some(MSparseArray_clone),
+        MSparseArray_free: Some(MSparseArray_free),
+        MSparseArray_disown: Some(MSparseArray_disown),
+        MSparseArray_disownAll: Some(MSparseArray_disownAll),
+        MSparseArray_shareCount: Some(MSparseArray_shareCount),
+        MSparseArray_getRank: Some(MSparseArray_getRank),
+        MSparseArray_getDimensions: Some(MSparseArray_getDimensions),
+        MSparseArray_getImplicitValue: Some(MSparseArray_getImplicitValue),
+        MSparseArray_getExplicitValues: Some(MSparseArray_getExplicitValues),
+        MSparseArray_getRowPointers: Some(MSparseArray_getRowPointers),
+        MSparseArray_getColumnIndices: Some(MSparseArray_getColumnIndices),
+        MSparseArray_getExplicitPositions: Some(MSparseArray_getExplicitPositions),
+        MSparseArray_resetImplicitValue: Some(MSparseArray_resetImplicitValue),
+        MSparseArray_toMTensor: Some(MSparseArray_toMTensor),
+        MSparseArray_fromMTensor: Some(MSparseArray_fromMTensor),
+        MSparseArray_fromExplicitPositions: Some(MSparseArray_fromExplicitPositions),
+    };
+
+    let io = sys::st_WolframIOLibrary_Functions {
+        createAsynchronousTaskWithoutThread: Some(createAsynchronousTaskWithoutThread),
+        createAsynchronousTaskWithThread: Some(createAsynchronousTaskWithThread),
+        raiseAsyncEvent: Some(raiseAsyncEvent),
+        asynchronousTaskAliveQ: Some(asynchronousTaskAliveQ),
+        asynchronousTaskStartedQ: Some(asynchronousTaskStartedQ),
+        createDataStore: Some(createDataStore),
+        DataStore_addInteger: Some(DataStore_addInteger),
+        DataStore_addReal: Some(DataStore_addReal),
+        DataStore_addComplex: Some(DataStore_addComplex),
+        DataStore_addString: Some(DataStore_addString),
+        DataStore_addMTensor: Some(DataStore_addMTensor),
+        DataStore_addMRawArray: Some(DataStore_addMRawArray),
+        DataStore_addMImage: Some(DataStore_addMImage),
+        DataStore_addDataStore: Some(DataStore_addDataStore),
+        DataStore_addNamedInteger: Some(DataStore_addNamedInteger),
+        DataStore_addNamedReal: Some(DataStore_addNamedReal),
+        DataStore_addNamedComplex: Some(DataStore_addNamedComplex),
+        DataStore_addNamedString: Some(DataStore_addNamedString),
+        DataStore_addNamedMTensor: Some(DataStore_addNamedMTensor),
+        DataStore_addNamedMRawArray: Some(DataStore_addNamedMRawArray),
+        DataStore_addNamedMImage: Some(DataStore_addNamedMImage),
+        DataStore_addNamedDataStore: Some(DataStore_addNamedDataStore),
+        removeAsynchronousTask: Some(removeAsynchronousTask),
+        deleteDataStore: Some(deleteDataStore),
+        copyDataStore: Some(copyDataStore),
+        DataStore_getLength: Some(DataStore_getLength),
+        DataStore_getFirstNode: Some(DataStore_getFirstNode),
+        DataStore_getLastNode: Some(DataStore_getLastNode),
+        DataStoreNode_getNextNode: Some(DataStoreNode_getNextNode),
+        DataStoreNode_getDataType: Some(DataStoreNode_getDataType),
+        DataStoreNode_getData: Some(DataStoreNode_getData),
+        DataStoreNode_getName: Some(DataStoreNode_getName),
+        DataStore_addBoolean: Some(DataStore_addBoolean),
+        DataStore_addNamedBoolean: Some(DataStore_addNamedBoolean),
+        DataStore_addMNumericArray: Some(DataStore_addMNumericArray),
+        DataStore_addNamedMNumericArray: Some(DataStore_addNamedMNumericArray),
+        DataStore_addMSparseArray: Some(DataStore_addMSparseArray),
+        DataStore_addNamedMSparseArray: Some(DataStore_addNamedMSparseArray),
+    };
+
+    sys::st_WolframLibraryData {
+        UTF8String_disown: Some(UTF8String_disown),
+        MTensor_new: Some(MTensor_new),
+        MTensor_free: Some(MTensor_free),
+        MTensor_clone: Some(MTensor_clone),
+        MTensor_shareCount: Some(MTensor_shareCount),
+        MTensor_disown: Some(MTensor_disown),
+        MTensor_disownAll: Some(MTensor_disownAll),
+        MTensor_setInteger: Some(MTensor_setInteger),
+        MTensor_setReal: Some(MTensor_setReal),
+        MTensor_setComplex: Some(MTensor_setComplex),
+        MTensor_setMTensor: Some(MTensor_setMTensor),
+        MTensor_getInteger: Some(MTensor_getInteger),
+        MTensor_getReal: Some(MTensor_getReal),
+        MTensor_getComplex: Some(MTensor_getComplex),
+        MTensor_getMTensor: Some(MTensor_getMTensor),
+        MTensor_getRank: Some(MTensor_getRank),
+        MTensor_getDimensions: Some(MTensor_getDimensions),
+        MTensor_getType: Some(MTensor_getType),
+        MTensor_getFlattenedLength: Some(MTensor_getFlattenedLength),
+        MTensor_getIntegerData: Some(MTensor_getIntegerData),
+        MTensor_getRealData: Some(MTensor_getRealData),
+        MTensor_getComplexData: Some(MTensor_getComplexData),
+        Message: Some(Message),
+        AbortQ: Some(AbortQ),
+        getWSLINK: Some(getWSLINK),
+        processWSLINK: Some(processWSLINK),
+        evaluateExpression: Some(evaluateExpression),
+        runtimeData: std::ptr::null_mut(),
+        compileLibraryFunctions: std::ptr::null_mut(),
+        VersionNumber: sys::WolframLibraryVersion as mint,
+        registerInputStreamMethod: Some(registerInputStreamMethod),
+        unregisterInputStreamMethod: Some(unregisterInputStreamMethod),
+        registerOutputStreamMethod: Some(registerOutputStreamMethod),
+        unregisterOutputStreamMethod: Some(unregisterOutputStreamMethod),
+        ioLibraryFunctions: Box::into_raw(Box::new(io)),
+        getWSLINKEnvironment: Some(getWSLINKEnvironment),
+        sparseLibraryFunctions: Box::into_raw(Box::new(sparse_arrays)),
+        imageLibraryFunctions: Box::into_raw(Box::new(images)),
+        registerLibraryExpressionManager: Some(registerLibraryExpressionManager),
+        unregisterLibraryExpressionManager: Some(unregisterLibraryExpressionManager),
+        releaseManagedLibraryExpression: Some(releaseManagedLibraryExpression),
+        registerLibraryCallbackManager: Some(registerLibraryCallbackManager),
+        unregisterLibraryCallbackManager: Some(unregisterLibraryCallbackManager),
+        callLibraryCallbackFunction: Some(callLibraryCallbackFunction),
+        releaseLibraryCallbackFunction: Some(releaseLibraryCallbackFunction),
+        validatePath: Some(validatePath),
+        protectedModeQ: Some(protectedModeQ),
+        // Deprecated in favor of numericarrayLibraryFunctions.
+        rawarrayLibraryFunctions: std::ptr::null_mut(),
+        numericarrayLibraryFunctions: Box::into_raw(Box::new(numeric_arrays)),
+        setParallelThreadNumber: Some(setParallelThreadNumber),
+        restoreParallelThreadNumber: Some(restoreParallelThreadNumber),
+        getParallelThreadNumber: Some(getParallelThreadNumber),
+    }
+}
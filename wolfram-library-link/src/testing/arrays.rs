@@ -0,0 +1,879 @@
+//! Mock implementations of the `MTensor_*`, `MNumericArray_*`, and `MImage_*`
+//! functions.
+//!
+//! All three array types are represented by the same [`MockArray`] struct. Raw
+//! [`MTensor`], [`MNumericArray`], and [`MImage`] pointers are pointers to a
+//! [`MockArray`] allocated using [`alloc()`].
+
+#![allow(non_snake_case, non_upper_case_globals)]
+
+use std::os::raw::{c_int, c_void};
+
+use crate::sys::{
+    self, colorspace_t, errcode_t, imagedata_t, mbool, mcomplex, mint, mreal,
+    numericarray_convert_method_t, numericarray_data_t, raw_t_bit, raw_t_real32,
+    raw_t_real64, raw_t_ubit16, raw_t_ubit8, MImage, MNumericArray, MTensor,
+};
+
+use super::{alloc, object, release, slice};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Kind {
+    Tensor,
+    NumericArray,
+    Image,
+}
+
+#[derive(Clone)]
+pub(super) struct MockArray {
+    kind: Kind,
+    /// The `MType_*`, `MNumericArray_Type_*`, or `MImage_Type_*` value.
+    data_type: mint,
+    element_size: usize,
+    /// For images, this is `{rows, columns, channels}` or
+    /// `{slices, rows, columns, channels}`.
+    dims: Vec<mint>,
+    /// Element storage. `u64` is used to guarantee sufficient alignment for every
+    /// element type.
+    data: Vec<u64>,
+    share_count: mint,
+    image: Option<ImageLayout>,
+}
+
+#[derive(Copy, Clone)]
+struct ImageLayout {
+    color_space: colorspace_t,
+    interleaved: bool,
+}
+
+impl MockArray {
+    fn new(
+        kind: Kind,
+        data_type: mint,
+        element_size: usize,
+        dims: Vec<mint>,
+        image: Option<ImageLayout>,
+    ) -> MockArray {
+        let len = dims.iter().product::<mint>() as usize;
+
+        MockArray {
+            kind,
+            data_type,
+            element_size,
+            dims,
+            data: vec![0; (len * element_size).div_ceil(8)],
+            share_count: 0,
+            image,
+        }
+    }
+
+    fn flattened_length(&self) -> mint {
+        self.dims.iter().product()
+    }
+
+    fn rank(&self) -> mint {
+        match self.kind {
+            // Don't count the channels dimension.
+            Kind::Image => self.dims.len() as mint - 1,
+            Kind::Tensor | Kind::NumericArray => self.dims.len() as mint,
+        }
+    }
+
+    fn data_ptr(&mut self) -> *mut c_void {
+        self.data.as_mut_ptr() as *mut c_void
+    }
+
+    /// Get the flat index of the element at the 1-based position `pos`.
+    unsafe fn index(&self, pos: *const mint) -> Option<usize> {
+        let pos: &[mint] = slice(pos, self.dims.len());
+
+        let mut index: mint = 0;
+
+        for (&p, &dim) in pos.iter().zip(&self.dims) {
+            if !(1..=dim).contains(&p) {
+                return None;
+            }
+
+            index = index * dim + (p - 1);
+        }
+
+        Some(index as usize)
+    }
+
+    /// Get the flat index of `channel` of the pixel at the 1-based position `pos`.
+    unsafe fn pixel_index(&self, pos: *const mint, channel: mint) -> Option<usize> {
+        let layout = self.image?;
+
+        let (slices, rows, columns, channels) = match *self.dims {
+            [rows, columns, channels] => (1, rows, columns, channels),
+            [slices, rows, columns, channels] => (slices, rows, columns, channels),
+            _ => return None,
+        };
+
+        let (slice, row, column) = match *slice(pos, self.dims.len() - 1) {
+            [row, column] => (1, row, column),
+            [slice, row, column] => (slice, row, column),
+            _ => return None,
+        };
+
+        let in_bounds = (1..=slices).contains(&slice)
+            && (1..=rows).contains(&row)
+            && (1..=columns).contains(&column)
+            && (1..=channels).contains(&channel);
+
+        if !in_bounds {
+            return None;
+        }
+
+        let (slice, row, column, channel) = (slice - 1, row - 1, column - 1, channel - 1);
+
+        let index = if layout.interleaved {
+            ((slice * rows + row) * columns + column) * channels + channel
+        } else {
+            ((channel * slices + slice) * rows + row) * columns + column
+        };
+
+        Some(index as usize)
+    }
+
+    unsafe fn read<T: Copy>(&self, index: usize) -> T {
+        debug_assert!(std::mem::size_of::<T>() == self.element_size);
+
+        std::ptr::read((self.data.as_ptr() as *const T).add(index))
+    }
+
+    unsafe fn write<T: Copy>(&mut self, index: usize, value: T) {
+        debug_assert!(std::mem::size_of::<T>() == self.element_size);
+
+        std::ptr::write((self.data.as_mut_ptr() as *mut T).add(index), value)
+    }
+}
+
+unsafe fn array<'a, P>(raw: *mut P, kind: Kind) -> &'a mut MockArray {
+    let array: &mut MockArray = object(raw as *mut MockArray);
+
+    assert_eq!(
+        array.kind, kind,
+        "mock runtime: expected {:?} object, got {:?}",
+        kind, array.kind
+    );
+
+    array
+}
+
+/// Read `rank` dimensions from `dims`, returning `None` if any are negative.
+unsafe fn dimensions(rank: mint, dims: *const mint) -> Option<Vec<mint>> {
+    if rank < 0 {
+        return None;
+    }
+
+    let dims: Vec<mint> = slice(dims, rank as usize).to_vec();
+
+    if dims.iter().any(|&dim| dim < 0) {
+        return None;
+    }
+
+    Some(dims)
+}
+
+/// Increment the share count of a tensor, numeric array, or image.
+///
+/// This simulates the Kernel passing `raw` using the `"Shared"` memory management
+/// strategy.
+pub(super) unsafe fn share<P>(raw: *mut P) {
+    let array: &mut MockArray = object(raw as *mut MockArray);
+
+    array.share_count += 1;
+}
+
+/// Construct a copy of a tensor, numeric array, or image.
+pub(super) unsafe fn duplicate<P>(raw: *mut P) -> *mut P {
+    let array: &MockArray = object(raw as *mut MockArray);
+
+    let mut copy = array.clone();
+    copy.share_count = 0;
+
+    alloc(copy) as *mut P
+}
+
+unsafe fn free<P>(raw: *mut P, kind: Kind) {
+    let array: &MockArray = array(raw, kind);
+
+    assert_eq!(
+        array.share_count, 0,
+        "mock runtime: attempted to free a shared {:?} (share count: {})",
+        kind, array.share_count
+    );
+
+    drop(release(raw as *mut MockArray));
+}
+
+unsafe fn disown<P>(raw: *mut P, kind: Kind) {
+    let array: &mut MockArray = array(raw, kind);
+
+    assert!(
+        array.share_count > 0,
+        "mock runtime: attempted to disown a {:?} that is not shared",
+        kind
+    );
+
+    array.share_count -= 1;
+}
+
+/// A single tensor element, stored as raw bytes.
+///
+/// Elements smaller than 16 bytes only use the leading bytes, and the remaining bytes
+/// are zero.
+pub(super) type Element = [u64; 2];
+
+/// Get the type, dimensions, and elements of a tensor.
+pub(super) unsafe fn tensor_parts(tensor: MTensor) -> (mint, Vec<mint>, Vec<Element>) {
+    let array: &MockArray = array(tensor, Kind::Tensor);
+
+    let elements = (0..array.flattened_length() as usize)
+        .map(|index| {
+            let mut element: Element = [0; 2];
+
+            std::ptr::copy_nonoverlapping(
+                (array.data.as_ptr() as *const u8).add(index * array.element_size),
+                element.as_mut_ptr() as *mut u8,
+                array.element_size,
+            );
+
+            element
+        })
+        .collect();
+
+    (array.data_type, array.dims.clone(), elements)
+}
+
+/// Construct a new tensor from its type, dimensions, and elements.
+pub(super) unsafe fn tensor_from_parts(
+    type_: mint,
+    dims: Vec<mint>,
+    elements: &[Element],
+) -> MTensor {
+    let element_size =
+        tensor_element_size(type_).expect("mock runtime: invalid tensor type");
+
+    let mut array = MockArray::new(Kind::Tensor, type_, element_size, dims, None);
+
+    assert_eq!(array.flattened_length() as usize, elements.len());
+
+    for (index, element) in elements.iter().enumerate() {
+        std::ptr::copy_nonoverlapping(
+            element.as_ptr() as *const u8,
+            (array.data.as_mut_ptr() as *mut u8).add(index * element_size),
+            element_size,
+        );
+    }
+
+    alloc(array) as MTensor
+}
+
+//======================================
+// MTensor_*
+//======================================
+
+fn tensor_element_size(type_: mint) -> Option<usize> {
+    match type_ as u32 {
+        sys::MType_Integer => Some(std::mem::size_of::<mint>()),
+        sys::MType_Real => Some(std::mem::size_of::<mreal>()),
+        sys::MType_Complex => Some(std::mem::size_of::<mcomplex>()),
+        _ => None,
+    }
+}
+
+pub(super) unsafe extern "C" fn MTensor_new(
+    type_: mint,
+    rank: mint,
+    dims: *const mint,
+    res: *mut MTensor,
+) -> c_int {
+    let element_size = match tensor_element_size(type_) {
+        Some(size) => size,
+        None => return sys::LIBRARY_TYPE_ERROR as c_int,
+    };
+
+    let dims = match dimensions(rank, dims) {
+        Some(dims) => dims,
+        None => return sys::LIBRARY_DIMENSION_ERROR as c_int,
+    };
+
+    let array = MockArray::new(Kind::Tensor, type_, element_size, dims, None);
+
+    *res = alloc(array) as MTensor;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MTensor_free(tensor: MTensor) {
+    free(tensor, Kind::Tensor)
+}
+
+pub(super) unsafe extern "C" fn MTensor_clone(
+    tensor: MTensor,
+    res: *mut MTensor,
+) -> c_int {
+    let _ = array(tensor, Kind::Tensor);
+
+    *res = duplicate(tensor);
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MTensor_shareCount(tensor: MTensor) -> mint {
+    array(tensor, Kind::Tensor).share_count
+}
+
+pub(super) unsafe extern "C" fn MTensor_disown(tensor: MTensor) {
+    disown(tensor, Kind::Tensor)
+}
+
+pub(super) unsafe extern "C" fn MTensor_disownAll(tensor: MTensor) {
+    array(tensor, Kind::Tensor).share_count = 0;
+}
+
+unsafe fn tensor_set<T: Copy>(
+    tensor: MTensor,
+    pos: *mut mint,
+    type_: u32,
+    value: T,
+) -> c_int {
+    let array = array(tensor, Kind::Tensor);
+
+    if array.data_type != mint::from(type_) {
+        return sys::LIBRARY_TYPE_ERROR as c_int;
+    }
+
+    match array.index(pos) {
+        Some(index) => array.write(index, value),
+        None => return sys::LIBRARY_DIMENSION_ERROR as c_int,
+    }
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+unsafe fn tensor_get<T: Copy>(
+    tensor: MTensor,
+    pos: *mut mint,
+    type_: u32,
+    res: *mut T,
+) -> c_int {
+    let array = array(tensor, Kind::Tensor);
+
+    if array.data_type != mint::from(type_) {
+        return sys::LIBRARY_TYPE_ERROR as c_int;
+    }
+
+    match array.index(pos) {
+        Some(index) => *res = array.read(index),
+        None => return sys::LIBRARY_DIMENSION_ERROR as c_int,
+    }
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MTensor_setInteger(
+    tensor: MTensor,
+    pos: *mut mint,
+    value: mint,
+) -> c_int {
+    tensor_set(tensor, pos, sys::MType_Integer, value)
+}
+
+pub(super) unsafe extern "C" fn MTensor_setReal(
+    tensor: MTensor,
+    pos: *mut mint,
+    value: mreal,
+) -> c_int {
+    tensor_set(tensor, pos, sys::MType_Real, value)
+}
+
+pub(super) unsafe extern "C" fn MTensor_setComplex(
+    tensor: MTensor,
+    pos: *mut mint,
+    value: mcomplex,
+) -> c_int {
+    tensor_set(tensor, pos, sys::MType_Complex, value)
+}
+
+pub(super) unsafe extern "C" fn MTensor_setMTensor(
+    _tensor: MTensor,
+    _value: MTensor,
+    _pos: *mut mint,
+    _rank: mint,
+) -> c_int {
+    // Not supported by the mock runtime.
+    sys::LIBRARY_FUNCTION_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MTensor_getInteger(
+    tensor: MTensor,
+    pos: *mut mint,
+    res: *mut mint,
+) -> c_int {
+    tensor_get(tensor, pos, sys::MType_Integer, res)
+}
+
+pub(super) unsafe extern "C" fn MTensor_getReal(
+    tensor: MTensor,
+    pos: *mut mint,
+    res: *mut mreal,
+) -> c_int {
+    tensor_get(tensor, pos, sys::MType_Real, res)
+}
+
+pub(super) unsafe extern "C" fn MTensor_getComplex(
+    tensor: MTensor,
+    pos: *mut mint,
+    res: *mut mcomplex,
+) -> c_int {
+    tensor_get(tensor, pos, sys::MType_Complex, res)
+}
+
+pub(super) unsafe extern "C" fn MTensor_getMTensor(
+    _tensor: MTensor,
+    _pos: *mut mint,
+    _rank: mint,
+    _res: *mut MTensor,
+) -> c_int {
+    // Not supported by the mock runtime.
+    sys::LIBRARY_FUNCTION_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MTensor_getRank(tensor: MTensor) -> mint {
+    array(tensor, Kind::Tensor).rank()
+}
+
+pub(super) unsafe extern "C" fn MTensor_getDimensions(tensor: MTensor) -> *const mint {
+    array(tensor, Kind::Tensor).dims.as_ptr()
+}
+
+pub(super) unsafe extern "C" fn MTensor_getType(tensor: MTensor) -> mint {
+    array(tensor, Kind::Tensor).data_type
+}
+
+pub(super) unsafe extern "C" fn MTensor_getFlattenedLength(tensor: MTensor) -> mint {
+    array(tensor, Kind::Tensor).flattened_length()
+}
+
+unsafe fn tensor_data(tensor: MTensor, type_: u32) -> *mut c_void {
+    let array = array(tensor, Kind::Tensor);
+
+    if array.data_type != mint::from(type_) {
+        return std::ptr::null_mut();
+    }
+
+    array.data_ptr()
+}
+
+pub(super) unsafe extern "C" fn MTensor_getIntegerData(tensor: MTensor) -> *mut mint {
+    tensor_data(tensor, sys::MType_Integer) as *mut mint
+}
+
+pub(super) unsafe extern "C" fn MTensor_getRealData(tensor: MTensor) -> *mut mreal {
+    tensor_data(tensor, sys::MType_Real) as *mut mreal
+}
+
+pub(super) unsafe extern "C" fn MTensor_getComplexData(tensor: MTensor) -> *mut mcomplex {
+    tensor_data(tensor, sys::MType_Complex) as *mut mcomplex
+}
+
+//======================================
+// MNumericArray_*
+//======================================
+
+fn numeric_array_element_size(type_: numericarray_data_t) -> Option<usize> {
+    use sys::MNumericArray_Data_Type::*;
+
+    let size = match type_ {
+        MNumericArray_Type_Bit8 | MNumericArray_Type_UBit8 => 1,
+        MNumericArray_Type_Bit16 | MNumericArray_Type_UBit16 => 2,
+        MNumericArray_Type_Bit32 | MNumericArray_Type_UBit32 => 4,
+        MNumericArray_Type_Bit64 | MNumericArray_Type_UBit64 => 8,
+        MNumericArray_Type_Real16 => 2,
+        MNumericArray_Type_Real32 => 4,
+        MNumericArray_Type_Real64 => 8,
+        MNumericArray_Type_Complex_Real16 => 4,
+        MNumericArray_Type_Complex_Real32 => 8,
+        MNumericArray_Type_Complex_Real64 => 16,
+        _ => return None,
+    };
+
+    Some(size)
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_new(
+    type_: numericarray_data_t,
+    rank: mint,
+    dims: *const mint,
+    res: *mut MNumericArray,
+) -> errcode_t {
+    let element_size = match numeric_array_element_size(type_) {
+        Some(size) => size,
+        None => return sys::LIBRARY_TYPE_ERROR as c_int,
+    };
+
+    let dims = match dimensions(rank, dims) {
+        Some(dims) => dims,
+        None => return sys::LIBRARY_DIMENSION_ERROR as c_int,
+    };
+
+    let array = MockArray::new(
+        Kind::NumericArray,
+        mint::from(type_),
+        element_size,
+        dims,
+        None,
+    );
+
+    *res = alloc(array) as MNumericArray;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_free(array: MNumericArray) {
+    free(array, Kind::NumericArray)
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_clone(
+    array: MNumericArray,
+    res: *mut MNumericArray,
+) -> errcode_t {
+    let _ = self::array(array, Kind::NumericArray);
+
+    *res = duplicate(array);
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_disown(array: MNumericArray) {
+    disown(array, Kind::NumericArray)
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_disownAll(array: MNumericArray) {
+    self::array(array, Kind::NumericArray).share_count = 0;
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_shareCount(array: MNumericArray) -> mint {
+    self::array(array, Kind::NumericArray).share_count
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_getType(
+    array: MNumericArray,
+) -> numericarray_data_t {
+    self::array(array, Kind::NumericArray).data_type as numericarray_data_t
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_getRank(array: MNumericArray) -> mint {
+    self::array(array, Kind::NumericArray).rank()
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_getDimensions(
+    array: MNumericArray,
+) -> *const mint {
+    self::array(array, Kind::NumericArray).dims.as_ptr()
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_getFlattenedLength(
+    array: MNumericArray,
+) -> mint {
+    self::array(array, Kind::NumericArray).flattened_length()
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_getData(
+    array: MNumericArray,
+) -> *mut c_void {
+    self::array(array, Kind::NumericArray).data_ptr()
+}
+
+pub(super) unsafe extern "C" fn MNumericArray_convertType(
+    _res: *mut MNumericArray,
+    _array: MNumericArray,
+    _type: numericarray_data_t,
+    _method: numericarray_convert_method_t,
+    _tolerance: mreal,
+) -> errcode_t {
+    // Not supported by the mock runtime.
+    sys::LIBRARY_FUNCTION_ERROR as c_int
+}
+
+//======================================
+// MImage_*
+//======================================
+
+fn image_element_size(type_: imagedata_t) -> Option<usize> {
+    use sys::MImage_Data_Type::*;
+
+    let size = match type_ {
+        MImage_Type_Bit | MImage_Type_Bit8 => 1,
+        MImage_Type_Bit16 => 2,
+        MImage_Type_Real32 => 4,
+        MImage_Type_Real => 8,
+        _ => return None,
+    };
+
+    Some(size)
+}
+
+/// The number of channels used by `color_space`, not including an alpha channel.
+fn color_space_channels(color_space: colorspace_t) -> Option<mint> {
+    use sys::MImage_CS_Type::*;
+
+    match color_space {
+        MImage_CS_Gray => Some(1),
+        MImage_CS_RGB | MImage_CS_HSB | MImage_CS_XYZ | MImage_CS_LUV | MImage_CS_LAB
+        | MImage_CS_LCH => Some(3),
+        MImage_CS_CMYK => Some(4),
+        _ => None,
+    }
+}
+
+unsafe fn new_image(
+    dims: Vec<mint>,
+    type_: imagedata_t,
+    color_space: colorspace_t,
+    interleaved: mbool,
+    res: *mut MImage,
+) -> c_int {
+    let element_size = match image_element_size(type_) {
+        Some(size) => size,
+        None => return sys::LIBRARY_TYPE_ERROR as c_int,
+    };
+
+    if dims.iter().any(|&dim| dim <= 0) {
+        return sys::LIBRARY_DIMENSION_ERROR as c_int;
+    }
+
+    let layout = ImageLayout {
+        color_space,
+        interleaved: interleaved != 0,
+    };
+
+    let array = MockArray::new(
+        Kind::Image,
+        mint::from(type_),
+        element_size,
+        dims,
+        Some(layout),
+    );
+
+    *res = alloc(array) as MImage;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MImage_new2D(
+    width: mint,
+    height: mint,
+    channels: mint,
+    type_: imagedata_t,
+    color_space: colorspace_t,
+    interleaved: mbool,
+    res: *mut MImage,
+) -> c_int {
+    new_image(
+        vec![height, width, channels],
+        type_,
+        color_space,
+        interleaved,
+        res,
+    )
+}
+
+pub(super) unsafe extern "C" fn MImage_new3D(
+    slices: mint,
+    width: mint,
+    height: mint,
+    channels: mint,
+    type_: imagedata_t,
+    color_space: colorspace_t,
+    interleaved: mbool,
+    res: *mut MImage,
+) -> c_int {
+    new_image(
+        vec![slices, height, width, channels],
+        type_,
+        color_space,
+        interleaved,
+        res,
+    )
+}
+
+pub(super) unsafe extern "C" fn MImage_clone(image: MImage, res: *mut MImage) -> c_int {
+    let _ = array(image, Kind::Image);
+
+    *res = duplicate(image);
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MImage_free(image: MImage) {
+    free(image, Kind::Image)
+}
+
+pub(super) unsafe extern "C" fn MImage_disown(image: MImage) {
+    disown(image, Kind::Image)
+}
+
+pub(super) unsafe extern "C" fn MImage_disownAll(image: MImage) {
+    array(image, Kind::Image).share_count = 0;
+}
+
+pub(super) unsafe extern "C" fn MImage_shareCount(image: MImage) -> mint {
+    array(image, Kind::Image).share_count
+}
+
+pub(super) unsafe extern "C" fn MImage_getDataType(image: MImage) -> imagedata_t {
+    array(image, Kind::Image).data_type as imagedata_t
+}
+
+pub(super) unsafe extern "C" fn MImage_getRowCount(image: MImage) -> mint {
+    let dims = &array(image, Kind::Image).dims;
+
+    dims[dims.len() - 3]
+}
+
+pub(super) unsafe extern "C" fn MImage_getColumnCount(image: MImage) -> mint {
+    let dims = &array(image, Kind::Image).dims;
+
+    dims[dims.len() - 2]
+}
+
+pub(super) unsafe extern "C" fn MImage_getSliceCount(image: MImage) -> mint {
+    match *array(image, Kind::Image).dims {
+        [slices, _, _, _] => slices,
+        _ => 0,
+    }
+}
+
+pub(super) unsafe extern "C" fn MImage_getRank(image: MImage) -> mint {
+    array(image, Kind::Image).rank()
+}
+
+pub(super) unsafe extern "C" fn MImage_getChannels(image: MImage) -> mint {
+    let dims = &array(image, Kind::Image).dims;
+
+    dims[dims.len() - 1]
+}
+
+pub(super) unsafe extern "C" fn MImage_alphaChannelQ(image: MImage) -> mbool {
+    let array = array(image, Kind::Image);
+
+    let channels = array.dims[array.dims.len() - 1];
+    let color_space = array.image.map(|layout| layout.color_space);
+
+    match color_space.and_then(color_space_channels) {
+        Some(color_channels) => mbool::from(channels == color_channels + 1),
+        None => 0,
+    }
+}
+
+pub(super) unsafe extern "C" fn MImage_interleavedQ(image: MImage) -> mbool {
+    let interleaved = array(image, Kind::Image)
+        .image
+        .is_some_and(|layout| layout.interleaved);
+
+    mbool::from(interleaved)
+}
+
+pub(super) unsafe extern "C" fn MImage_getColorSpace(image: MImage) -> colorspace_t {
+    array(image, Kind::Image)
+        .image
+        .map_or(sys::MImage_CS_Type::MImage_CS_Undef, |layout| {
+            layout.color_space
+        })
+}
+
+pub(super) unsafe extern "C" fn MImage_getFlattenedLength(image: MImage) -> mint {
+    array(image, Kind::Image).flattened_length()
+}
+
+unsafe fn image_get<T: Copy>(
+    image: MImage,
+    pos: *mut mint,
+    channel: mint,
+    type_: imagedata_t,
+    res: *mut T,
+) -> c_int {
+    let array = array(image, Kind::Image);
+
+    if array.data_type != mint::from(type_) {
+        return sys::LIBRARY_TYPE_ERROR as c_int;
+    }
+
+    match array.pixel_index(pos, channel) {
+        Some(index) => *res = array.read(index),
+        None => return sys::LIBRARY_DIMENSION_ERROR as c_int,
+    }
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+unsafe fn image_set<T: Copy>(
+    image: MImage,
+    pos: *mut mint,
+    channel: mint,
+    type_: imagedata_t,
+    value: T,
+) -> c_int {
+    let array = array(image, Kind::Image);
+
+    if array.data_type != mint::from(type_) {
+        return sys::LIBRARY_TYPE_ERROR as c_int;
+    }
+
+    match array.pixel_index(pos, channel) {
+        Some(index) => array.write(index, value),
+        None => return sys::LIBRARY_DIMENSION_ERROR as c_int,
+    }
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+macro_rules! image_accessors {
+    ($($get:ident, $set:ident, $data:ident: $ty:ty = $type_:ident;)*) => {
+        $(
+            pub(super) unsafe extern "C" fn $get(
+                image: MImage,
+                pos: *mut mint,
+                channel: mint,
+                res: *mut $ty,
+            ) -> c_int {
+                image_get(image, pos, channel, sys::MImage_Data_Type::$type_, res)
+            }
+
+            pub(super) unsafe extern "C" fn $set(
+                image: MImage,
+                pos: *mut mint,
+                channel: mint,
+                value: $ty,
+            ) -> c_int {
+                image_set(image, pos, channel, sys::MImage_Data_Type::$type_, value)
+            }
+
+            pub(super) unsafe extern "C" fn $data(image: MImage) -> *mut $ty {
+                let array = array(image, Kind::Image);
+
+                if array.data_type != mint::from(sys::MImage_Data_Type::$type_) {
+                    return std::ptr::null_mut();
+                }
+
+                array.data_ptr() as *mut $ty
+            }
+        )*
+    };
+}
+
+image_accessors! {
+    MImage_getBit, MImage_setBit, MImage_getBitData: raw_t_bit = MImage_Type_Bit;
+    MImage_getByte, MImage_setByte, MImage_getByteData: raw_t_ubit8 = MImage_Type_Bit8;
+    MImage_getBit16, MImage_setBit16, MImage_getBit16Data: raw_t_ubit16 = MImage_Type_Bit16;
+    MImage_getReal32, MImage_setReal32, MImage_getReal32Data: raw_t_real32 = MImage_Type_Real32;
+    MImage_getReal, MImage_setReal, MImage_getRealData: raw_t_real64 = MImage_Type_Real;
+}
+
+pub(super) unsafe extern "C" fn MImage_getRawData(image: MImage) -> *mut c_void {
+    array(image, Kind::Image).data_ptr()
+}
@@ -0,0 +1,388 @@
+//! Mock implementations of the `WolframIOLibrary_Functions` functions: `DataStore_*`
+//! and asynchronous tasks.
+
+#![allow(non_snake_case)]
+
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use once_cell::sync::Lazy;
+
+use crate::sys::{
+    self, errcode_t, mbool, mcomplex, mint, mreal, type_t, DataStore, DataStoreNode,
+    MArgument, MImage, MNumericArray, MRawArray, MSparseArray, MTensor,
+};
+
+use super::{alloc, arrays, object, release, sparse};
+
+//======================================
+// DataStore_*
+//======================================
+
+#[derive(Default)]
+pub(super) struct MockDataStore {
+    /// Nodes are boxed so that their addresses, which are linked by [`MockNode::next`]
+    /// and returned as `DataStoreNode` pointers, are stable.
+    #[allow(clippy::vec_box)]
+    nodes: Vec<Box<MockNode>>,
+}
+
+struct MockNode {
+    name: Option<CString>,
+    data_type: type_t,
+    /// Storage for the node value. An [`MArgument`] returned by
+    /// [`DataStoreNode_getData()`] points at this field.
+    value: [u64; 2],
+    next: *mut MockNode,
+}
+
+impl MockNode {
+    unsafe fn write<T>(data_type: u32, value: T) -> MockNode {
+        let mut node = MockNode {
+            name: None,
+            data_type: data_type as type_t,
+            value: [0; 2],
+            next: std::ptr::null_mut(),
+        };
+
+        debug_assert!(std::mem::size_of::<T>() <= std::mem::size_of_val(&node.value));
+
+        std::ptr::write(node.value.as_mut_ptr() as *mut T, value);
+
+        node
+    }
+
+    unsafe fn read<T: Copy>(&self) -> T {
+        std::ptr::read(self.value.as_ptr() as *const T)
+    }
+
+    /// Construct a deep copy of this node.
+    unsafe fn duplicate(&self) -> MockNode {
+        let mut copy = MockNode {
+            name: self.name.clone(),
+            data_type: self.data_type,
+            value: self.value,
+            next: std::ptr::null_mut(),
+        };
+
+        let value: *mut c_void = self.read();
+
+        let value_copy: *mut c_void = match self.data_type as u32 {
+            sys::MType_UTF8String => {
+                CString::from(CStr::from_ptr(value as *const c_char)).into_raw()
+                    as *mut c_void
+            },
+            sys::MType_Tensor | sys::MType_NumericArray | sys::MType_Image => {
+                arrays::duplicate(value)
+            },
+            sys::MType_SparseArray => {
+                let mut copy: MSparseArray = std::ptr::null_mut();
+                sparse::MSparseArray_clone(value as MSparseArray, &mut copy);
+                copy as *mut c_void
+            },
+            sys::MType_DataStore => copyDataStore(value as DataStore) as *mut c_void,
+            _ => return copy,
+        };
+
+        std::ptr::write(copy.value.as_mut_ptr() as *mut *mut c_void, value_copy);
+
+        copy
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        unsafe {
+            let value: *mut c_void = self.read();
+
+            match self.data_type as u32 {
+                sys::MType_UTF8String => drop(CString::from_raw(value as *mut c_char)),
+                sys::MType_Tensor => arrays::MTensor_free(value as MTensor),
+                sys::MType_NumericArray => {
+                    arrays::MNumericArray_free(value as MNumericArray)
+                },
+                sys::MType_Image => arrays::MImage_free(value as MImage),
+                sys::MType_SparseArray => {
+                    sparse::MSparseArray_free(value as MSparseArray)
+                },
+                sys::MType_DataStore => deleteDataStore(value as DataStore),
+                _ => (),
+            }
+        }
+    }
+}
+
+unsafe fn data_store<'a>(ds: DataStore) -> &'a mut MockDataStore {
+    object(ds as *mut MockDataStore)
+}
+
+unsafe fn add(ds: DataStore, name: *mut c_char, node: MockNode) {
+    let ds = data_store(ds);
+
+    let mut node = Box::new(node);
+
+    if !name.is_null() {
+        node.name = Some(CString::from(CStr::from_ptr(name)));
+    }
+
+    if let Some(last) = ds.nodes.last_mut() {
+        last.next = &mut *node;
+    }
+
+    ds.nodes.push(node);
+}
+
+unsafe fn add_string(ds: DataStore, name: *mut c_char, value: *mut c_char) {
+    let value = CString::from(CStr::from_ptr(value)).into_raw();
+
+    add(ds, name, MockNode::write(sys::MType_UTF8String, value))
+}
+
+pub(super) unsafe extern "C" fn createDataStore() -> DataStore {
+    alloc(MockDataStore::default()) as DataStore
+}
+
+pub(super) unsafe extern "C" fn deleteDataStore(ds: DataStore) {
+    drop(release(ds as *mut MockDataStore))
+}
+
+pub(super) unsafe extern "C" fn copyDataStore(ds: DataStore) -> DataStore {
+    let copy = createDataStore();
+
+    for node in &data_store(ds).nodes {
+        add(copy, std::ptr::null_mut(), node.duplicate());
+    }
+
+    copy
+}
+
+pub(super) unsafe extern "C" fn DataStore_getLength(ds: DataStore) -> mint {
+    data_store(ds).nodes.len() as mint
+}
+
+pub(super) unsafe extern "C" fn DataStore_getFirstNode(ds: DataStore) -> DataStoreNode {
+    match data_store(ds).nodes.first_mut() {
+        Some(node) => &mut **node as *mut MockNode as DataStoreNode,
+        None => std::ptr::null_mut(),
+    }
+}
+
+pub(super) unsafe extern "C" fn DataStore_getLastNode(ds: DataStore) -> DataStoreNode {
+    match data_store(ds).nodes.last_mut() {
+        Some(node) => &mut **node as *mut MockNode as DataStoreNode,
+        None => std::ptr::null_mut(),
+    }
+}
+
+pub(super) unsafe extern "C" fn DataStoreNode_getNextNode(
+    node: DataStoreNode,
+) -> DataStoreNode {
+    (*(node as *mut MockNode)).next as DataStoreNode
+}
+
+pub(super) unsafe extern "C" fn DataStoreNode_getDataType(node: DataStoreNode) -> type_t {
+    (*(node as *mut MockNode)).data_type
+}
+
+pub(super) unsafe extern "C" fn DataStoreNode_getData(
+    node: DataStoreNode,
+    res: *mut MArgument,
+) -> errcode_t {
+    let node = &mut *(node as *mut MockNode);
+
+    // Every MArgument field is a pointer, so it doesn't matter which one is set.
+    *res = MArgument {
+        integer: node.value.as_mut_ptr() as *mut mint,
+    };
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn DataStoreNode_getName(
+    node: DataStoreNode,
+    res: *mut *mut c_char,
+) -> errcode_t {
+    match (*(node as *mut MockNode)).name {
+        Some(ref name) => {
+            *res = name.as_ptr() as *mut c_char;
+            sys::LIBRARY_NO_ERROR as c_int
+        },
+        None => sys::LIBRARY_FUNCTION_ERROR as c_int,
+    }
+}
+
+macro_rules! data_store_add {
+    ($($add:ident, $add_named:ident: $ty:ty => $type_:ident;)*) => {
+        $(
+            pub(super) unsafe extern "C" fn $add(ds: DataStore, value: $ty) {
+                add(ds, std::ptr::null_mut(), MockNode::write(sys::$type_, value))
+            }
+
+            pub(super) unsafe extern "C" fn $add_named(
+                ds: DataStore,
+                name: *mut c_char,
+                value: $ty,
+            ) {
+                add(ds, name, MockNode::write(sys::$type_, value))
+            }
+        )*
+    };
+}
+
+data_store_add! {
+    DataStore_addBoolean, DataStore_addNamedBoolean: mbool => MType_Boolean;
+    DataStore_addInteger, DataStore_addNamedInteger: mint => MType_Integer;
+    DataStore_addReal, DataStore_addNamedReal: mreal => MType_Real;
+    DataStore_addComplex, DataStore_addNamedComplex: mcomplex => MType_Complex;
+    DataStore_addMTensor, DataStore_addNamedMTensor: MTensor => MType_Tensor;
+    DataStore_addMRawArray, DataStore_addNamedMRawArray: MRawArray => MType_NumericArray;
+    DataStore_addMNumericArray, DataStore_addNamedMNumericArray:
+        MNumericArray => MType_NumericArray;
+    DataStore_addMImage, DataStore_addNamedMImage: MImage => MType_Image;
+    DataStore_addMSparseArray, DataStore_addNamedMSparseArray:
+        MSparseArray => MType_SparseArray;
+    DataStore_addDataStore, DataStore_addNamedDataStore: DataStore => MType_DataStore;
+}
+
+pub(super) unsafe extern "C" fn DataStore_addString(ds: DataStore, value: *mut c_char) {
+    add_string(ds, std::ptr::null_mut(), value)
+}
+
+pub(super) unsafe extern "C" fn DataStore_addNamedString(
+    ds: DataStore,
+    name: *mut c_char,
+    value: *mut c_char,
+) {
+    add_string(ds, name, value)
+}
+
+//======================================
+// Asynchronous tasks
+//======================================
+
+struct Task {
+    alive: bool,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// An event raised by an asynchronous task using `raiseAsyncEvent()`.
+pub(super) struct RaisedEvent {
+    pub task_id: mint,
+    pub name: String,
+    pub data: DataStore,
+}
+
+// Safety: The DataStore is owned by the event, and is not shared with any other thread.
+unsafe impl Send for RaisedEvent {}
+
+static NEXT_TASK_ID: AtomicI64 = AtomicI64::new(1);
+
+static TASKS: Lazy<Mutex<HashMap<mint, Task>>> = Lazy::new(Default::default);
+
+pub(super) static EVENTS: Lazy<Mutex<Vec<RaisedEvent>>> = Lazy::new(Default::default);
+
+fn new_task(thread: Option<thread::JoinHandle<()>>) -> mint {
+    let id = NEXT_TASK_ID.fetch_add(1, Ordering::SeqCst);
+
+    let task = Task {
+        alive: true,
+        thread,
+    };
+
+    TASKS.lock().unwrap().insert(id, task);
+
+    id
+}
+
+/// Wait for the background thread of the specified asynchronous task to finish.
+pub(super) fn join_task(id: mint) {
+    let thread = match TASKS.lock().unwrap().get_mut(&id) {
+        Some(task) => task.thread.take(),
+        None => None,
+    };
+
+    if let Some(thread) = thread {
+        let _ = thread.join();
+    }
+}
+
+pub(super) unsafe extern "C" fn createAsynchronousTaskWithoutThread() -> mint {
+    new_task(None)
+}
+
+pub(super) unsafe extern "C" fn createAsynchronousTaskWithThread(
+    runner: Option<unsafe extern "C" fn(mint, *mut c_void)>,
+    init_data: *mut c_void,
+) -> mint {
+    let runner = runner.expect("mock runtime: async task runner is NULL");
+
+    // Hold the lock until the task has been registered, so that the background thread
+    // can't finish before its task exists.
+    let mut tasks = TASKS.lock().unwrap();
+
+    let id = NEXT_TASK_ID.fetch_add(1, Ordering::SeqCst);
+
+    // Raw pointers are not `Send`.
+    let init_data = init_data as usize;
+
+    let thread = thread::spawn(move || {
+        runner(id, init_data as *mut c_void);
+
+        if let Some(task) = TASKS.lock().unwrap().get_mut(&id) {
+            task.alive = false;
+        }
+    });
+
+    tasks.insert(id, Task {
+        alive: true,
+        thread: Some(thread),
+    });
+
+    id
+}
+
+pub(super) unsafe extern "C" fn raiseAsyncEvent(
+    id: mint,
+    event_type: *mut c_char,
+    data: DataStore,
+) {
+    let name = CStr::from_ptr(event_type).to_string_lossy().into_owned();
+
+    EVENTS.lock().unwrap().push(RaisedEvent {
+        task_id: id,
+        name,
+        data,
+    });
+}
+
+pub(super) unsafe extern "C" fn asynchronousTaskAliveQ(id: mint) -> mbool {
+    let alive = TASKS
+        .lock()
+        .unwrap()
+        .get(&id)
+        .is_some_and(|task| task.alive);
+
+    mbool::from(alive)
+}
+
+pub(super) unsafe extern "C" fn asynchronousTaskStartedQ(id: mint) -> mbool {
+    mbool::from(TASKS.lock().unwrap().contains_key(&id))
+}
+
+pub(super) unsafe extern "C" fn removeAsynchronousTask(id: mint) -> mint {
+    match TASKS.lock().unwrap().get_mut(&id) {
+        Some(task) if task.alive => {
+            task.alive = false;
+            1
+        },
+        _ => 0,
+    }
+}
@@ -0,0 +1,422 @@
+//! Mock implementations of the remaining top-level `WolframLibraryData` functions.
+
+#![allow(non_snake_case)]
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use once_cell::sync::Lazy;
+
+use super::arrays;
+
+use crate::sys::{
+    self, mbool, mint, MArgument, MInputStream, MOutputStream, MTensor,
+    WolframLibraryData, WSENV, WSLINK,
+};
+
+//======================================
+// Strings
+//======================================
+
+/// Addresses of strings allocated by [`new_string()`] that have not yet been disowned.
+static STRINGS: Lazy<Mutex<HashSet<usize>>> = Lazy::new(Default::default);
+
+/// Allocate a string, as if it had been passed as an argument by the Kernel.
+pub(super) fn new_string(value: &str) -> *mut c_char {
+    let value = CString::new(value).expect("mock runtime: string contains NUL byte");
+
+    let raw = value.into_raw();
+
+    STRINGS.lock().unwrap().insert(raw as usize);
+
+    raw
+}
+
+/// Free a string allocated by [`new_string()`], if it has not already been disowned.
+pub(super) unsafe fn free_string(value: *mut c_char) {
+    if STRINGS.lock().unwrap().remove(&(value as usize)) {
+        drop(CString::from_raw(value));
+    }
+}
+
+pub(super) unsafe extern "C" fn UTF8String_disown(value: *mut c_char) {
+    // Strings that were not allocated by new_string() (e.g. strings returned by a library
+    // function) are not owned by the mock runtime, and are ignored.
+    free_string(value)
+}
+
+//======================================
+// Messages and aborts
+//======================================
+
+thread_local! {
+    pub(super) static MESSAGES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(super) static ABORT: AtomicBool = AtomicBool::new(false);
+
+pub(super) unsafe extern "C" fn Message(message: *const c_char) {
+    let message = CStr::from_ptr(message).to_string_lossy().into_owned();
+
+    MESSAGES.with(|messages| messages.borrow_mut().push(message));
+}
+
+pub(super) unsafe extern "C" fn AbortQ() -> mint {
+    mint::from(ABORT.load(Ordering::SeqCst))
+}
+
+//======================================
+// Evaluation
+//======================================
+
+thread_local! {
+    pub(super) static EVALUATIONS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records `expr`, and evaluates it if it is a numeric literal or a list of numeric
+/// literals. Any other code fails with `LIBRARY_FUNCTION_ERROR`.
+pub(super) unsafe extern "C" fn evaluateExpression(
+    _lib: WolframLibraryData,
+    expr: *mut c_char,
+    type_: c_int,
+    rank: mint,
+    res: *mut c_void,
+) -> c_int {
+    let code = CStr::from_ptr(expr).to_string_lossy().into_owned();
+
+    EVALUATIONS.with(|evaluations| evaluations.borrow_mut().push(code.clone()));
+
+    let code = code.trim();
+
+    let literals: Vec<&str> = match (rank, code.strip_prefix('{')) {
+        (0, None) => vec![code],
+        (1, Some(list)) => match list.strip_suffix('}') {
+            Some(list) if list.trim().is_empty() => Vec::new(),
+            Some(list) => list.split(',').map(str::trim).collect(),
+            None => return sys::LIBRARY_FUNCTION_ERROR as c_int,
+        },
+        _ => return sys::LIBRARY_FUNCTION_ERROR as c_int,
+    };
+
+    let elements: Option<Vec<arrays::Element>> = match type_ as u32 {
+        sys::MType_Integer => literals
+            .iter()
+            .map(|literal| literal.parse::<mint>().ok().map(|value| [value as u64, 0]))
+            .collect(),
+        sys::MType_Real => literals
+            .iter()
+            .map(|literal| {
+                literal
+                    .parse::<f64>()
+                    .ok()
+                    .map(|value| [value.to_bits(), 0])
+            })
+            .collect(),
+        _ => None,
+    };
+
+    let elements = match elements {
+        Some(elements) => elements,
+        None => return sys::LIBRARY_FUNCTION_ERROR as c_int,
+    };
+
+    if rank == 0 {
+        *(res as *mut u64) = elements[0][0];
+    } else {
+        let dims = vec![elements.len() as mint];
+
+        *(res as *mut MTensor) =
+            arrays::tensor_from_parts(mint::from(type_), dims, &elements);
+    }
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+//======================================
+// Library expression and callback managers
+//======================================
+
+type ManagerCallback = unsafe extern "C" fn(WolframLibraryData, mbool, mint);
+
+type CallbackManagerCallback =
+    unsafe extern "C" fn(WolframLibraryData, mint, MTensor) -> mbool;
+
+static MANAGERS: Lazy<Mutex<HashMap<CString, ManagerCallback>>> =
+    Lazy::new(Default::default);
+
+static CALLBACK_MANAGERS: Lazy<Mutex<HashMap<CString, CallbackManagerCallback>>> =
+    Lazy::new(Default::default);
+
+static NEXT_INSTANCE_ID: AtomicI64 = AtomicI64::new(1);
+
+fn manager(name: &CStr) -> Option<ManagerCallback> {
+    MANAGERS.lock().unwrap().get(name).copied()
+}
+
+/// Create a new instance of a managed library expression, as if the Kernel had
+/// evaluated `CreateManagedLibraryExpression[name, ...]`.
+pub(super) fn create_managed_instance(name: &str) -> Option<mint> {
+    let name = CString::new(name).ok()?;
+
+    let callback = manager(&name)?;
+
+    let id = NEXT_INSTANCE_ID.fetch_add(1, Ordering::SeqCst);
+
+    unsafe { callback(super::library_data(), 0, id) };
+
+    Some(id)
+}
+
+pub(super) unsafe extern "C" fn registerLibraryExpressionManager(
+    name: *const c_char,
+    callback: Option<ManagerCallback>,
+) -> c_int {
+    let name = CString::from(CStr::from_ptr(name));
+
+    let callback = match callback {
+        Some(callback) => callback,
+        None => return sys::LIBRARY_FUNCTION_ERROR as c_int,
+    };
+
+    let mut managers = MANAGERS.lock().unwrap();
+
+    if managers.contains_key(&name) {
+        return sys::LIBRARY_FUNCTION_ERROR as c_int;
+    }
+
+    managers.insert(name, callback);
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn unregisterLibraryExpressionManager(
+    name: *const c_char,
+) -> c_int {
+    match MANAGERS.lock().unwrap().remove(CStr::from_ptr(name)) {
+        Some(_) => sys::LIBRARY_NO_ERROR as c_int,
+        None => sys::LIBRARY_FUNCTION_ERROR as c_int,
+    }
+}
+
+pub(super) unsafe extern "C" fn releaseManagedLibraryExpression(
+    name: *const c_char,
+    id: mint,
+) -> c_int {
+    // Look up the callback before calling it, so that the MANAGERS lock is not held
+    // while the callback runs.
+    match manager(CStr::from_ptr(name)) {
+        Some(callback) => {
+            callback(super::library_data(), 1, id);
+            sys::LIBRARY_NO_ERROR as c_int
+        },
+        None => sys::LIBRARY_FUNCTION_ERROR as c_int,
+    }
+}
+
+pub(super) unsafe extern "C" fn registerLibraryCallbackManager(
+    name: *const c_char,
+    callback: Option<CallbackManagerCallback>,
+) -> c_int {
+    let name = CString::from(CStr::from_ptr(name));
+
+    let callback = match callback {
+        Some(callback) => callback,
+        None => return sys::LIBRARY_FUNCTION_ERROR as c_int,
+    };
+
+    let mut managers = CALLBACK_MANAGERS.lock().unwrap();
+
+    if managers.contains_key(&name) {
+        return sys::LIBRARY_FUNCTION_ERROR as c_int;
+    }
+
+    managers.insert(name, callback);
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn unregisterLibraryCallbackManager(
+    name: *const c_char,
+) -> c_int {
+    match CALLBACK_MANAGERS
+        .lock()
+        .unwrap()
+        .remove(CStr::from_ptr(name))
+    {
+        Some(_) => sys::LIBRARY_NO_ERROR as c_int,
+        None => sys::LIBRARY_FUNCTION_ERROR as c_int,
+    }
+}
+
+pub(super) type CallbackFunction = Arc<dyn Fn(&[MArgument], MArgument) + Send + Sync>;
+
+/// Functions connected to a callback manager by [`connect_callback_function()`].
+static CALLBACK_FUNCTIONS: Lazy<Mutex<HashMap<mint, CallbackFunction>>> =
+    Lazy::new(Default::default);
+
+static NEXT_CALLBACK_ID: AtomicI64 = AtomicI64::new(1);
+
+/// Connect `func` to the callback manager named `name`, as if the Kernel had evaluated
+/// `ConnectLibraryCallbackFunction[name, cf]`.
+pub(super) fn connect_callback_function(
+    name: &str,
+    types: &[(mint, mint)],
+    func: CallbackFunction,
+) -> Option<mint> {
+    let name = CString::new(name).ok()?;
+
+    let callback = *CALLBACK_MANAGERS.lock().unwrap().get(&name)?;
+
+    let id = NEXT_CALLBACK_ID.fetch_add(1, Ordering::SeqCst);
+
+    CALLBACK_FUNCTIONS.lock().unwrap().insert(id, func);
+
+    // The argument types are passed as an `n x 2` matrix of `{type, rank}` pairs.
+    let elements: Vec<arrays::Element> = types
+        .iter()
+        .flat_map(|&(type_, rank)| [[type_ as u64, 0], [rank as u64, 0]])
+        .collect();
+
+    let accepted = unsafe {
+        let arg_types = arrays::tensor_from_parts(
+            sys::MType_Integer as mint,
+            vec![types.len() as mint, 2],
+            &elements,
+        );
+
+        let accepted = callback(super::library_data(), id, arg_types);
+
+        arrays::MTensor_free(arg_types);
+
+        accepted != 0
+    };
+
+    if !accepted {
+        CALLBACK_FUNCTIONS.lock().unwrap().remove(&id);
+        return None;
+    }
+
+    Some(id)
+}
+
+pub(super) fn is_callback_function_connected(id: mint) -> bool {
+    CALLBACK_FUNCTIONS.lock().unwrap().contains_key(&id)
+}
+
+pub(super) unsafe extern "C" fn callLibraryCallbackFunction(
+    id: mint,
+    argc: mint,
+    args: *mut MArgument,
+    res: MArgument,
+) -> c_int {
+    // Clone the function before calling it, so that the CALLBACK_FUNCTIONS lock is not
+    // held while it runs.
+    let func = match CALLBACK_FUNCTIONS.lock().unwrap().get(&id) {
+        Some(func) => Arc::clone(func),
+        None => return sys::LIBRARY_FUNCTION_ERROR as c_int,
+    };
+
+    let args: &[MArgument] = match argc {
+        0 => &[],
+        _ => std::slice::from_raw_parts(args, argc as usize),
+    };
+
+    func(args, res);
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn releaseLibraryCallbackFunction(id: mint) -> c_int {
+    match CALLBACK_FUNCTIONS.lock().unwrap().remove(&id) {
+        Some(_) => sys::LIBRARY_NO_ERROR as c_int,
+        None => sys::LIBRARY_FUNCTION_ERROR as c_int,
+    }
+}
+
+//======================================
+// Miscellaneous
+//======================================
+
+pub(super) unsafe extern "C" fn validatePath(_path: *mut c_char, _type: c_char) -> mbool {
+    mbool::from(true)
+}
+
+pub(super) unsafe extern "C" fn protectedModeQ() -> mbool {
+    mbool::from(false)
+}
+
+pub(super) unsafe extern "C" fn setParallelThreadNumber(_count: c_int) -> c_int {
+    1
+}
+
+pub(super) unsafe extern "C" fn restoreParallelThreadNumber(_count: c_int) {}
+
+pub(super) unsafe extern "C" fn getParallelThreadNumber() -> c_int {
+    1
+}
+
+//======================================
+// Unsupported
+//======================================
+
+// Note: These functions are `extern "C"`, so these panics will abort the test process
+//       instead of unwinding.
+
+fn unsupported(name: &str) -> ! {
+    panic!(
+        "{}() is not supported by the wolfram_library_link::testing mock runtime",
+        name
+    )
+}
+
+pub(super) unsafe extern "C" fn getWSLINK(_lib: WolframLibraryData) -> WSLINK {
+    unsupported("getWSLINK")
+}
+
+pub(super) unsafe extern "C" fn processWSLINK(_link: WSLINK) -> c_int {
+    unsupported("processWSLINK")
+}
+
+pub(super) unsafe extern "C" fn getWSLINKEnvironment(_lib: WolframLibraryData) -> WSENV {
+    unsupported("getWSLINKEnvironment")
+}
+
+pub(super) unsafe extern "C" fn registerInputStreamMethod(
+    _name: *const c_char,
+    _ctor: Option<unsafe extern "C" fn(MInputStream, *const c_char, *mut c_void)>,
+    _handler_test: Option<unsafe extern "C" fn(*mut c_void, *mut c_char) -> mbool>,
+    _method_data: *mut c_void,
+    _destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+) -> mbool {
+    unsupported("registerInputStreamMethod")
+}
+
+pub(super) unsafe extern "C" fn unregisterInputStreamMethod(
+    _name: *const c_char,
+) -> mbool {
+    unsupported("unregisterInputStreamMethod")
+}
+
+pub(super) unsafe extern "C" fn registerOutputStreamMethod(
+    _name: *const c_char,
+    _ctor: Option<unsafe extern "C" fn(MOutputStream, *const c_char, *mut c_void, mbool)>,
+    _handler_test: Option<unsafe extern "C" fn(*mut c_void, *mut c_char) -> mbool>,
+    _method_data: *mut c_void,
+    _destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+) -> mbool {
+    unsupported("registerOutputStreamMethod")
+}
+
+pub(super) unsafe extern "C" fn unregisterOutputStreamMethod(
+    _name: *const c_char,
+) -> mbool {
+    unsupported("unregisterOutputStreamMethod")
+}
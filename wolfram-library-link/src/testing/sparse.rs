@@ -0,0 +1,461 @@
+//! Mock implementations of the `MSparseArray_*` functions.
+//!
+//! Sparse arrays are always stored in normalized form: explicit positions are sorted in
+//! row-major order, and elements equal to the implicit value are not stored explicitly.
+
+#![allow(non_snake_case)]
+
+use std::os::raw::c_int;
+
+use crate::sys::{self, mint, MSparseArray, MTensor};
+
+use super::{
+    alloc,
+    arrays::{self, tensor_from_parts, tensor_parts, Element},
+    object, release,
+};
+
+pub(super) struct MockSparseArray {
+    data_type: mint,
+    dims: Vec<mint>,
+    /// Rank 0 tensor.
+    implicit_value: MTensor,
+    /// `NULL` if there are no explicit values.
+    explicit_values: MTensor,
+    /// `NULL` if there are no explicit values.
+    row_pointers: MTensor,
+    /// `NULL` if there are no explicit values.
+    column_indices: MTensor,
+    share_count: mint,
+}
+
+/// An explicit element of a sparse array: its 1-based position and value.
+type Entry = (Vec<mint>, Element);
+
+impl MockSparseArray {
+    /// Construct a new sparse array from entries sorted by position.
+    unsafe fn new(
+        data_type: mint,
+        dims: Vec<mint>,
+        implicit_value: Element,
+        entries: Vec<Entry>,
+    ) -> MockSparseArray {
+        let implicit_value = tensor_from_parts(data_type, vec![], &[implicit_value]);
+
+        let mut sparse = MockSparseArray {
+            data_type,
+            dims,
+            implicit_value,
+            explicit_values: std::ptr::null_mut(),
+            row_pointers: std::ptr::null_mut(),
+            column_indices: std::ptr::null_mut(),
+            share_count: 0,
+        };
+
+        if entries.is_empty() {
+            return sparse;
+        }
+
+        let count = entries.len() as mint;
+
+        // Vectors are stored as a matrix with a single row.
+        let (rows, index_rank) = match *sparse.dims {
+            [_] => (1, 1),
+            [rows, ..] => (rows, sparse.dims.len() - 1),
+            [] => unreachable!(),
+        };
+
+        let mut row_pointers: Vec<Element> = vec![[0, 0]; rows as usize + 1];
+        let mut column_indices: Vec<Element> = Vec::new();
+
+        for (pos, _) in &entries {
+            let (row, indices) = match pos.len() {
+                1 => (1, &pos[..]),
+                _ => (pos[0], &pos[1..]),
+            };
+
+            row_pointers[row as usize][0] += 1;
+            column_indices.extend(indices.iter().map(|&index| [index as u64, 0]));
+        }
+
+        // Convert the per-row counts into cumulative offsets.
+        for row in 1..row_pointers.len() {
+            row_pointers[row][0] += row_pointers[row - 1][0];
+        }
+
+        let values: Vec<Element> = entries.iter().map(|(_, value)| *value).collect();
+
+        let integer = mint::from(sys::MType_Integer);
+
+        sparse.explicit_values = tensor_from_parts(data_type, vec![count], &values);
+        sparse.row_pointers = tensor_from_parts(integer, vec![rows + 1], &row_pointers);
+        sparse.column_indices =
+            tensor_from_parts(integer, vec![count, index_rank as mint], &column_indices);
+
+        sparse
+    }
+
+    unsafe fn implicit_element(&self) -> Element {
+        tensor_parts(self.implicit_value).2[0]
+    }
+
+    unsafe fn entries(&self) -> Vec<Entry> {
+        if self.explicit_values.is_null() {
+            return Vec::new();
+        }
+
+        let (_, _, values) = tensor_parts(self.explicit_values);
+        let (_, _, row_pointers) = tensor_parts(self.row_pointers);
+        let (_, _, column_indices) = tensor_parts(self.column_indices);
+
+        let index_rank = column_indices.len() / values.len();
+
+        let mut entries = Vec::with_capacity(values.len());
+
+        for row in 0..row_pointers.len() - 1 {
+            for index in row_pointers[row][0]..row_pointers[row + 1][0] {
+                let index = index as usize;
+
+                let mut pos: Vec<mint> = Vec::with_capacity(self.dims.len());
+
+                if self.dims.len() > 1 {
+                    pos.push(row as mint + 1);
+                }
+
+                pos.extend(
+                    column_indices[index * index_rank..(index + 1) * index_rank]
+                        .iter()
+                        .map(|element| element[0] as mint),
+                );
+
+                entries.push((pos, values[index]));
+            }
+        }
+
+        entries
+    }
+
+    unsafe fn to_dense(&self) -> Vec<Element> {
+        let len = self.dims.iter().product::<mint>() as usize;
+
+        let mut elements = vec![self.implicit_element(); len];
+
+        for (pos, value) in self.entries() {
+            elements[flat_index(&self.dims, &pos)] = value;
+        }
+
+        elements
+    }
+}
+
+impl Drop for MockSparseArray {
+    fn drop(&mut self) {
+        let tensors = [
+            self.implicit_value,
+            self.explicit_values,
+            self.row_pointers,
+            self.column_indices,
+        ];
+
+        for tensor in tensors {
+            if !tensor.is_null() {
+                unsafe { arrays::MTensor_free(tensor) }
+            }
+        }
+    }
+}
+
+fn flat_index(dims: &[mint], pos: &[mint]) -> usize {
+    pos.iter()
+        .zip(dims)
+        .fold(0, |index, (&p, &dim)| index * dim + (p - 1)) as usize
+}
+
+/// Get the 1-based position of the element at `index` in a dense array.
+fn position(dims: &[mint], mut index: mint) -> Vec<mint> {
+    let mut pos = vec![0; dims.len()];
+
+    for (p, &dim) in pos.iter_mut().zip(dims).rev() {
+        *p = index % dim + 1;
+        index /= dim;
+    }
+
+    pos
+}
+
+unsafe fn sparse<'a>(raw: MSparseArray) -> &'a mut MockSparseArray {
+    object(raw as *mut MockSparseArray)
+}
+
+/// Get the implicit value stored in the rank 0 tensor `implicit_value`, or zero if
+/// `implicit_value` is `NULL`.
+unsafe fn implicit_element(implicit_value: MTensor, data_type: mint) -> Option<Element> {
+    if implicit_value.is_null() {
+        return Some([0, 0]);
+    }
+
+    match tensor_parts(implicit_value) {
+        (type_, dims, elements) if type_ == data_type && dims.is_empty() => {
+            Some(elements[0])
+        },
+        _ => None,
+    }
+}
+
+/// Increment the share count of a sparse array.
+///
+/// This simulates the Kernel passing `raw` using the `"Shared"` memory management
+/// strategy.
+pub(super) unsafe fn share(raw: MSparseArray) {
+    sparse(raw).share_count += 1;
+}
+
+//======================================
+// MSparseArray_*
+//======================================
+
+pub(super) unsafe extern "C" fn MSparseArray_clone(
+    raw: MSparseArray,
+    res: *mut MSparseArray,
+) -> c_int {
+    let sparse = sparse(raw);
+
+    let copy = MockSparseArray::new(
+        sparse.data_type,
+        sparse.dims.clone(),
+        sparse.implicit_element(),
+        sparse.entries(),
+    );
+
+    *res = alloc(copy) as MSparseArray;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_free(raw: MSparseArray) {
+    let share_count = sparse(raw).share_count;
+
+    assert_eq!(
+        share_count, 0,
+        "mock runtime: attempted to free a shared SparseArray (share count: {})",
+        share_count
+    );
+
+    drop(release(raw as *mut MockSparseArray));
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_disown(raw: MSparseArray) {
+    let sparse = sparse(raw);
+
+    assert!(
+        sparse.share_count > 0,
+        "mock runtime: attempted to disown a SparseArray that is not shared"
+    );
+
+    sparse.share_count -= 1;
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_disownAll(raw: MSparseArray) {
+    sparse(raw).share_count = 0;
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_shareCount(raw: MSparseArray) -> mint {
+    sparse(raw).share_count
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getRank(raw: MSparseArray) -> mint {
+    sparse(raw).dims.len() as mint
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getDimensions(
+    raw: MSparseArray,
+) -> *const mint {
+    sparse(raw).dims.as_ptr()
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getImplicitValue(
+    raw: MSparseArray,
+) -> *mut MTensor {
+    &mut sparse(raw).implicit_value
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getExplicitValues(
+    raw: MSparseArray,
+) -> *mut MTensor {
+    &mut sparse(raw).explicit_values
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getRowPointers(
+    raw: MSparseArray,
+) -> *mut MTensor {
+    &mut sparse(raw).row_pointers
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getColumnIndices(
+    raw: MSparseArray,
+) -> *mut MTensor {
+    &mut sparse(raw).column_indices
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_getExplicitPositions(
+    raw: MSparseArray,
+    res: *mut MTensor,
+) -> c_int {
+    let sparse = sparse(raw);
+
+    let entries = sparse.entries();
+
+    let positions: Vec<Element> = entries
+        .iter()
+        .flat_map(|(pos, _)| pos.iter().map(|&p| [p as u64, 0]))
+        .collect();
+
+    *res = tensor_from_parts(
+        mint::from(sys::MType_Integer),
+        vec![entries.len() as mint, sparse.dims.len() as mint],
+        &positions,
+    );
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_resetImplicitValue(
+    raw: MSparseArray,
+    implicit_value: MTensor,
+    res: *mut MSparseArray,
+) -> c_int {
+    let sparse = sparse(raw);
+
+    let implicit_value = match implicit_element(implicit_value, sparse.data_type) {
+        Some(value) => value,
+        None => return sys::LIBRARY_TYPE_ERROR as c_int,
+    };
+
+    let entries: Vec<Entry> = sparse
+        .to_dense()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, value)| *value != implicit_value)
+        .map(|(index, value)| (position(&sparse.dims, index as mint), value))
+        .collect();
+
+    let new = MockSparseArray::new(
+        sparse.data_type,
+        sparse.dims.clone(),
+        implicit_value,
+        entries,
+    );
+
+    *res = alloc(new) as MSparseArray;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_toMTensor(
+    raw: MSparseArray,
+    res: *mut MTensor,
+) -> c_int {
+    let sparse = sparse(raw);
+
+    *res = tensor_from_parts(sparse.data_type, sparse.dims.clone(), &sparse.to_dense());
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_fromMTensor(
+    tensor: MTensor,
+    implicit_value: MTensor,
+    res: *mut MSparseArray,
+) -> c_int {
+    let (data_type, dims, elements) = tensor_parts(tensor);
+
+    if dims.is_empty() {
+        return sys::LIBRARY_RANK_ERROR as c_int;
+    }
+
+    let implicit_value = match implicit_element(implicit_value, data_type) {
+        Some(value) => value,
+        None => return sys::LIBRARY_TYPE_ERROR as c_int,
+    };
+
+    let entries: Vec<Entry> = elements
+        .into_iter()
+        .enumerate()
+        .filter(|(_, value)| *value != implicit_value)
+        .map(|(index, value)| (position(&dims, index as mint), value))
+        .collect();
+
+    let sparse = MockSparseArray::new(data_type, dims, implicit_value, entries);
+
+    *res = alloc(sparse) as MSparseArray;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
+
+pub(super) unsafe extern "C" fn MSparseArray_fromExplicitPositions(
+    positions: MTensor,
+    values: MTensor,
+    dims: MTensor,
+    implicit_value: MTensor,
+    res: *mut MSparseArray,
+) -> c_int {
+    let integer = mint::from(sys::MType_Integer);
+
+    let (positions_type, positions_dims, positions) = tensor_parts(positions);
+    let (data_type, _, values) = tensor_parts(values);
+    let (dims_type, _, dims) = tensor_parts(dims);
+
+    if positions_type != integer || dims_type != integer {
+        return sys::LIBRARY_TYPE_ERROR as c_int;
+    }
+
+    let dims: Vec<mint> = dims.iter().map(|element| element[0] as mint).collect();
+
+    let rank = dims.len();
+
+    if rank == 0 || dims.iter().any(|&dim| dim < 0) {
+        return sys::LIBRARY_DIMENSION_ERROR as c_int;
+    }
+
+    let count = values.len();
+
+    if positions_dims != [count as mint, rank as mint] {
+        return sys::LIBRARY_DIMENSION_ERROR as c_int;
+    }
+
+    let implicit_value = match implicit_element(implicit_value, data_type) {
+        Some(value) => value,
+        None => return sys::LIBRARY_TYPE_ERROR as c_int,
+    };
+
+    let mut entries: Vec<Entry> = Vec::with_capacity(count);
+
+    for (index, value) in values.into_iter().enumerate() {
+        let pos: Vec<mint> = positions[index * rank..(index + 1) * rank]
+            .iter()
+            .map(|element| element[0] as mint)
+            .collect();
+
+        if pos
+            .iter()
+            .zip(&dims)
+            .any(|(&p, &dim)| !(1..=dim).contains(&p))
+        {
+            return sys::LIBRARY_DIMENSION_ERROR as c_int;
+        }
+
+        entries.push((pos, value));
+    }
+
+    // Sort into row-major order. If a position is specified more than once, the first
+    // value wins, matching the behavior of `SparseArray[{pos -> value, ...}]`.
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries.dedup_by(|(a, _), (b, _)| a == b);
+    entries.retain(|(_, value)| *value != implicit_value);
+
+    let sparse = MockSparseArray::new(data_type, dims, implicit_value, entries);
+
+    *res = alloc(sparse) as MSparseArray;
+
+    sys::LIBRARY_NO_ERROR as c_int
+}
@@ -0,0 +1,34 @@
+//! Tests of behavior that depends on being called from the main Kernel thread.
+//!
+//! The main Kernel thread of the mock runtime is the thread that first calls
+//! `testing::initialize()`, so this file must contain only one `#[test]` function.
+//!
+//! Run these tests using:
+//!
+//! ```shell
+//! $ cargo test --features testing --test mock_main_thread
+//! ```
+
+use wolfram_library_link::{testing, AsyncTaskObject, KernelThread, MainThreadExecutor};
+
+#[test]
+fn test_main_thread() {
+    testing::initialize();
+
+    assert!(KernelThread::current().is_some());
+
+    //
+    // MainThreadExecutor::run() runs the closure immediately on the main thread.
+    //
+
+    let task = AsyncTaskObject::new_without_thread();
+    let executor = MainThreadExecutor::new(&task);
+
+    assert_eq!(executor.run(|_| 42), Some(42));
+    assert_eq!(executor.run(|_| -> i64 { panic!("job panicked") }), None);
+
+    // No job was submitted.
+    assert!(testing::take_async_events(&task).is_empty());
+
+    assert!(task.remove());
+}
@@ -0,0 +1,1342 @@
+//! Tests for the `wolfram_library_link::testing` mock Wolfram runtime.
+//!
+//! Run these tests using:
+//!
+//! ```shell
+//! $ cargo test --features testing --test mock_runtime
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use wolfram_library_link::{
+    self as wll,
+    callback::{self, CallbackConnection, CallbackFunction},
+    managed::{self, Managed, ManagedExpressionEvent, ManagedStore},
+    sys,
+    testing::{self, Arg, Passing},
+    AsyncTaskObject, BatchOptions, CancellationToken, ColorSpace, DataStore,
+    DataStoreNodeValue, EventSender, Image, KernelThread, MainThreadExecutor, Manual,
+    NativeFunction, NumericArray, Pixel, Shared, Tensor, ToExpr, UninitImage,
+};
+
+//======================================
+// Basic types
+//======================================
+
+#[wll::export]
+fn square(x: i64) -> i64 {
+    x * x
+}
+
+#[wll::export]
+fn reverse_string(string: String) -> String {
+    string.chars().rev().collect()
+}
+
+#[wll::export]
+fn always_panics() -> i64 {
+    panic!("this function always panics")
+}
+
+#[wll::export]
+fn checked_half(x: i64) -> Result<i64, String> {
+    if x % 2 != 0 {
+        return Err(format!("{} is odd", x));
+    }
+
+    Ok(x / 2)
+}
+
+#[wll::export]
+fn add_evaluated(kernel: &KernelThread, x: i64) -> i64 {
+    let list: Tensor<i64> = kernel.evaluate_str_as_tensor("{1, 2, 3}", 1).unwrap();
+
+    x + list.as_slice().iter().sum::<i64>()
+}
+
+#[test]
+fn test_integer() {
+    let result = unsafe { testing::call(square::square, &[Arg::integer(4)]) }.unwrap();
+
+    assert_eq!(unsafe { result.get::<i64>() }, 16);
+}
+
+#[test]
+fn test_string() {
+    let result =
+        unsafe { testing::call(reverse_string::reverse_string, &[Arg::string("hello")]) }
+            .unwrap();
+
+    assert_eq!(unsafe { result.get::<String>() }, "olleh");
+}
+
+#[test]
+fn test_kernel_thread_parameter() {
+    let result =
+        unsafe { testing::call(add_evaluated::add_evaluated, &[Arg::integer(2)]) };
+
+    assert_eq!(unsafe { result.unwrap().get::<i64>() }, 8);
+    assert_eq!(testing::take_evaluations(), vec!["{1, 2, 3}".to_owned()]);
+
+    // The `&KernelThread` parameter is not passed by the Kernel.
+    let func: fn(_, _) -> _ = add_evaluated;
+    let (params, _) = func.signature().unwrap();
+
+    assert_eq!(params.len(), 1);
+    assert_eq!(params[0].to_string(), "System`Integer");
+}
+
+#[test]
+fn test_panic_is_error() {
+    let result = unsafe { testing::call(always_panics::always_panics, &[]) };
+
+    assert_eq!(result.err(), Some(1002));
+
+    // The panic message is issued as a LibraryFunction::rustpanic message.
+    let evaluations = testing::take_evaluations();
+
+    assert!(
+        evaluations
+            .iter()
+            .any(|code| code.contains(r#""rustpanic""#)
+                && code.contains("this function always panics")),
+        "{:?}",
+        evaluations
+    );
+
+    let failure = wll::last_panic()
+        .expect("panic was not recorded")
+        .to_string();
+
+    assert!(
+        failure.contains("this function always panics"),
+        "{}",
+        failure
+    );
+}
+
+#[test]
+fn test_result() {
+    let result = unsafe { testing::call(checked_half::checked_half, &[Arg::integer(8)]) };
+
+    assert_eq!(unsafe { result.unwrap().get::<i64>() }, 4);
+    assert!(testing::take_evaluations().is_empty());
+
+    let result = unsafe { testing::call(checked_half::checked_half, &[Arg::integer(3)]) };
+
+    assert_eq!(result.err(), Some(wll::sys::LIBRARY_FUNCTION_ERROR as i32));
+
+    // The error text is issued as an argument of LibraryFunction::rusterr.
+    let evaluations = testing::take_evaluations();
+
+    assert_eq!(evaluations.len(), 1);
+    assert!(
+        evaluations[0].contains(r#"System`Message[System`MessageName["#)
+            && evaluations[0].contains(r#""rusterr"], "3 is odd"]"#),
+        "{}",
+        evaluations[0]
+    );
+}
+
+#[test]
+fn test_typed_wstp_argument_failures() {
+    // Used by the wrapper generated by #[export(wstp)] for typed parameters.
+    use wll::macro_utils::{check_wstp_arg_count, wstp_arg};
+
+    let args = vec![wll::expr::Expr::from(1), wll::expr::Expr::string("two")];
+
+    assert!(check_wstp_arg_count(&args, 2).is_ok());
+    assert_eq!(
+        check_wstp_arg_count(&args, 3).unwrap_err().to_string(),
+        r#"System`Failure["ArgumentCount", System`Association[System`Rule["MessageTemplate", "Expected `expected` argument(s), got `actual`."], System`Rule["MessageParameters", System`Association[System`Rule["expected", 3], System`Rule["actual", 2]]]]]"#
+    );
+
+    assert_eq!(wstp_arg::<i64>(&args, 0).unwrap(), 1);
+
+    let failure = wstp_arg::<i64>(&args, 1).unwrap_err().to_string();
+
+    assert!(
+        failure.starts_with(r#"System`Failure["ArgumentConversion", "#)
+            && failure.contains(r#"System`Rule["position", 2]"#),
+        "{}",
+        failure
+    );
+}
+
+//======================================
+// Arrays
+//======================================
+
+#[wll::export]
+fn na_constant_count(array: &NumericArray<i64>) -> i64 {
+    array.share_count() as i64
+}
+
+#[wll::export]
+fn na_shared_count(array: Shared<NumericArray<i64>>) -> i64 {
+    array.share_count() as i64
+}
+
+#[wll::export]
+fn na_manual_count(array: Manual<NumericArray<i64>>) -> i64 {
+    array.share_count() as i64
+}
+
+#[wll::export]
+fn na_double_in_place(mut array: Shared<NumericArray<f64>>) {
+    for elem in array.as_slice_mut().unwrap() {
+        *elem *= 2.0;
+    }
+}
+
+#[wll::export]
+fn tensor_total(tensor: &Tensor<f64>) -> f64 {
+    tensor.as_slice().iter().sum()
+}
+
+#[wll::export]
+fn tensor_reverse(tensor: &Tensor<i64>) -> Tensor<i64> {
+    let mut data = tensor.as_slice().to_vec();
+    data.reverse();
+    Tensor::from_array(tensor.dimensions(), &data)
+}
+
+#[test]
+fn test_numeric_array_share_counts() {
+    let array = NumericArray::<i64>::from_slice(&[1, 2, 3]);
+
+    let cases = [
+        (
+            na_constant_count::na_constant_count as testing::LibraryFunction,
+            Passing::Constant,
+            0,
+        ),
+        (na_shared_count::na_shared_count, Passing::Shared, 1),
+        (na_manual_count::na_manual_count, Passing::Manual, 0),
+    ];
+
+    for (function, passing, expected) in cases {
+        let result =
+            unsafe { testing::call(function, &[Arg::numeric_array(&array, passing)]) };
+
+        assert_eq!(unsafe { result.unwrap().get::<i64>() }, expected);
+    }
+
+    // Every share acquired by the calls above was released.
+    assert_eq!(array.share_count(), 0);
+}
+
+#[test]
+fn test_numeric_array_shared_mutation() {
+    let array = NumericArray::<f64>::from_slice(&[1.0, 2.5, -3.0]);
+
+    unsafe {
+        testing::call(na_double_in_place::na_double_in_place, &[
+            Arg::numeric_array(&array, Passing::Shared),
+        ])
+    }
+    .unwrap();
+
+    assert_eq!(array.as_slice(), &[2.0, 5.0, -6.0]);
+}
+
+#[test]
+fn test_tensors() {
+    let tensor = Tensor::<f64>::from_slice(&[1.0, 2.0, 3.5]);
+
+    let result = unsafe {
+        testing::call(tensor_total::tensor_total, &[Arg::tensor(
+            &tensor,
+            Passing::Constant,
+        )])
+    };
+
+    assert_eq!(unsafe { result.unwrap().get::<f64>() }, 6.5);
+
+    let tensor = Tensor::<i64>::from_array(&[2, 2], &[1, 2, 3, 4]);
+
+    let result = unsafe {
+        testing::call(tensor_reverse::tensor_reverse, &[Arg::tensor(
+            &tensor,
+            Passing::Constant,
+        )])
+    }
+    .unwrap();
+
+    let reversed: Tensor<i64> = unsafe { result.get() };
+
+    assert_eq!(reversed.dimensions(), &[2, 2]);
+    assert_eq!(reversed.as_slice(), &[4, 3, 2, 1]);
+}
+
+//======================================
+// Images
+//======================================
+
+#[wll::export]
+fn image_pixel(image: &Image<u8>, row: i64, column: i64, channel: i64) -> i64 {
+    let pixel = Pixel::D2([row as usize, column as usize]);
+
+    i64::from(image.get(pixel, channel as usize).unwrap())
+}
+
+#[test]
+fn test_images() {
+    let mut image = UninitImage::<u8>::new_2d(2, 2, 3, ColorSpace::RGB, false);
+    image.zero();
+    image.set(Pixel::D2([1, 2]), 2, 200);
+    image.set(Pixel::D2([2, 1]), 3, 100);
+    let image = unsafe { image.assume_init() };
+
+    assert_eq!(image.channels(), 3);
+    assert_eq!(image.flattened_length(), 12);
+    assert!(!image.has_alpha_channel());
+
+    for (row, column, channel, expected) in [(1, 2, 2, 200), (2, 1, 3, 100), (1, 1, 1, 0)]
+    {
+        let result = unsafe {
+            testing::call(image_pixel::image_pixel, &[
+                Arg::image(&image, Passing::Constant),
+                Arg::integer(row),
+                Arg::integer(column),
+                Arg::integer(channel),
+            ])
+        };
+
+        assert_eq!(unsafe { result.unwrap().get::<i64>() }, expected);
+    }
+}
+
+//======================================
+// DataStore
+//======================================
+
+#[wll::export]
+fn data_store_summary(data: DataStore) -> DataStore {
+    let mut summary = DataStore::new();
+    summary.add_named_i64("length", data.len() as i64);
+    summary.add_data_store(data);
+    summary
+}
+
+#[test]
+fn test_data_store() {
+    let mut data = DataStore::new();
+    data.add_named_str("name", "value");
+    data.add_f64(1.5);
+    data.add_numeric_array(NumericArray::<i64>::from_slice(&[1, 2]).into_generic());
+
+    let result = unsafe {
+        testing::call(data_store_summary::data_store_summary, &[Arg::data_store(
+            &data,
+        )])
+    }
+    .unwrap();
+
+    let summary: DataStore = unsafe { result.get() };
+
+    let nodes: Vec<_> = summary.nodes().collect();
+    assert_eq!(nodes.len(), 2);
+
+    assert_eq!(nodes[0].name().as_deref(), Some("length"));
+    assert!(matches!(nodes[0].value(), DataStoreNodeValue::Integer(3)));
+
+    let copy = match nodes[1].value() {
+        DataStoreNodeValue::DataStore(copy) => copy,
+        other => panic!("unexpected node value: {:?}", other),
+    };
+
+    let copied: Vec<_> = copy.nodes().collect();
+    assert_eq!(copied[0].name().as_deref(), Some("name"));
+    assert!(matches!(
+        copied[0].value(),
+        DataStoreNodeValue::Str("value")
+    ));
+    assert!(matches!(copied[1].value(), DataStoreNodeValue::Real(x) if x == 1.5));
+    match copied[2].value() {
+        DataStoreNodeValue::NumericArray(array) => {
+            assert_eq!(array.try_kind::<i64>().unwrap().as_slice(), &[1, 2])
+        },
+        other => panic!("unexpected node value: {:?}", other),
+    }
+
+    // The argument was passed as a copy, so the original is unchanged.
+    assert_eq!(data.len(), 3);
+}
+
+//======================================
+// Messages and async tasks
+//======================================
+
+#[test]
+fn test_messages() {
+    testing::initialize();
+
+    unsafe { wll::rtl::Message(c"mock-message".as_ptr()) };
+
+    assert_eq!(testing::take_messages(), vec!["mock-message".to_owned()]);
+    assert!(testing::take_messages().is_empty());
+}
+
+static LOADED: wll::MessageName = wll::MessageName::new("MockTests`Messages::loaded")
+    .with_template("Defined when the library is loaded.");
+
+wll::register_messages!(LOADED);
+
+#[test]
+fn test_registered_messages() {
+    testing::initialize();
+
+    let _ = testing::take_evaluations();
+
+    // Called by the loader function generated by generate_loader!.
+    unsafe { wll::macro_utils::define_registered_messages() };
+
+    let evaluations = testing::take_evaluations();
+
+    assert!(
+        evaluations.iter().any(|code| code
+            .contains(r#"System`Symbol["MockTests`Messages"]"#)
+            && code.contains(r#""Defined when the library is loaded.""#)),
+        "{:?}",
+        evaluations
+    );
+
+    // The message issued for an `Err(..)` returned from a native function.
+    assert!(
+        evaluations
+            .iter()
+            .any(|code| code.contains(r#"System`Symbol["LibraryFunction"]"#)
+                && code.contains(r#""rusterr""#)),
+        "{:?}",
+        evaluations
+    );
+}
+
+static REAL_ARG: wll::MessageName = wll::MessageName::new("MockTests`Messages::real");
+
+#[wll::export]
+fn issue_real(kernel: &KernelThread, x: f64) {
+    REAL_ARG.issue(kernel, &[x.to_expr()]);
+}
+
+#[test]
+fn test_message_real_arguments() {
+    let cases = [
+        (1.5e-10, "1.5*^-10"),
+        (f64::INFINITY, "System`DirectedInfinity[1]"),
+        (f64::NEG_INFINITY, "System`DirectedInfinity[-1]"),
+        // `ToExpr` converts NaN to `Indeterminate`.
+        (f64::NAN, "System`Indeterminate"),
+    ];
+
+    for (x, expected) in cases {
+        let result = unsafe { testing::call(issue_real::issue_real, &[Arg::real(x)]) };
+
+        assert!(result.is_ok());
+
+        let evaluations = testing::take_evaluations();
+
+        assert_eq!(evaluations.len(), 1);
+        assert!(
+            evaluations[0].contains(&format!(r#""real"], {}]"#, expected)),
+            "{:?}",
+            evaluations
+        );
+    }
+}
+
+#[test]
+fn test_message_try_issue() {
+    testing::initialize();
+
+    // Not issued from a thread other than the main Kernel thread.
+    let (result, evaluations) = std::thread::spawn(|| {
+        let result = REAL_ARG.try_issue(&[1.to_expr()]);
+
+        (result, testing::take_evaluations())
+    })
+    .join()
+    .unwrap();
+
+    let error = result.unwrap_err();
+
+    assert!(
+        error.contains("not called from the main Kernel thread"),
+        "{}",
+        error
+    );
+    assert!(evaluations.is_empty());
+}
+
+#[wll::export(abortable)]
+fn abortable_identity(kernel: &KernelThread, x: i64) -> Result<i64, wll::AbortError> {
+    kernel.check_abort()?;
+
+    Ok(x)
+}
+
+#[test]
+fn test_abortable() {
+    testing::initialize();
+
+    let result = unsafe {
+        testing::call(abortable_identity::abortable_identity, &[Arg::integer(3)])
+    };
+
+    assert_eq!(unsafe { result.unwrap().get::<i64>() }, 3);
+
+    testing::set_aborted(true);
+
+    let result = unsafe {
+        testing::call(abortable_identity::abortable_identity, &[Arg::integer(3)])
+    };
+
+    testing::set_aborted(false);
+
+    // The abort is not issued as a message.
+    assert_eq!(result.err(), Some(wll::sys::LIBRARY_FUNCTION_ERROR as i32));
+    assert!(testing::take_evaluations().is_empty());
+}
+
+#[test]
+fn test_async_task_events() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::spawn_with_thread(|task: AsyncTaskObject| {
+        for i in 0..3 {
+            let mut data = DataStore::new();
+            data.add_i64(i);
+            task.raise_async_event("tick", data);
+        }
+    });
+
+    testing::wait_for_async_task(&task);
+
+    assert!(!task.is_alive());
+
+    let events = testing::take_async_events(&task);
+
+    assert_eq!(events.len(), 3);
+
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!(event.name, "tick");
+
+        let node = event.data.nodes().next().unwrap();
+        assert!(matches!(node.value(), DataStoreNodeValue::Integer(x) if x == i as i64));
+    }
+}
+
+#[test]
+fn test_async_task_panic_event() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::spawn_with_thread(|task: AsyncTaskObject| {
+        task.raise_async_event("started", DataStore::new());
+
+        panic!("background task failed");
+    });
+
+    testing::wait_for_async_task(&task);
+
+    let events = testing::take_async_events(&task);
+    let names: Vec<&str> = events.iter().map(|event| event.name.as_str()).collect();
+
+    assert_eq!(names, vec!["started", "RustPanic"]);
+
+    let nodes: Vec<_> = events[1].data.nodes().collect();
+
+    assert_eq!(nodes[0].name().as_deref(), Some("message"));
+    assert!(matches!(
+        nodes[0].value(),
+        DataStoreNodeValue::Str("background task failed")
+    ));
+
+    assert_eq!(nodes[1].name().as_deref(), Some("location"));
+    assert!(matches!(
+        nodes[1].value(),
+        DataStoreNodeValue::Str(location) if location.contains("mock_runtime.rs")
+    ));
+}
+
+#[test]
+fn test_async_task_cancellation() {
+    testing::initialize();
+
+    // Used to check that the task closure is dropped when the task ends.
+    let dropped = Arc::new(());
+    let captured = Arc::clone(&dropped);
+
+    let task = AsyncTaskObject::spawn_with_thread(move |task: AsyncTaskObject| {
+        let _captured = &captured;
+        let token = task.cancellation_token();
+
+        let (sender, receiver) = std::sync::mpsc::channel::<i64>();
+        sender.send(1).unwrap();
+
+        assert_eq!(token.recv_or_cancelled(&receiver), Some(1));
+
+        while !token.sleep_or_cancelled(Duration::from_secs(60)) {
+            task.raise_async_event("tick", DataStore::new());
+        }
+
+        assert!(token.is_cancelled());
+        assert_eq!(token.recv_or_cancelled(&receiver), None);
+    });
+
+    assert!(!task.cancellation_token().is_cancelled());
+
+    // Simulate a call to TaskRemove[..].
+    assert_eq!(unsafe { wll::rtl::removeAsynchronousTask(task.id()) }, 1);
+
+    testing::wait_for_async_task(&task);
+
+    assert!(task.cancellation_token().is_cancelled());
+    assert_eq!(Arc::strong_count(&dropped), 1);
+}
+
+#[test]
+fn test_spawn_cancellable() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::spawn_cancellable(
+        |task: AsyncTaskObject, token: CancellationToken| {
+            // Only returns once the token of this task is cancelled.
+            while !token.sleep_or_cancelled(Duration::from_secs(60)) {
+                task.raise_async_event("tick", DataStore::new());
+            }
+        },
+    );
+
+    // Simulate a call to TaskRemove[..].
+    assert_eq!(unsafe { wll::rtl::removeAsynchronousTask(task.id()) }, 1);
+
+    testing::wait_for_async_task(&task);
+}
+
+#[test]
+fn test_periodic_task() {
+    testing::initialize();
+
+    let count = Arc::new(Mutex::new(0usize));
+
+    let task = AsyncTaskObject::spawn_periodic(Duration::from_millis(5), {
+        let count = Arc::clone(&count);
+
+        move || {
+            let mut count = count.lock().unwrap();
+            *count += 1;
+
+            // Only raise an event for every other call.
+            if count.is_multiple_of(2) {
+                Some(("tick".to_owned(), DataStore::new()))
+            } else {
+                None
+            }
+        }
+    });
+
+    while *count.lock().unwrap() < 4 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    assert!(AsyncTaskObject::pause_periodic(task.id()));
+
+    // Wait for any call that was in progress when the task was paused.
+    std::thread::sleep(Duration::from_millis(20));
+    let paused_count = *count.lock().unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(*count.lock().unwrap(), paused_count);
+
+    assert!(AsyncTaskObject::resume_periodic(task.id()));
+
+    while *count.lock().unwrap() < paused_count + 2 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    // Simulate a call to TaskRemove[..].
+    unsafe { wll::rtl::removeAsynchronousTask(task.id()) };
+    testing::wait_for_async_task(&task);
+
+    let final_count = *count.lock().unwrap();
+    let events = testing::take_async_events(&task);
+
+    assert_eq!(events.len(), final_count / 2);
+    assert!(events.iter().all(|event| event.name == "tick"));
+
+    // The task is unregistered once it has stopped.
+    assert!(!AsyncTaskObject::pause_periodic(task.id()));
+}
+
+#[test]
+fn test_event_sender() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::new_without_thread();
+
+    // Batches are limited by size.
+    let sender = EventSender::<f64>::new(&task, "values", BatchOptions {
+        max_items: 4,
+        max_delay: Duration::from_secs(60),
+        capacity: 2,
+    });
+
+    for i in 0..10 {
+        sender.send(f64::from(i)).unwrap();
+    }
+
+    // Dropping the sender raises an event for the remaining items.
+    drop(sender);
+
+    let batches: Vec<Vec<f64>> = testing::take_async_events(&task)
+        .iter()
+        .map(|event| {
+            assert_eq!(event.name, "values");
+
+            match event.data.nodes().next().unwrap().value() {
+                DataStoreNodeValue::NumericArray(array) => {
+                    array.try_kind::<f64>().unwrap().as_slice().to_vec()
+                },
+                _ => panic!("expected NumericArray event data"),
+            }
+        })
+        .collect();
+
+    assert_eq!(batches, vec![
+        vec![0.0, 1.0, 2.0, 3.0],
+        vec![4.0, 5.0, 6.0, 7.0],
+        vec![8.0, 9.0],
+    ]);
+
+    // Batches are limited by time.
+    let sender = EventSender::<String>::new(&task, "lines", BatchOptions {
+        max_items: 100,
+        max_delay: Duration::from_millis(10),
+        ..BatchOptions::default()
+    });
+
+    sender.send("first".to_owned()).unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    let events = testing::take_async_events(&task);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].data.len(), 1);
+
+    // Items are not sent after the task is removed.
+    assert_eq!(unsafe { wll::rtl::removeAsynchronousTask(task.id()) }, 1);
+
+    sender.send("second".to_owned()).unwrap();
+    drop(sender);
+
+    assert!(testing::take_async_events(&task).is_empty());
+}
+
+wll::export_main_thread_executor!();
+
+// The function exported by export_main_thread_executor!() is not nameable from Rust
+// code, so link to it by its exported symbol name instead.
+extern "C" {
+    fn wll_run_main_thread_job(
+        lib: sys::WolframLibraryData,
+        argc: sys::mint,
+        args: *mut sys::MArgument,
+        res: sys::MArgument,
+    ) -> std::os::raw::c_int;
+}
+
+#[test]
+fn test_main_thread_executor() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::new_without_thread();
+    let executor = MainThreadExecutor::new(&task);
+
+    let worker = std::thread::spawn(move || executor.run(|_| 42));
+
+    // Wait for the worker to submit its job.
+    let events = loop {
+        let events = testing::take_async_events(&task);
+
+        if !events.is_empty() {
+            break events;
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    };
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name, MainThreadExecutor::EVENT_NAME);
+
+    // The event data is the job ID, which the Wolfram Language handler passes to
+    // wll_run_main_thread_job.
+    let nodes: Vec<_> = events[0].data.nodes().collect();
+    let id = match nodes[..] {
+        [ref node] => match node.value() {
+            DataStoreNodeValue::Integer(id) => id,
+            _ => panic!("expected integer job ID"),
+        },
+        _ => panic!("expected one node"),
+    };
+
+    let result = unsafe { testing::call(wll_run_main_thread_job, &[Arg::integer(id)]) };
+
+    assert!(result.is_ok());
+    assert_eq!(worker.join().unwrap(), Some(42));
+
+    assert!(task.remove());
+}
+
+#[test]
+fn test_main_thread_executor_removed_task() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::new_without_thread();
+    let executor = MainThreadExecutor::new(&task);
+
+    let worker = std::thread::spawn(move || executor.run(|_| 42));
+
+    // Wait for the worker to submit its job.
+    let events = loop {
+        let events = testing::take_async_events(&task);
+
+        if !events.is_empty() {
+            break events;
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    };
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name, MainThreadExecutor::EVENT_NAME);
+
+    // The event data is the job ID.
+    let nodes: Vec<_> = events[0].data.nodes().collect();
+    assert_eq!(nodes.len(), 1);
+    assert!(matches!(nodes[0].value(), DataStoreNodeValue::Integer(_)));
+
+    assert!(task.remove());
+
+    // The job is never run, because the task was removed before the Wolfram Language
+    // handler could run it.
+    assert_eq!(worker.join().unwrap(), None);
+}
+
+#[test]
+fn test_async_task_without_thread() {
+    testing::initialize();
+
+    let task = AsyncTaskObject::new_without_thread();
+    let id = task.id();
+
+    assert!(task.is_alive());
+
+    // Raise events from a thread that is not owned by the Wolfram runtime.
+    let names: Vec<String> = std::thread::spawn(move || {
+        task.raise_async_event("done", DataStore::new());
+
+        let events = testing::take_async_events(&task);
+
+        // Dropping the handle removes the task.
+        drop(task);
+
+        events.into_iter().map(|event| event.name).collect()
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(names, vec!["done".to_owned()]);
+    assert_eq!(unsafe { wll::rtl::asynchronousTaskAliveQ(id) }, 0);
+
+    // Explicitly removing a task reports whether it was still alive.
+    let task = AsyncTaskObject::new_without_thread();
+    assert!(task.remove());
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn test_async_task_stream() {
+    use futures::StreamExt;
+
+    testing::initialize();
+
+    let stream = futures::stream::iter(0..3).map(|i| {
+        let mut data = DataStore::new();
+        data.add_i64(i);
+        data
+    });
+
+    let task = AsyncTaskObject::spawn_stream(stream);
+
+    // The task is removed after its final event is raised.
+    while task.is_alive() {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    let events = testing::take_async_events(&task);
+    let names: Vec<&str> = events.iter().map(|event| event.name.as_str()).collect();
+
+    assert_eq!(names, vec!["data", "data", "data", "done"]);
+
+    let task = AsyncTaskObject::spawn_future(async { panic!("future failed") });
+
+    while task.is_alive() {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    let events = testing::take_async_events(&task);
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name, "error");
+
+    // The "error" event has the same data as a "RustPanic" event.
+    let nodes: Vec<_> = events[0].data.nodes().collect();
+
+    assert_eq!(nodes[0].name().as_deref(), Some("message"));
+    assert!(matches!(
+        nodes[0].value(),
+        DataStoreNodeValue::Str("future failed")
+    ));
+
+    assert_eq!(nodes[1].name().as_deref(), Some("location"));
+    assert!(matches!(
+        nodes[1].value(),
+        DataStoreNodeValue::Str(location) if location.contains("mock_runtime.rs")
+    ));
+
+    // The stream is dropped without being polled again once the task is removed.
+    let dropped = Arc::new(());
+    let captured = Arc::clone(&dropped);
+
+    let stream = futures::stream::repeat_with(move || {
+        let _ = &captured;
+        std::thread::sleep(Duration::from_millis(1));
+        DataStore::new()
+    });
+
+    let task = AsyncTaskObject::spawn_stream(stream);
+
+    assert!(task.remove());
+
+    while Arc::strong_count(&dropped) > 1 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+//======================================
+// Managed expressions
+//======================================
+
+#[test]
+fn test_library_expression_managers() {
+    testing::initialize();
+
+    let events: Arc<Mutex<Vec<(usize, ManagedExpressionEvent)>>> = Default::default();
+
+    // Register more managers than the previous fixed limit of 8.
+    for index in 0..12 {
+        let events = Arc::clone(&events);
+
+        managed::register_library_expression_manager(
+            &format!("test_manager_{}", index),
+            move |event| events.lock().unwrap().push((index, event)),
+        );
+    }
+
+    let id = testing::create_managed_instance("test_manager_10").unwrap();
+
+    managed::release_managed_library_expression("test_manager_10", id as u32).unwrap();
+
+    assert!(matches!(
+        events.lock().unwrap().as_slice(),
+        [
+            (10, ManagedExpressionEvent::Create(create)),
+            (10, ManagedExpressionEvent::Drop(drop)),
+        ] if i64::from(*create) == id && i64::from(*drop) == id
+    ));
+
+    // Unregistered managers don't receive events, and the name can be registered again.
+    managed::unregister_library_expression_manager("test_manager_3").unwrap();
+
+    assert_eq!(testing::create_managed_instance("test_manager_3"), None);
+    assert!(managed::unregister_library_expression_manager("test_manager_3").is_err());
+
+    managed::register_library_expression_manager("test_manager_3", |_| ());
+    assert!(testing::create_managed_instance("test_manager_3").is_some());
+}
+
+unsafe extern "C" fn ignore_managed_event(
+    _: sys::WolframLibraryData,
+    _: sys::mbool,
+    _: sys::mint,
+) {
+}
+
+#[test]
+fn test_library_expression_manager_kernel_errors() {
+    testing::initialize();
+
+    // A manager registered with the Kernel without using the `managed` module.
+    let name = c"test_manager_kernel_only";
+    let err_code = unsafe {
+        wll::rtl::registerLibraryExpressionManager(
+            name.as_ptr(),
+            Some(ignore_managed_event),
+        )
+    };
+    assert_eq!(err_code, 0);
+
+    let panic = std::panic::catch_unwind(|| {
+        managed::register_library_expression_manager("test_manager_kernel_only", |_| ())
+    })
+    .unwrap_err();
+
+    let message = panic.downcast_ref::<String>().unwrap();
+    let expected = format!("error code {}", sys::LIBRARY_FUNCTION_ERROR);
+    assert!(message.contains(&expected), "{}", message);
+
+    // A manager the Kernel fails to unregister stays registered.
+    managed::register_library_expression_manager("test_manager_stale", |_| ());
+
+    let err_code = unsafe {
+        wll::rtl::unregisterLibraryExpressionManager(c"test_manager_stale".as_ptr())
+    };
+    assert_eq!(err_code, 0);
+
+    assert!(
+        managed::unregister_library_expression_manager("test_manager_stale").is_err()
+    );
+
+    let panic = std::panic::catch_unwind(|| {
+        managed::register_library_expression_manager("test_manager_stale", |_| ())
+    })
+    .unwrap_err();
+
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("has already been registered"),
+        "{}",
+        message
+    );
+}
+
+struct Counter {
+    count: i64,
+}
+
+static COUNTERS: ManagedStore<Counter> = ManagedStore::new();
+
+#[wll::export]
+fn increment_counter(mut counter: Managed<Counter>) -> i64 {
+    counter.count += 1;
+    counter.count
+}
+
+#[test]
+fn test_managed_store() {
+    testing::initialize();
+
+    COUNTERS.register("test_counter", |id| Counter {
+        count: i64::from(id) * 100,
+    });
+
+    let id = testing::create_managed_instance("test_counter").unwrap();
+
+    assert!(COUNTERS.contains(id as u32));
+
+    for expected in 1..=2 {
+        let result = unsafe {
+            testing::call(increment_counter::increment_counter, &[Arg::integer(id)])
+        };
+
+        assert_eq!(unsafe { result.unwrap().get::<i64>() }, id * 100 + expected);
+    }
+
+    managed::release_managed_library_expression("test_counter", id as u32).unwrap();
+
+    assert!(!COUNTERS.contains(id as u32));
+
+    // Unknown instance IDs are reported as a LibraryFunction::rusterr message.
+    let _ = testing::take_evaluations();
+
+    let result = unsafe {
+        testing::call(increment_counter::increment_counter, &[Arg::integer(id)])
+    };
+
+    assert_eq!(result.err(), Some(sys::LIBRARY_FUNCTION_ERROR as i32));
+
+    let evaluations = testing::take_evaluations();
+
+    assert_eq!(evaluations.len(), 1);
+    assert!(
+        evaluations[0].contains(&format!(
+            "no 'test_counter' managed expression instance with ID {}",
+            id
+        )),
+        "{}",
+        evaluations[0]
+    );
+}
+
+struct Gauge {
+    level: i64,
+}
+
+static GAUGES: ManagedStore<Gauge> = ManagedStore::new();
+
+#[wll::export]
+fn release_gauge(gauge: Managed<Gauge>) -> i64 {
+    // This would deadlock if the Drop event waited for the lock held by `gauge`.
+    managed::release_managed_library_expression("test_gauge", gauge.id()).unwrap();
+
+    gauge.level
+}
+
+#[wll::export]
+fn total_level(a: Managed<Gauge>, b: Managed<Gauge>) -> i64 {
+    a.level + b.level
+}
+
+#[test]
+fn test_managed_store_locked() {
+    testing::initialize();
+
+    GAUGES.register("test_gauge", |_| Gauge { level: 7 });
+
+    let id = testing::create_managed_instance("test_gauge").unwrap();
+
+    let result =
+        unsafe { testing::call(release_gauge::release_gauge, &[Arg::integer(id)]) };
+
+    assert_eq!(unsafe { result.unwrap().get::<i64>() }, 7);
+
+    // The Drop event failed, so the instance was not dropped.
+    assert!(GAUGES.contains(id as u32));
+    assert!(wll::last_panic()
+        .unwrap()
+        .to_string()
+        .contains("ManagedStore<mock_runtime::Gauge> is locked by a Managed"));
+
+    // Passing the same store twice is an error instead of a deadlock.
+    let _ = testing::take_evaluations();
+
+    let result = unsafe {
+        testing::call(total_level::total_level, &[
+            Arg::integer(id),
+            Arg::integer(id),
+        ])
+    };
+
+    assert_eq!(result.err(), Some(sys::LIBRARY_FUNCTION_ERROR as i32));
+
+    let evaluations = testing::take_evaluations();
+
+    assert_eq!(evaluations.len(), 1);
+    assert!(
+        evaluations[0].contains("ManagedStore is already locked"),
+        "{}",
+        evaluations[0]
+    );
+
+    managed::release_managed_library_expression("test_gauge", id as u32).unwrap();
+
+    assert!(!GAUGES.contains(id as u32));
+}
+
+struct Accumulator {
+    total: i64,
+}
+
+#[wll::class(name = "TestAccumulator")]
+impl Accumulator {
+    pub fn new(start: i64) -> Self {
+        Accumulator { total: start }
+    }
+
+    pub fn add(&mut self, value: i64) -> i64 {
+        self.total += value;
+        self.total
+    }
+
+    pub fn total(&self) -> i64 {
+        self.total
+    }
+}
+
+// The native functions exported by #[class] are not nameable from Rust code, so link
+// to them by their exported symbol names instead.
+extern "C" {
+    fn __wll_class_TestAccumulator__new(
+        lib: sys::WolframLibraryData,
+        argc: sys::mint,
+        args: *mut sys::MArgument,
+        res: sys::MArgument,
+    ) -> std::os::raw::c_int;
+    fn __wll_class_TestAccumulator__add(
+        lib: sys::WolframLibraryData,
+        argc: sys::mint,
+        args: *mut sys::MArgument,
+        res: sys::MArgument,
+    ) -> std::os::raw::c_int;
+    fn __wll_class_TestAccumulator__total(
+        lib: sys::WolframLibraryData,
+        argc: sys::mint,
+        args: *mut sys::MArgument,
+        res: sys::MArgument,
+    ) -> std::os::raw::c_int;
+}
+
+#[test]
+fn test_class() {
+    testing::initialize();
+
+    let functions = wll::exported_library_functions_association(Some("libtest".into()));
+    let functions = functions.to_string();
+
+    assert!(functions.contains("\"TestAccumulator\""));
+    assert!(functions.contains("\"__wll_class_TestAccumulator__add\""));
+    assert!(functions.contains("\"__wll_class_TestAccumulator__total\""));
+    assert!(functions.contains(
+        "System`CreateManagedLibraryExpression[\"TestAccumulator\", Global`TestAccumulator]"
+    ));
+    // The constructor returns the failure of `new` instead of the instance.
+    assert!(functions.contains(
+        "System`If[System`MatchQ[RustLink`Private`result, System`Alternatives[System`Blank[System`LibraryFunctionError], System`Blank[System`Failure]]], RustLink`Private`result, RustLink`Private`obj]"
+    ));
+
+    let id = testing::create_managed_instance("TestAccumulator").unwrap();
+
+    unsafe {
+        testing::call(__wll_class_TestAccumulator__new, &[
+            Arg::integer(id),
+            Arg::integer(10),
+        ])
+        .unwrap();
+
+        let result = testing::call(__wll_class_TestAccumulator__add, &[
+            Arg::integer(id),
+            Arg::integer(5),
+        ]);
+        assert_eq!(result.unwrap().get::<i64>(), 15);
+
+        let result =
+            testing::call(__wll_class_TestAccumulator__total, &[Arg::integer(id)]);
+        assert_eq!(result.unwrap().get::<i64>(), 15);
+    }
+
+    managed::release_managed_library_expression("TestAccumulator", id as u32).unwrap();
+
+    let result =
+        unsafe { testing::call(__wll_class_TestAccumulator__total, &[Arg::integer(id)]) };
+    assert!(result.is_err());
+}
+
+//======================================
+// Callback functions
+//======================================
+
+type Objective = CallbackFunction<fn(f64, f64) -> f64>;
+
+static OBJECTIVE: Mutex<Option<Objective>> = Mutex::new(None);
+
+fn connect_objective(connection: CallbackConnection) -> bool {
+    match CallbackFunction::new(connection) {
+        Ok(function) => {
+            *OBJECTIVE.lock().unwrap() = Some(function);
+            true
+        },
+        Err(_) => false,
+    }
+}
+
+type Norm = CallbackFunction<fn(Tensor<f64>) -> f64>;
+
+static NORM: Mutex<Option<Norm>> = Mutex::new(None);
+
+fn connect_norm(connection: CallbackConnection) -> bool {
+    match CallbackFunction::new(connection) {
+        Ok(function) => {
+            *NORM.lock().unwrap() = Some(function);
+            true
+        },
+        Err(_) => false,
+    }
+}
+
+const REAL: (i64, i64) = (sys::MType_Real as i64, 0);
+
+#[test]
+fn test_callback_function() {
+    testing::initialize();
+
+    callback::register_library_callback_manager("test_objective", connect_objective);
+
+    // A compiled function with the wrong signature is rejected.
+    assert_eq!(
+        testing::connect_callback_function("test_objective", &[REAL, REAL], |_, _| ()),
+        None
+    );
+    assert!(OBJECTIVE.lock().unwrap().is_none());
+
+    let id = testing::connect_callback_function(
+        "test_objective",
+        &[REAL, REAL, REAL],
+        |args, res| unsafe { *res.real = *args[0].real - *args[1].real },
+    )
+    .unwrap();
+
+    let objective = OBJECTIVE.lock().unwrap().take().unwrap();
+
+    assert_eq!(objective.id(), id);
+    assert_eq!(objective.call(5.0, 1.5), Ok(3.5));
+
+    // Dropping the handle releases the connected function.
+    assert!(testing::is_callback_function_connected(id));
+    drop(objective);
+    assert!(!testing::is_callback_function_connected(id));
+}
+
+#[test]
+fn test_callback_function_tensor_arg() {
+    testing::initialize();
+
+    callback::register_library_callback_manager("test_norm", connect_norm);
+
+    testing::connect_callback_function(
+        "test_norm",
+        &[(sys::MType_Real as i64, 1), REAL],
+        |args, res| unsafe {
+            let tensor: &Tensor<f64> = <&Tensor<f64> as wll::FromArg>::from_arg(&args[0]);
+            *res.real = tensor.as_slice().iter().map(|x| x * x).sum::<f64>().sqrt();
+        },
+    )
+    .unwrap();
+
+    let norm = NORM.lock().unwrap();
+    let norm = norm.as_ref().unwrap();
+
+    assert_eq!(norm.call(Tensor::from_slice(&[3.0, 4.0])), Ok(5.0));
+}
+
+#[test]
+fn test_many_callback_managers() {
+    testing::initialize();
+
+    // Register more managers than the previous fixed limit of 8.
+    for index in 0..12 {
+        callback::register_library_callback_manager(
+            &format!("test_callback_manager_{}", index),
+            |connection| connection.parameter_types().is_empty(),
+        );
+    }
+
+    let id = testing::connect_callback_function(
+        "test_callback_manager_10",
+        &[REAL],
+        |_, _| (),
+    );
+
+    assert!(id.is_some());
+}
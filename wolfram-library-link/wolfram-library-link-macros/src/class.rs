@@ -0,0 +1,350 @@
+use proc_macro2::{Span, TokenStream as TokenStream2};
+
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Error, FnArg, Ident, ImplItem, ItemImpl, Meta, NestedMeta};
+
+use crate::export::export_native_function;
+
+//======================================
+// #[wolfram_library_link::class]
+//======================================
+
+/// A constructor or method of the class.
+struct ClassFunction<'i> {
+    /// Name of the method, as it appears in the Rust `impl` block and in the Wolfram
+    /// Language method call.
+    name: &'i Ident,
+    /// Name of the wrapper function in the generated class module, which is also the
+    /// name of the `#[no_mangle]` native function: `__wll_class_{Class}__{method}`.
+    ///
+    /// The reserved prefix prevents collisions with the names of `#[export]` functions,
+    /// and the double underscore separates the class name from the method name, so
+    /// that e.g. `A_b::c` and `A::b_c` don't collide.
+    exported_name: Ident,
+    /// Types of the method parameters, not including the receiver.
+    param_tys: Vec<&'i syn::Type>,
+    /// Whether the method takes `&mut self`.
+    mutable: bool,
+    /// Return type of the method.
+    output: &'i syn::ReturnType,
+}
+
+pub(crate) fn class(
+    attrs: syn::AttributeArgs,
+    item: ItemImpl,
+) -> Result<TokenStream2, Error> {
+    if !cfg!(feature = "automate-function-loading-boilerplate") {
+        return Err(Error::new(
+            Span::call_site(),
+            "#[class] requires the `automate-function-loading-boilerplate` feature of wolfram-library-link",
+        ));
+    }
+
+    //--------------------------------------------------
+    // Validate that this attribute was applied to an
+    // inherent, non-generic `impl Type { .. }` block.
+    //--------------------------------------------------
+
+    if let Some((_, ref trait_, _)) = item.trait_ {
+        return Err(Error::new(
+            trait_.span(),
+            "#[class] cannot be applied to trait `impl` blocks",
+        ));
+    }
+
+    if let Some(lt) = item.generics.lt_token {
+        return Err(Error::new(lt.span(), "exported class cannot be generic"));
+    }
+
+    let self_ty: &syn::Type = &item.self_ty;
+
+    let type_name: &Ident = match self_ty {
+        syn::Type::Path(path) if path.qself.is_none() => {
+            &path.path.segments.last().expect("empty type path").ident
+        },
+        _ => {
+            return Err(Error::new(
+                self_ty.span(),
+                "#[class] can only be applied to `impl` blocks of named types",
+            ))
+        },
+    };
+
+    //--------------------------------
+    // Parse the attribute arguments.
+    //--------------------------------
+
+    let symbol: String = match parse_class_attribute_args(attrs)? {
+        Some(symbol) => symbol,
+        None => type_name.to_string(),
+    };
+
+    // The class name is the symbol name without its context.
+    let class_name: &str = symbol.rsplit('`').next().unwrap_or(&symbol);
+
+    let class_ident: Ident = syn::parse_str(class_name).map_err(|_| {
+        Error::new(
+            Span::call_site(),
+            format!("class name `{}` must be a valid identifier", class_name),
+        )
+    })?;
+
+    let symbol: String = if symbol.contains('`') {
+        symbol.clone()
+    } else {
+        format!("Global`{}", symbol)
+    };
+
+    //--------------------------------------------------
+    // Collect the constructor and the exported methods.
+    //--------------------------------------------------
+
+    let mut constructor: Option<ClassFunction> = None;
+    let mut methods: Vec<ClassFunction> = Vec::new();
+
+    for impl_item in &item.items {
+        let method = match impl_item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+
+        if !matches!(method.vis, syn::Visibility::Public(_)) {
+            continue;
+        }
+
+        let sig = &method.sig;
+
+        if let Some(async_) = sig.asyncness {
+            return Err(Error::new(
+                async_.span(),
+                "exported method cannot be `async`",
+            ));
+        }
+
+        if let Some(lt) = sig.generics.lt_token {
+            return Err(Error::new(lt.span(), "exported method cannot be generic"));
+        }
+
+        let param_tys: Vec<&syn::Type> = sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(pat_type) => Some(&*pat_type.ty),
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+
+        let mut function = ClassFunction {
+            name: &sig.ident,
+            exported_name: format_ident!("__wll_class_{}__{}", class_ident, sig.ident),
+            param_tys,
+            mutable: false,
+            output: &sig.output,
+        };
+
+        match sig.receiver() {
+            Some(FnArg::Receiver(receiver)) => {
+                if receiver.reference.is_none() {
+                    return Err(Error::new(
+                        receiver.span(),
+                        "exported method must take `&self` or `&mut self`",
+                    ));
+                }
+
+                function.mutable = receiver.mutability.is_some();
+                methods.push(function);
+            },
+            Some(FnArg::Typed(pat_type)) => {
+                return Err(Error::new(
+                    pat_type.span(),
+                    "exported method must take `&self` or `&mut self`",
+                ))
+            },
+            // Associated functions other than the constructor are not exported.
+            None if sig.ident == "new" => constructor = Some(function),
+            None => (),
+        }
+    }
+
+    let constructor: ClassFunction = match constructor {
+        Some(constructor) => constructor,
+        None => {
+            return Err(Error::new(
+                item.impl_token.span(),
+                "exported class must have a `pub fn new(..) -> Self` constructor",
+            ))
+        },
+    };
+
+    //---------------------------
+    // Generate the class module.
+    //---------------------------
+
+    let module = format_ident!("__wll_class_{}", class_ident);
+
+    let constructor_wrapper = {
+        let ClassFunction {
+            name,
+            exported_name,
+            param_tys,
+            ..
+        } = &constructor;
+        let args = argument_idents(param_tys.len());
+
+        quote! {
+            pub(super) fn #exported_name(
+                id: ::wolfram_library_link::sys::mint,
+                #(#args: #param_tys),*
+            ) {
+                register();
+
+                let id = ::std::convert::TryFrom::try_from(id)
+                    .expect("managed expression ID overflows u32");
+
+                STORE.insert(id, <#self_ty>::#name(#(#args),*));
+            }
+        }
+    };
+
+    let method_wrappers = methods.iter().map(|method| {
+        let ClassFunction {
+            name,
+            exported_name,
+            param_tys,
+            mutable,
+            output,
+        } = method;
+        let args = argument_idents(param_tys.len());
+        let mut_ = if *mutable {
+            quote! { mut }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            pub(super) fn #exported_name(
+                #mut_ instance: ::wolfram_library_link::managed::Managed<#self_ty>,
+                #(#args: #param_tys),*
+            ) #output {
+                instance.#name(#(#args),*)
+            }
+        }
+    });
+
+    // Every wrapper takes the managed expression ID as an additional first argument.
+    let native_functions =
+        std::iter::once(&constructor)
+            .chain(methods.iter())
+            .map(|function| {
+                export_native_function(
+                    &function.exported_name,
+                    &function.exported_name,
+                    function.param_tys.len() + 1,
+                    true,
+                    false,
+                )
+            });
+
+    let class_function = |function: &ClassFunction| {
+        let ClassFunction {
+            name,
+            exported_name,
+            param_tys,
+            ..
+        } = function;
+        let params = vec![quote! { _ }; param_tys.len() + 1];
+
+        quote! {
+            ::wolfram_library_link::macro_utils::ClassFunction {
+                method: stringify!(#name),
+                exported_name: stringify!(#exported_name),
+                signature: || {
+                    let func: fn(#(#params),*) -> _ = #module::#exported_name;
+                    let func: &dyn ::wolfram_library_link::NativeFunction<'_> = &func;
+
+                    func.signature()
+                },
+            }
+        }
+    };
+
+    let constructor_info = class_function(&constructor);
+    let method_infos = methods.iter().map(class_function);
+
+    let output = quote! {
+        // Include the user's impl block in the output unchanged.
+        #item
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #module {
+            use super::*;
+
+            pub(super) static STORE: ::wolfram_library_link::managed::ManagedStore<#self_ty> =
+                ::wolfram_library_link::managed::ManagedStore::new();
+
+            pub(super) fn register() {
+                static REGISTER: ::std::sync::Once = ::std::sync::Once::new();
+
+                REGISTER.call_once(|| STORE.register_deferred(#class_name));
+            }
+
+            #constructor_wrapper
+
+            #(#method_wrappers)*
+
+            #(#native_functions)*
+        }
+
+        // Register this exported class.
+        ::wolfram_library_link::inventory::submit! {
+            ::wolfram_library_link::macro_utils::LibraryLinkFunction::Class {
+                name: #class_name,
+                symbol: #symbol,
+                register: #module::register,
+                constructor: #constructor_info,
+                methods: &[#(#method_infos),*],
+            }
+        }
+    };
+
+    Ok(output)
+}
+
+fn argument_idents(count: usize) -> Vec<Ident> {
+    (0..count).map(|i| format_ident!("arg{}", i)).collect()
+}
+
+fn parse_class_attribute_args(
+    attrs: syn::AttributeArgs,
+) -> Result<Option<String>, Error> {
+    let mut name: Option<String> = None;
+
+    for attr in attrs {
+        match attr {
+            NestedMeta::Meta(Meta::NameValue(syn::MetaNameValue {
+                path, lit, ..
+            })) if path.is_ident("name") => {
+                if name.is_some() {
+                    return Err(Error::new(
+                        path.span(),
+                        "duplicate definition for `name`",
+                    ));
+                }
+
+                match lit {
+                    syn::Lit::Str(str) => name = Some(str.value()),
+                    _ => return Err(Error::new(lit.span(), "expected `name = \"...\"`")),
+                }
+            },
+            _ => {
+                return Err(Error::new(
+                    attr.span(),
+                    "unrecognized class attribute argument",
+                ))
+            },
+        }
+    }
+
+    Ok(name)
+}
@@ -0,0 +1,211 @@
+use proc_macro2::TokenStream as TokenStream2;
+
+use quote::quote;
+use syn::{
+    ext::IdentExt, spanned::Spanned, Data, DataEnum, DeriveInput, Error, Fields, Ident,
+};
+
+//======================================
+// #[derive(FromExpr)]
+//======================================
+
+pub(crate) fn derive_from_expr(input: DeriveInput) -> Result<TokenStream2, Error> {
+    validate_generics(&input, "FromExpr")?;
+
+    let name = &input.ident;
+
+    let body = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let idents: Vec<&Ident> = fields
+                    .named
+                    .iter()
+                    .filter_map(|f| f.ident.as_ref())
+                    .collect();
+                let keys: Vec<String> =
+                    idents.iter().map(|i| i.unraw().to_string()).collect();
+
+                quote! {
+                    let rules = ::wolfram_library_link::macro_utils::association_rules(expr)?;
+
+                    Ok(#name {
+                        #(
+                            #idents: ::wolfram_library_link::macro_utils::association_field(
+                                &rules,
+                                #keys,
+                            )?,
+                        )*
+                    })
+                }
+            },
+            Fields::Unnamed(ref fields) => {
+                let len = fields.unnamed.len();
+                let indices = 0..len;
+
+                quote! {
+                    let elements =
+                        ::wolfram_library_link::macro_utils::list_elements(expr, #len)?;
+
+                    Ok(#name(
+                        #(
+                            ::wolfram_library_link::FromExpr::from_expr(&elements[#indices])
+                                .map_err(|err| err.context(format!("Part {}", #indices + 1)))?,
+                        )*
+                    ))
+                }
+            },
+            Fields::Unit => quote! {
+                let () = ::wolfram_library_link::FromExpr::from_expr(expr)?;
+
+                Ok(#name)
+            },
+        },
+        Data::Enum(ref data) => {
+            let variants = unit_variants(data, "FromExpr")?;
+            let names: Vec<String> =
+                variants.iter().map(|v| v.unraw().to_string()).collect();
+
+            let expected = format!(
+                "String naming a variant of {} ({})",
+                name,
+                names
+                    .iter()
+                    .map(|name| format!("{:?}", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+
+            quote! {
+                let variant: String = ::wolfram_library_link::FromExpr::from_expr(expr)
+                    .map_err(|_| {
+                        ::wolfram_library_link::FromExprError::expected(#expected, expr)
+                    })?;
+
+                match variant.as_str() {
+                    #( #names => Ok(#name::#variants), )*
+                    _ => Err(::wolfram_library_link::FromExprError::expected(#expected, expr)),
+                }
+            }
+        },
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "#[derive(FromExpr)] cannot be used with unions",
+            ))
+        },
+    };
+
+    Ok(quote! {
+        impl ::wolfram_library_link::FromExpr for #name {
+            fn from_expr(
+                expr: &::wolfram_library_link::expr::Expr,
+            ) -> ::std::result::Result<Self, ::wolfram_library_link::FromExprError> {
+                #body
+            }
+        }
+    })
+}
+
+//======================================
+// #[derive(ToExpr)]
+//======================================
+
+pub(crate) fn derive_to_expr(input: DeriveInput) -> Result<TokenStream2, Error> {
+    validate_generics(&input, "ToExpr")?;
+
+    let name = &input.ident;
+
+    let body = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let idents: Vec<&Ident> = fields
+                    .named
+                    .iter()
+                    .filter_map(|f| f.ident.as_ref())
+                    .collect();
+                let keys: Vec<String> =
+                    idents.iter().map(|i| i.unraw().to_string()).collect();
+
+                quote! {
+                    ::wolfram_library_link::macro_utils::association(vec![
+                        #( (#keys, ::wolfram_library_link::ToExpr::to_expr(&self.#idents)), )*
+                    ])
+                }
+            },
+            Fields::Unnamed(ref fields) => {
+                let indices = (0..fields.unnamed.len()).map(syn::Index::from);
+
+                quote! {
+                    ::wolfram_library_link::expr::Expr::list(vec![
+                        #( ::wolfram_library_link::ToExpr::to_expr(&self.#indices), )*
+                    ])
+                }
+            },
+            Fields::Unit => quote! {
+                ::wolfram_library_link::expr::Expr::null()
+            },
+        },
+        Data::Enum(ref data) => {
+            let variants = unit_variants(data, "ToExpr")?;
+            let names: Vec<String> =
+                variants.iter().map(|v| v.unraw().to_string()).collect();
+
+            quote! {
+                match *self {
+                    #( #name::#variants => ::wolfram_library_link::expr::Expr::string(#names), )*
+                }
+            }
+        },
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "#[derive(ToExpr)] cannot be used with unions",
+            ))
+        },
+    };
+
+    Ok(quote! {
+        impl ::wolfram_library_link::ToExpr for #name {
+            fn to_expr(&self) -> ::wolfram_library_link::expr::Expr {
+                #body
+            }
+        }
+    })
+}
+
+//======================================
+// Utilities
+//======================================
+
+fn validate_generics(input: &DeriveInput, trait_name: &str) -> Result<(), Error> {
+    match input.generics.lt_token {
+        Some(lt) => Err(Error::new(
+            lt.span(),
+            format!(
+                "#[derive({})] cannot be used with generic types",
+                trait_name
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Returns the names of the variants of `data`, which must all be unit variants.
+fn unit_variants<'d>(
+    data: &'d DataEnum,
+    trait_name: &str,
+) -> Result<Vec<&'d Ident>, Error> {
+    data.variants
+        .iter()
+        .map(|variant| match variant.fields {
+            Fields::Unit => Ok(&variant.ident),
+            _ => Err(Error::new(
+                variant.span(),
+                format!(
+                    "#[derive({})] only supports enums whose variants have no fields",
+                    trait_name
+                ),
+            )),
+        })
+        .collect()
+}
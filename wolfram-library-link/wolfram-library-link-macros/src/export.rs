@@ -93,6 +93,7 @@ pub(crate) fn export(
         use_wstp,
         exported_name,
         hidden,
+        abortable,
     } = parse_export_attribute_args(attrs)?;
 
     //--------------------------------------------------------------------
@@ -143,7 +144,7 @@ pub(crate) fn export(
     let wrapper = if use_wstp {
         export_wstp_function(&name, &exported_name, params, hidden)
     } else {
-        export_native_function(&name, &exported_name, params.len(), hidden)
+        export_native_function(&name, &exported_name, params.len(), hidden, abortable)
     };
 
     let output = quote! {
@@ -160,14 +161,21 @@ pub(crate) fn export(
 // #[export]: export NativeFunction
 //--------------------------------------
 
-fn export_native_function(
+pub(crate) fn export_native_function(
     name: &Ident,
     exported_name: &Ident,
     parameter_count: usize,
     hidden: bool,
+    abortable: bool,
 ) -> TokenStream2 {
     let params = vec![quote! { _ }; parameter_count];
 
+    let call_function = if abortable {
+        quote! { call_abortable_native_wolfram_library_function }
+    } else {
+        quote! { call_native_wolfram_library_function }
+    };
+
     let mut tokens = quote! {
         mod #name {
             #[no_mangle]
@@ -181,7 +189,7 @@ fn export_native_function(
                 // generic `fn(...)` type.
                 let func: fn(#(#params),*) -> _ = super::#name;
 
-                ::wolfram_library_link::macro_utils::call_native_wolfram_library_function(
+                ::wolfram_library_link::macro_utils::#call_function(
                     lib,
                     args,
                     argc,
@@ -223,9 +231,124 @@ fn export_wstp_function(
     parameter_tys: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
     hidden: bool,
 ) -> TokenStream2 {
-    // let params = vec![quote! { _ }; parameter_count];
+    let mut tokens = if is_untyped_wstp_function(&parameter_tys) {
+        export_untyped_wstp_function(name, exported_name, parameter_tys)
+    } else {
+        export_typed_wstp_function(name, exported_name, parameter_tys.len())
+    };
 
-    let mut tokens = quote! {
+    if !hidden && cfg!(feature = "automate-function-loading-boilerplate") {
+        tokens.extend(quote! {
+            // Register this exported function.
+            ::wolfram_library_link::inventory::submit! {
+                ::wolfram_library_link::macro_utils::LibraryLinkFunction::Wstp { name: stringify!(#exported_name) }
+            }
+        });
+    }
+
+    tokens
+}
+
+/// Returns `true` if the function parameters are `(&mut Link)` or `(Vec<Expr>)`, which
+/// are handled by the `WstpFunction` trait impls.
+///
+/// Functions with any other parameters are typed WSTP functions, whose arguments are
+/// decoded using `FromExpr`.
+fn is_untyped_wstp_function(
+    params: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+) -> bool {
+    let ty: &syn::Type = match params.first() {
+        Some(syn::FnArg::Typed(pat_type)) if params.len() == 1 => &pat_type.ty,
+        _ => return false,
+    };
+
+    match ty {
+        syn::Type::Reference(reference) => {
+            reference.mutability.is_some() && type_name_is(&reference.elem, "Link")
+        },
+        syn::Type::Path(path) => {
+            let last = match path.path.segments.last() {
+                Some(last) if last.ident == "Vec" => last,
+                _ => return false,
+            };
+
+            match last.arguments {
+                syn::PathArguments::AngleBracketed(ref args) => match args.args.first() {
+                    Some(syn::GenericArgument::Type(elem)) if args.args.len() == 1 => {
+                        type_name_is(elem, "Expr")
+                    },
+                    _ => false,
+                },
+                _ => false,
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Returns `true` if `ty` is a path whose last segment is `name`, e.g. `Link` or
+/// `wstp::Link`.
+fn type_name_is(ty: &syn::Type, name: &str) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+fn export_typed_wstp_function(
+    name: &Ident,
+    exported_name: &Ident,
+    parameter_count: usize,
+) -> TokenStream2 {
+    let indices = 0..parameter_count;
+
+    quote! {
+        mod #name {
+            #[no_mangle]
+            pub unsafe extern "C" fn #exported_name(
+                lib: ::wolfram_library_link::sys::WolframLibraryData,
+                raw_link: ::wolfram_library_link::wstp::sys::WSLINK,
+            ) -> std::os::raw::c_int {
+                ::wolfram_library_link::macro_utils::call_typed_wstp_wolfram_library_function(
+                    lib,
+                    raw_link,
+                    |args: Vec<::wolfram_library_link::expr::Expr>| {
+                        // Return a Failure[..] instead of the result if the arguments
+                        // are invalid.
+                        if let Err(failure) = ::wolfram_library_link::macro_utils::check_wstp_arg_count(
+                            &args,
+                            #parameter_count,
+                        ) {
+                            return failure;
+                        }
+
+                        let result = super::#name(
+                            #(
+                                match ::wolfram_library_link::macro_utils::wstp_arg(&args, #indices) {
+                                    Ok(arg) => arg,
+                                    Err(failure) => return failure,
+                                },
+                            )*
+                        );
+
+                        ::wolfram_library_link::ToExpr::to_expr(&result)
+                    }
+                )
+            }
+        }
+    }
+}
+
+fn export_untyped_wstp_function(
+    name: &Ident,
+    exported_name: &Ident,
+    parameter_tys: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+) -> TokenStream2 {
+    quote! {
         mod #name {
             // Ensure that types imported into the enclosing parent module can be used in
             // the expansion of $argc. Always `Link` or `Vec<Expr>` at the moment.
@@ -255,18 +378,7 @@ fn export_wstp_function(
             }
 
         }
-    };
-
-    if !hidden && cfg!(feature = "automate-function-loading-boilerplate") {
-        tokens.extend(quote! {
-            // Register this exported function.
-            ::wolfram_library_link::inventory::submit! {
-                ::wolfram_library_link::macro_utils::LibraryLinkFunction::Wstp { name: stringify!(#exported_name) }
-            }
-        });
     }
-
-    tokens
 }
 
 //======================================
@@ -284,11 +396,17 @@ struct ExportArgs {
     /// If set, this exported function will not have an automatic loader entry generated
     /// for it.
     hidden: bool,
+    /// `#[export(abortable)]`
+    ///
+    /// If set, this exported function will return `$Aborted` if an abort is in progress
+    /// when it returns.
+    abortable: bool,
 }
 
 fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs, Error> {
     let mut use_wstp = false;
     let mut hidden = false;
+    let mut abortable = false;
     let mut exported_name: Option<Ident> = None;
 
     for attr in attrs {
@@ -314,6 +432,16 @@ fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs,
 
                     hidden = true;
                 },
+                Meta::Path(path) if path.is_ident("abortable") => {
+                    if abortable {
+                        return Err(Error::new(
+                            attr.span(),
+                            "duplicate export `abortable` attribute argument",
+                        ));
+                    }
+
+                    abortable = true;
+                },
                 Meta::List(_) | Meta::Path(_) => {
                     return Err(Error::new(
                         attr.span(),
@@ -366,9 +494,17 @@ fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs,
         }
     }
 
+    if use_wstp && abortable {
+        return Err(Error::new(
+            proc_macro2::Span::call_site(),
+            "export `abortable` attribute argument is not supported for `wstp` functions",
+        ));
+    }
+
     Ok(ExportArgs {
         use_wstp,
         exported_name,
         hidden,
+        abortable,
     })
 }
@@ -1,3 +1,5 @@
+mod class;
+mod derive;
 mod export;
 
 
@@ -111,3 +113,45 @@ pub fn export(
         Err(err) => err.into_compile_error().into(),
     }
 }
+
+//======================================
+// #[wolfram_library_link::class]
+//======================================
+
+#[proc_macro_attribute]
+pub fn class(
+    attrs: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let attrs: syn::AttributeArgs = syn::parse_macro_input!(attrs);
+    let item: syn::ItemImpl = syn::parse_macro_input!(item);
+
+    match self::class::class(attrs, item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+//======================================
+// #[derive(FromExpr)] and #[derive(ToExpr)]
+//======================================
+
+#[proc_macro_derive(FromExpr)]
+pub fn derive_from_expr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    match self::derive::derive_from_expr(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(ToExpr)]
+pub fn derive_to_expr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    match self::derive::derive_to_expr(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}